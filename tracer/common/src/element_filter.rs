@@ -0,0 +1,136 @@
+use std::{convert::Infallible, str::FromStr};
+
+/// Selects elements by name with comma-separated wildcard patterns, like `GST_DEBUG` categories.
+///
+/// `*` matches any run of characters and `?` a single one; a pattern prefixed with `!` excludes
+/// the names it matches. Patterns are applied in order and the last one matching a name decides,
+/// so `video*,!*sink*` selects `videotestsrc0` and `videoconvert0` but not `videosink0`. Names
+/// matching no pattern are selected only if every pattern is an exclusion, which makes an empty
+/// filter select everything and `!queue*` everything but queues.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Filter {
+    patterns: Vec<Pattern>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pattern {
+    glob: Vec<char>,
+    exclude: bool,
+}
+
+impl Filter {
+    /// Parses a spec like `video*,!*sink*`, ignoring empty patterns.
+    pub fn new(spec: &str) -> Self {
+        let patterns = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|pattern| !pattern.is_empty())
+            .map(|pattern| match pattern.strip_prefix('!') {
+                Some(glob) => Pattern {
+                    glob: glob.chars().collect(),
+                    exclude: true,
+                },
+                None => Pattern {
+                    glob: pattern.chars().collect(),
+                    exclude: false,
+                },
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    /// Whether the filter selects everything.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether `name` is selected.
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.chars().collect::<Vec<_>>();
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| glob_matches(&pattern.glob, &name))
+            .map_or_else(
+                || self.patterns.iter().all(|pattern| pattern.exclude),
+                |pattern| !pattern.exclude,
+            )
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Infallible;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(spec))
+    }
+}
+
+/// Wildcard match, backtracking to the last `*` on a mismatch.
+fn glob_matches(glob: &[char], name: &[char]) -> bool {
+    let (mut g, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    g = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Filter;
+
+    #[test]
+    fn last_matching_pattern_decides() {
+        let filter = Filter::new("video*,!*sink*");
+        assert!(filter.matches("videotestsrc0"));
+        assert!(filter.matches("videoconvert0"));
+        assert!(!filter.matches("videosink0"));
+        assert!(!filter.matches("queue0"));
+        // Without a trailing `*`, names must end with the pattern
+        assert!(Filter::new("video*,!*sink").matches("videosink0"));
+
+        let filter = Filter::new("!*sink, fakesink");
+        assert!(filter.matches("fakesink"));
+        assert!(!filter.matches("autovideosink"));
+        // With an include pattern, names matching nothing are not selected
+        assert!(!filter.matches("queue0"));
+        assert!(Filter::new("!queue*").matches("identity0"));
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter: Filter = " , ".parse().unwrap();
+        assert!(filter.is_empty());
+        assert!(filter.matches("anything"));
+    }
+
+    #[test]
+    fn wildcards_match_runs_and_single_characters() {
+        let filter = Filter::new("q*e?,*x*y");
+        assert!(filter.matches("queue0"));
+        assert!(filter.matches("qe1"));
+        assert!(!filter.matches("queue"));
+        assert!(filter.matches("axbxcy"));
+        assert!(!filter.matches("axbyc"));
+        assert!(Filter::new("*").matches(""));
+        assert!(!Filter::new("?").matches(""));
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    mem::MaybeUninit,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Mutex, Once,
+    },
+};
+
+type Callback = Box<dyn Fn() + Send>;
+
+/// Run, in order, once `SIGTERM` is received.
+static CALLBACKS: Mutex<Vec<Callback>> = Mutex::new(Vec::new());
+static INSTALL: Once = Once::new();
+/// Write end of the pipe through which the signal handler wakes the flush thread.
+static WAKE_FD: AtomicI32 = AtomicI32::new(-1);
+/// The `SIGTERM` disposition replaced by ours, restored once flushed.
+static PREVIOUS: Mutex<Option<libc::sigaction>> = Mutex::new(None);
+
+/// Runs `callback` when the process receives `SIGTERM`, e.g. to flush telemetry before a
+/// container is killed.
+///
+/// The first call installs a handler that only wakes a `sigterm-flush` thread, since nothing
+/// that allocates or locks may run in a signal handler. That thread runs the callbacks, restores
+/// the disposition `SIGTERM` had before, such as the application's own handler or the default of
+/// terminating, and raises the signal again, so the application sees it as if no handler was
+/// installed. Callbacks must bound how long they block, as the orchestrator's grace period runs.
+///
+/// A handler the application installs afterwards replaces this one.
+pub fn on_sigterm(callback: impl Fn() + Send + 'static) {
+    CALLBACKS.lock().unwrap().push(Box::new(callback));
+    INSTALL.call_once(install);
+}
+
+fn install() {
+    let mut fds = [-1; 2];
+    // SAFETY: `fds` has room for the two descriptors `pipe2` writes.
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return;
+    }
+    let [read_fd, write_fd] = fds;
+    WAKE_FD.store(write_fd, Ordering::Relaxed);
+
+    // SAFETY: a zeroed `sigaction` is valid, and the handler only calls `write`, which is
+    // async-signal-safe.
+    unsafe {
+        let mut action: libc::sigaction = MaybeUninit::zeroed().assume_init();
+        action.sa_sigaction = handle_sigterm as *const () as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        let mut previous: libc::sigaction = MaybeUninit::zeroed().assume_init();
+        if libc::sigaction(libc::SIGTERM, &action, &mut previous) != 0 {
+            return;
+        }
+        *PREVIOUS.lock().unwrap() = Some(previous);
+    }
+
+    // Started after the handler is installed; a signal received meanwhile waits in the pipe
+    let _ = std::thread::Builder::new()
+        .name("sigterm-flush".into())
+        .spawn(move || {
+            let mut byte = 0u8;
+            loop {
+                // SAFETY: `byte` is valid for a one byte read.
+                let read = unsafe { libc::read(read_fd, &mut byte as *mut u8 as *mut _, 1) };
+                if read == 1 {
+                    break;
+                }
+                if read < 0
+                    && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted
+                {
+                    continue;
+                }
+                return;
+            }
+
+            for callback in CALLBACKS.lock().unwrap().iter() {
+                callback();
+            }
+
+            if let Some(previous) = PREVIOUS.lock().unwrap().take() {
+                // SAFETY: `previous` was returned by `sigaction` for this signal.
+                unsafe {
+                    libc::sigaction(libc::SIGTERM, &previous, std::ptr::null_mut());
+                    libc::raise(libc::SIGTERM);
+                }
+            }
+        });
+}
+
+extern "C" fn handle_sigterm(_signal: libc::c_int) {
+    let fd = WAKE_FD.load(Ordering::Relaxed);
+    let byte = 1u8;
+    // SAFETY: `write` is async-signal-safe; a full pipe means the thread is already woken.
+    unsafe { libc::write(fd, &byte as *const u8 as *const _, 1) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::on_sigterm;
+    use std::{sync::mpsc, time::Duration};
+
+    #[test]
+    fn callbacks_run_on_sigterm_before_the_previous_disposition() {
+        // Ignored before, so the signal raised again once flushed does not end the test
+        unsafe { libc::signal(libc::SIGTERM, libc::SIG_IGN) };
+        let (flushed_tx, flushed_rx) = mpsc::channel();
+        on_sigterm(move || {
+            let _ = flushed_tx.send(());
+        });
+
+        unsafe { libc::raise(libc::SIGTERM) };
+        flushed_rx
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Expected the callback to run on SIGTERM");
+    }
+}
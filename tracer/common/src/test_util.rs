@@ -0,0 +1,91 @@
+//! Fixtures for the tracers' integration tests. Each test file is its own
+//! binary so that it can set its own `GST_TRACERS` before initializing
+//! GStreamer.
+use gst::prelude::*;
+use gstreamer as gst;
+use std::{
+    env::{self, consts::ARCH},
+    path::Path,
+};
+
+/// Initializes GStreamer with `tracers` as `GST_TRACERS`, loading the
+/// plugins from the workspace's debug build.
+pub fn setup_tracers(tracers: &str) {
+    env::set_var("GST_TRACERS", tracers);
+    env::set_var(
+        "GST_DEBUG",
+        "GST_TRACER:5,prom-latency:7,otel-tracer:7,observability:7",
+    );
+    env::set_var("GST_PLUGIN_PATH", plugin_path());
+
+    // Initialize GStreamer
+    gst::init().expect("Failed to initialize GStreamer");
+}
+
+/// The workspace's debug target directories, with and without a target triple.
+pub fn plugin_path() -> String {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let root_manifest_dir = manifest_dir.parent().unwrap().parent().unwrap();
+    let plugin_targets = [("debug", true), ("debug", false)];
+    let plugin_paths = plugin_targets.iter().map(|(profile, with_target)| {
+        let base = root_manifest_dir.join(format!("target/{}", profile));
+        if *with_target {
+            base.join(format!("{ARCH}-unknown-linux-gnu"))
+                .to_str()
+                .unwrap()
+                .to_owned()
+        } else {
+            base.to_str().unwrap().to_owned()
+        }
+    });
+    plugin_paths.collect::<Vec<_>>().join(":")
+}
+
+/// Plays `pipeline` until EOS, then shuts it down.
+///
+/// Panics on an error message, so a broken pipeline fails the test rather
+/// than leaving it to assert on whatever was recorded before the error.
+pub fn run_to_eos(pipeline: &impl IsA<gst::Element>) {
+    pipeline
+        .set_state(gst::State::Playing)
+        .expect("Unable to set the pipeline to Playing");
+    wait_for_eos(pipeline);
+    pipeline.set_state(gst::State::Null).unwrap();
+}
+
+/// Blocks until EOS is posted on the bus of `pipeline`, panicking on an error
+/// message.
+pub fn wait_for_eos(pipeline: &impl IsA<gst::Element>) {
+    let bus = pipeline.bus().unwrap();
+    for msg in bus.iter_timed(gst::ClockTime::NONE) {
+        use gst::MessageView;
+        match msg.view() {
+            MessageView::Eos(..) => break,
+            MessageView::Error(err) => panic!(
+                "Error from {:?}: {} ({:?})",
+                err.src().map(|s| s.path_string()),
+                err.error(),
+                err.debug()
+            ),
+            _ => (),
+        }
+    }
+}
+
+/// The active tracer of type `type_name`, e.g. `promlatencytracer`.
+pub fn tracer(type_name: &str) -> gst::Tracer {
+    gst::active_tracers()
+        .into_iter()
+        .find(|t| t.type_().name() == type_name)
+        .unwrap_or_else(|| panic!("Expected an active {type_name}"))
+}
+
+/// The value of the first series starting with `metric_prefix` in a
+/// Prometheus exposition.
+pub fn metric_value(metrics: &str, metric_prefix: &str) -> Option<f64> {
+    metrics
+        .lines()
+        .find(|line| line.starts_with(metric_prefix))
+        .and_then(|line| line.split_whitespace().last())
+        .and_then(|value| value.parse::<f64>().ok())
+}
@@ -0,0 +1,126 @@
+/// What to measure, from the `flags` parameter of GStreamer's `latency` tracer.
+///
+/// Flags are joined with `+`, e.g. `pipeline+element`, as upstream parses them as `GFlags`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyFlags {
+    /// From a source element to a sink element.
+    pub pipeline: bool,
+    /// Across each element.
+    pub element: bool,
+    /// The latency each element reports in latency queries.
+    pub reported: bool,
+}
+
+impl LatencyFlags {
+    /// Parses a spec like `pipeline+element`, failing with the first unknown flag.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut flags = Self::default();
+        for flag in spec.split(['+', '|']).map(str::trim) {
+            match flag {
+                "pipeline" => flags.pipeline = true,
+                "element" => flags.element = true,
+                "reported" => flags.reported = true,
+                "" => {}
+                unknown => return Err(unknown.to_string()),
+            }
+        }
+        Ok(flags)
+    }
+}
+
+/// The objects whose hooks a tracer registers, from the `filters` parameter GStreamer's
+/// `leaks` and `stats` tracers take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TracedTypes {
+    /// `GstBuffer`: buffer pushes and pulls.
+    pub buffers: bool,
+    /// `GstBufferList`: buffer list pushes.
+    pub buffer_lists: bool,
+    /// `GstEvent`: events pushed downstream.
+    pub events: bool,
+}
+
+impl Default for TracedTypes {
+    fn default() -> Self {
+        Self {
+            buffers: true,
+            buffer_lists: true,
+            events: true,
+        }
+    }
+}
+
+impl TracedTypes {
+    /// Parses comma-separated type names like `GstBuffer,GstEvent`, failing with the first
+    /// unknown one. An empty spec traces everything.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let names = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>();
+        if names.is_empty() {
+            return Ok(Self::default());
+        }
+        let mut types = Self {
+            buffers: false,
+            buffer_lists: false,
+            events: false,
+        };
+        for name in names {
+            match name {
+                "GstBuffer" => types.buffers = true,
+                "GstBufferList" => types.buffer_lists = true,
+                "GstEvent" => types.events = true,
+                unknown => return Err(unknown.to_string()),
+            }
+        }
+        Ok(types)
+    }
+
+    /// The objects traced by either `self` or `other`.
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            buffers: self.buffers || other.buffers,
+            buffer_lists: self.buffer_lists || other.buffer_lists,
+            events: self.events || other.events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LatencyFlags, TracedTypes};
+
+    #[test]
+    fn flags_are_joined_with_plus() {
+        assert_eq!(
+            LatencyFlags::parse("pipeline+element"),
+            Ok(LatencyFlags {
+                pipeline: true,
+                element: true,
+                reported: false,
+            })
+        );
+        assert!(LatencyFlags::parse("element").unwrap().element);
+        assert_eq!(LatencyFlags::parse("element+bogus"), Err("bogus".into()));
+    }
+
+    #[test]
+    fn filters_select_types_unless_empty() {
+        let types = TracedTypes::parse("GstBuffer, GstEvent").unwrap();
+        assert!(types.buffers && types.events);
+        assert!(!types.buffer_lists);
+        assert_eq!(TracedTypes::parse(""), Ok(TracedTypes::default()));
+        assert_eq!(TracedTypes::parse("GstQuery"), Err("GstQuery".into()));
+    }
+
+    #[test]
+    fn union_traces_types_of_either() {
+        let buffers = TracedTypes::parse("GstBuffer").unwrap();
+        let events = TracedTypes::parse("GstEvent").unwrap();
+        let types = buffers.union(events);
+        assert!(types.buffers && types.events);
+        assert!(!types.buffer_lists);
+    }
+}
@@ -20,7 +20,8 @@ use gst::ffi;
 use gst::prelude::*;
 use gst::subclass::prelude::*;
 use gstreamer as gst;
-use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, OnceLock};
 static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
     gst::DebugCategory::new(
         "noop-latency",
@@ -34,8 +35,43 @@ mod imp {
     use super::*;
     use glib::translate::ToGlibPtr;
 
+    /// Number of times each hook fired, to check hooks ran when benchmarking dispatch overhead.
     #[derive(Default)]
-    pub struct NoopTracer;
+    pub struct NoopTracer {
+        push_pre: AtomicU64,
+        push_post: AtomicU64,
+        push_list_pre: AtomicU64,
+        push_list_post: AtomicU64,
+        pull_range_pre: AtomicU64,
+        pull_range_post: AtomicU64,
+    }
+
+    impl NoopTracer {
+        /// # Safety
+        ///
+        /// `tracer` must point to a live `NoopTracer`, which holds for the duration of any hook
+        /// registered in `constructed`.
+        unsafe fn from_tracer_ptr<'a>(tracer: *mut gst::ffi::GstTracer) -> &'a Self {
+            let tracer = gst::Tracer::from_glib_ptr_borrow(&tracer);
+            let imp: *const Self = tracer.unsafe_cast_ref::<super::NoopTracer>().imp();
+            &*imp
+        }
+
+        fn request_counts(&self) -> String {
+            [
+                ("push_pre", &self.push_pre),
+                ("push_post", &self.push_post),
+                ("push_list_pre", &self.push_list_pre),
+                ("push_list_post", &self.push_list_post),
+                ("pull_range_pre", &self.pull_range_pre),
+                ("pull_range_post", &self.pull_range_post),
+            ]
+            .iter()
+            .map(|(hook, count)| format!("{hook}={}", count.load(Ordering::Relaxed)))
+            .collect::<Vec<_>>()
+            .join(",")
+        }
+    }
 
     #[glib::object_subclass]
     impl ObjectSubclass for NoopTracer {
@@ -45,6 +81,20 @@ mod imp {
     }
 
     impl ObjectImpl for NoopTracer {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<glib::subclass::Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![glib::subclass::Signal::builder("request-counts")
+                    .flags(glib::SignalFlags::ACTION)
+                    .return_type::<String>()
+                    .class_handler(|_, args| {
+                        let tracer = args[0].get::<super::NoopTracer>().expect("signal arg");
+                        Some(tracer.imp().request_counts().to_value())
+                    })
+                    .build()]
+            })
+        }
+
         // Called once when the class is initialized
         fn constructed(&self) {
             self.parent_constructed();
@@ -53,10 +103,13 @@ mod imp {
 
             // Hook callbacks
             unsafe extern "C" fn do_push_buffer_pre(
-                _tracer: *mut gst::Tracer,
+                tracer: *mut gst::ffi::GstTracer,
                 _ts: u64,
                 ffi_pad: *mut gst::ffi::GstPad,
             ) {
+                NoopTracer::from_tracer_ptr(tracer)
+                    .push_pre
+                    .fetch_add(1, Ordering::Relaxed);
                 let pad = gst::Pad::from_glib_ptr_borrow(&ffi_pad);
                 gst::debug!(
                     CAT,
@@ -69,10 +122,13 @@ mod imp {
             }
 
             unsafe extern "C" fn do_pull_range_pre(
-                _tracer: *mut gst::Tracer,
+                tracer: *mut gst::ffi::GstTracer,
                 _ts: u64,
                 ffi_pad: *mut gst::ffi::GstPad,
             ) {
+                NoopTracer::from_tracer_ptr(tracer)
+                    .pull_range_pre
+                    .fetch_add(1, Ordering::Relaxed);
                 let pad = gst::Pad::from_glib_ptr_borrow(&ffi_pad);
                 gst::debug!(
                     CAT,
@@ -85,10 +141,13 @@ mod imp {
             }
 
             unsafe extern "C" fn do_push_buffer_post(
-                _tracer: *mut gst::Tracer,
+                tracer: *mut gst::ffi::GstTracer,
                 _ts: u64,
                 ffi_pad: *mut gst::ffi::GstPad,
             ) {
+                NoopTracer::from_tracer_ptr(tracer)
+                    .push_post
+                    .fetch_add(1, Ordering::Relaxed);
                 let pad = gst::Pad::from_glib_ptr_borrow(&ffi_pad);
                 gst::debug!(
                     CAT,
@@ -100,11 +159,54 @@ mod imp {
                 );
             }
 
+            unsafe extern "C" fn do_push_list_pre(
+                tracer: *mut gst::ffi::GstTracer,
+                _ts: u64,
+                ffi_pad: *mut gst::ffi::GstPad,
+                ffi_list: *mut gst::ffi::GstBufferList,
+            ) {
+                NoopTracer::from_tracer_ptr(tracer)
+                    .push_list_pre
+                    .fetch_add(1, Ordering::Relaxed);
+                let pad = gst::Pad::from_glib_ptr_borrow(&ffi_pad);
+                gst::debug!(
+                    CAT,
+                    "noop tracer: do_push_list_pre called on {}.{} {}.{} with {} buffers, but noop tracer does nothing",
+                    pad.parent().map(|p| p.name()).unwrap_or("unknown".into()),
+                    pad.name(),
+                    pad.peer().map(|p| p.name()).unwrap_or("unknown".into()),
+                    pad.peer().and_then(|p| p.parent()).map(|p| p.name()).unwrap_or("unknown".into()),
+                    gst::ffi::gst_buffer_list_length(ffi_list)
+                );
+            }
+
+            unsafe extern "C" fn do_push_list_post(
+                tracer: *mut gst::ffi::GstTracer,
+                _ts: u64,
+                ffi_pad: *mut gst::ffi::GstPad,
+            ) {
+                NoopTracer::from_tracer_ptr(tracer)
+                    .push_list_post
+                    .fetch_add(1, Ordering::Relaxed);
+                let pad = gst::Pad::from_glib_ptr_borrow(&ffi_pad);
+                gst::debug!(
+                    CAT,
+                    "noop tracer: do_push_list_post called on {}.{} {}.{}, but noop tracer does nothing",
+                    pad.parent().map(|p| p.name()).unwrap_or("unknown".into()),
+                    pad.name(),
+                    pad.peer().map(|p| p.name()).unwrap_or("unknown".into()),
+                    pad.peer().and_then(|p| p.parent()).map(|p| p.name()).unwrap_or("unknown".into())
+                );
+            }
+
             unsafe extern "C" fn do_pull_range_post(
-                _tracer: *mut gst::Tracer,
+                tracer: *mut gst::ffi::GstTracer,
                 _ts: u64,
                 ffi_pad: *mut gst::ffi::GstPad,
             ) {
+                NoopTracer::from_tracer_ptr(tracer)
+                    .pull_range_post
+                    .fetch_add(1, Ordering::Relaxed);
                 let pad = gst::Pad::from_glib_ptr_borrow(&ffi_pad);
                 gst::debug!(
                     CAT,
@@ -130,6 +232,20 @@ mod imp {
                         do_push_buffer_post as *const (),
                     ),
                 );
+                ffi::gst_tracing_register_hook(
+                    tracer_obj.to_glib_none().0,
+                    c"pad-push-list-pre".as_ptr(),
+                    std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                        do_push_list_pre as *const (),
+                    ),
+                );
+                ffi::gst_tracing_register_hook(
+                    tracer_obj.to_glib_none().0,
+                    c"pad-push-list-post".as_ptr(),
+                    std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                        do_push_list_post as *const (),
+                    ),
+                );
                 ffi::gst_tracing_register_hook(
                     tracer_obj.to_glib_none().0,
                     c"pad-pull-range-pre".as_ptr(),
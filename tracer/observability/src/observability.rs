@@ -0,0 +1,344 @@
+use glib::subclass::prelude::*;
+use glib::translate::{FromGlibPtrBorrow, ToGlibPtr};
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gstoteltracer::OtelTracerCore;
+use gstprometheustracer::{PromLatencySettings, PromLatencyTracerImp, TracedTypes};
+use gstreamer as gst;
+use std::sync::{LazyLock, OnceLock, RwLock};
+
+static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
+    gst::DebugCategory::new(
+        "observability",
+        gst::DebugColorFlags::empty(),
+        Some("Prometheus and Open Telemetry tracer"),
+    )
+});
+
+mod imp {
+    use super::*;
+
+    /// Feeds the `prom-latency` and `otel-tracer` cores from a single set of hooks.
+    #[derive(Default)]
+    pub struct ObservabilityTracer {
+        prometheus: PromLatencyTracerImp,
+        prometheus_settings: RwLock<PromLatencySettings>,
+        otel: OtelTracerCore,
+        /// Objects whose hooks reach each core, from its `filters`, read on every push.
+        types: OnceLock<(TracedTypes, TracedTypes)>,
+    }
+
+    impl ObservabilityTracer {
+        /// # Safety
+        ///
+        /// `tracer` must point to a live `ObservabilityTracer`, which holds for the duration of
+        /// any hook registered in `register_hooks`.
+        unsafe fn from_tracer_ptr<'a>(tracer: *mut gst::ffi::GstTracer) -> &'a Self {
+            let tracer = gst::Tracer::from_glib_borrow(tracer);
+            let imp: *const Self = tracer.unsafe_cast_ref::<super::ObservabilityTracer>().imp();
+            &*imp
+        }
+
+        /// The objects traced by `prom-latency` and by `otel-tracer`.
+        fn types(&self) -> (TracedTypes, TracedTypes) {
+            *self
+                .types
+                .get()
+                .expect("types are set before the hooks are registered")
+        }
+
+        /// Register the pad hooks for the objects either core traces.
+        ///
+        /// Pre hooks call `prom-latency` then `otel-tracer`, and post hooks the other way around,
+        /// so each core's pre and post nest around the other's.
+        fn register_hooks(&self) {
+            unsafe extern "C" fn do_push_buffer_pre(
+                tracer: *mut gst::ffi::GstTracer,
+                ts: u64,
+                pad: *mut gst::ffi::GstPad,
+                buf_ptr: *mut gst::ffi::GstBuffer,
+            ) {
+                let imp = ObservabilityTracer::from_tracer_ptr(tracer);
+                let (prometheus, otel) = imp.types();
+                if prometheus.buffers {
+                    imp.prometheus.pad_push_pre(ts, pad, buf_ptr);
+                }
+                if otel.buffers {
+                    imp.otel.pad_push_pre(ts, pad, buf_ptr);
+                }
+            }
+
+            unsafe extern "C" fn do_push_buffer_post(
+                tracer: *mut gst::ffi::GstTracer,
+                ts: u64,
+                pad: *mut gst::ffi::GstPad,
+                res: gst::ffi::GstFlowReturn,
+            ) {
+                let imp = ObservabilityTracer::from_tracer_ptr(tracer);
+                let (prometheus, otel) = imp.types();
+                if otel.buffers {
+                    imp.otel.pad_push_post(ts, pad, res);
+                }
+                if prometheus.buffers {
+                    imp.prometheus.pad_push_post(ts, pad);
+                }
+            }
+
+            unsafe extern "C" fn do_push_list_pre(
+                tracer: *mut gst::ffi::GstTracer,
+                ts: u64,
+                pad: *mut gst::ffi::GstPad,
+                list_ptr: *mut gst::ffi::GstBufferList,
+            ) {
+                let imp = ObservabilityTracer::from_tracer_ptr(tracer);
+                let (prometheus, otel) = imp.types();
+                if prometheus.buffer_lists {
+                    imp.prometheus.pad_push_list_pre(ts, pad, list_ptr);
+                }
+                if otel.buffer_lists {
+                    imp.otel.pad_push_list_pre(ts, pad, list_ptr);
+                }
+            }
+
+            unsafe extern "C" fn do_push_list_post(
+                tracer: *mut gst::ffi::GstTracer,
+                ts: u64,
+                pad: *mut gst::ffi::GstPad,
+                res: gst::ffi::GstFlowReturn,
+            ) {
+                let imp = ObservabilityTracer::from_tracer_ptr(tracer);
+                let (prometheus, otel) = imp.types();
+                if otel.buffer_lists {
+                    imp.otel.pad_push_post(ts, pad, res);
+                }
+                if prometheus.buffer_lists {
+                    imp.prometheus.pad_push_post(ts, pad);
+                }
+            }
+
+            unsafe extern "C" fn do_push_event_pre(
+                tracer: *mut gst::ffi::GstTracer,
+                ts: u64,
+                pad: *mut gst::ffi::GstPad,
+                event: *mut gst::ffi::GstEvent,
+            ) {
+                let imp = ObservabilityTracer::from_tracer_ptr(tracer);
+                let (prometheus, otel) = imp.types();
+                if prometheus.events {
+                    imp.prometheus.pad_push_event_pre(pad, event);
+                }
+                if otel.events {
+                    imp.otel.pad_push_event_pre(ts, pad, event);
+                }
+            }
+
+            unsafe extern "C" fn do_pad_link_post(
+                tracer: *mut gst::ffi::GstTracer,
+                _ts: u64,
+                src_pad: *mut gst::ffi::GstPad,
+                sink_pad: *mut gst::ffi::GstPad,
+                res: gst::ffi::GstPadLinkReturn,
+            ) {
+                ObservabilityTracer::from_tracer_ptr(tracer)
+                    .prometheus
+                    .pad_link_post(src_pad, sink_pad, res);
+            }
+
+            unsafe extern "C" fn do_pad_unlink_post(
+                tracer: *mut gst::ffi::GstTracer,
+                ts: u64,
+                src_pad: *mut gst::ffi::GstPad,
+                sink_pad: *mut gst::ffi::GstPad,
+                res: glib::ffi::gboolean,
+            ) {
+                let imp = ObservabilityTracer::from_tracer_ptr(tracer);
+                imp.otel.pad_unlink_post(ts, src_pad, sink_pad);
+                imp.prometheus.pad_unlink_post(src_pad, sink_pad, res);
+            }
+
+            let (prometheus, otel) = self.types();
+            let types = prometheus.union(otel);
+            let obj = self.obj();
+            let tracer_obj: &gst::Tracer = obj.upcast_ref();
+            unsafe {
+                let obj = tracer_obj.to_glib_none().0;
+                if types.buffers {
+                    gst::ffi::gst_tracing_register_hook(
+                        obj,
+                        c"pad-push-pre".as_ptr(),
+                        std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                            do_push_buffer_pre as *const (),
+                        ),
+                    );
+                    gst::ffi::gst_tracing_register_hook(
+                        obj,
+                        c"pad-push-post".as_ptr(),
+                        std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                            do_push_buffer_post as *const (),
+                        ),
+                    );
+                }
+                if types.buffer_lists {
+                    gst::ffi::gst_tracing_register_hook(
+                        obj,
+                        c"pad-push-list-pre".as_ptr(),
+                        std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                            do_push_list_pre as *const (),
+                        ),
+                    );
+                    gst::ffi::gst_tracing_register_hook(
+                        obj,
+                        c"pad-push-list-post".as_ptr(),
+                        std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                            do_push_list_post as *const (),
+                        ),
+                    );
+                }
+                if types.events {
+                    gst::ffi::gst_tracing_register_hook(
+                        obj,
+                        c"pad-push-event-pre".as_ptr(),
+                        std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                            do_push_event_pre as *const (),
+                        ),
+                    );
+                }
+                gst::ffi::gst_tracing_register_hook(
+                    obj,
+                    c"pad-link-post".as_ptr(),
+                    std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                        do_pad_link_post as *const (),
+                    ),
+                );
+                gst::ffi::gst_tracing_register_hook(
+                    obj,
+                    c"pad-unlink-post".as_ptr(),
+                    std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                        do_pad_unlink_post as *const (),
+                    ),
+                );
+            }
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ObservabilityTracer {
+        const NAME: &'static str = "observabilitytracer";
+        type Type = super::ObservabilityTracer;
+        type ParentType = gst::Tracer;
+    }
+
+    impl ObjectImpl for ObservabilityTracer {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+            let tracer_obj: &gst::Tracer = obj.upcast_ref();
+
+            // Both cores read the same params and ignore those of the other. The ones they share,
+            // `filter`, `flags` and `filters`, select the same elements and objects for both.
+            let settings = PromLatencySettings::from_tracer(tracer_obj);
+            gst::debug!(
+                CAT,
+                imp = self,
+                "using prom-latency settings: {:?}",
+                settings
+            );
+            self.prometheus.configure(&settings);
+            self.otel.configure(tracer_obj);
+            let _ = self.types.set((settings.filters, self.otel.filters()));
+            let state_changes = settings.state_changes;
+            *self.prometheus_settings.write().unwrap() = settings;
+
+            self.register_hook(TracerHook::ElementNew);
+            self.register_hook(TracerHook::ElementPostMessagePre);
+            if state_changes {
+                self.register_hook(TracerHook::ElementChangeStatePre);
+                self.register_hook(TracerHook::ElementChangeStatePost);
+            }
+            self.register_hooks();
+        }
+
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<glib::subclass::Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![
+                    glib::subclass::Signal::builder("metrics")
+                        .flags(glib::SignalFlags::ACTION)
+                        .return_type::<Option<String>>()
+                        .class_handler(|_, args| {
+                            let tracer = args[0]
+                                .get::<super::ObservabilityTracer>()
+                                .expect("signal arg");
+                            let metrics = tracer.imp().prometheus.request_metrics();
+                            Some(Some(metrics).to_value())
+                        })
+                        .build(),
+                    glib::subclass::Signal::builder("request-span-count")
+                        .flags(glib::SignalFlags::ACTION)
+                        .return_type::<u64>()
+                        .class_handler(|_, args| {
+                            let tracer = args[0]
+                                .get::<super::ObservabilityTracer>()
+                                .expect("signal arg");
+                            Some(tracer.imp().otel.span_count().to_value())
+                        })
+                        .build(),
+                ]
+            })
+        }
+
+        fn dispose(&self) {
+            self.otel.dispose();
+            self.prometheus.dispose();
+        }
+    }
+
+    impl GstObjectImpl for ObservabilityTracer {}
+
+    impl TracerImpl for ObservabilityTracer {
+        fn element_new(&self, ts: u64, element: &gst::Element) {
+            self.prometheus
+                .element_new(ts, element, &self.prometheus_settings.read().unwrap());
+            self.otel.element_new(element);
+        }
+
+        fn element_post_message_pre(&self, _ts: u64, element: &gst::Element, msg: &gst::Message) {
+            self.otel.element_post_message_pre(element, msg);
+        }
+
+        fn element_change_state_pre(
+            &self,
+            ts: u64,
+            element: &gst::Element,
+            _change: gst::StateChange,
+        ) {
+            self.prometheus.element_change_state_pre(ts, element);
+        }
+
+        fn element_change_state_post(
+            &self,
+            ts: u64,
+            element: &gst::Element,
+            change: gst::StateChange,
+            _result: Result<gst::StateChangeSuccess, gst::StateChangeError>,
+        ) {
+            self.prometheus
+                .element_change_state_post(ts, element, change);
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct ObservabilityTracer(ObjectSubclass<imp::ObservabilityTracer>)
+        @extends gst::Tracer, gst::Object;
+}
+
+/// Register plugin
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Tracer::register(
+        Some(plugin),
+        "observability",
+        ObservabilityTracer::static_type(),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use gst::prelude::*;
+    use gstreamer::{self as gst};
+    use gsttracercommon::test_util as common;
+
+    const PROM_PORT: u16 = 19100;
+
+    #[test]
+    fn given_observability_tracer_when_pipeline_runs_then_metrics_and_spans_captured() {
+        setup_test();
+
+        let pipeline =
+            gst::parse::launch("fakesrc num-buffers=20 ! identity name=middle ! fakesink")
+                .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        let tracer = common::tracer("observabilitytracer");
+        let metrics = tracer
+            .emit_by_name::<Option<String>>("metrics", &[])
+            .expect("Expected to get metrics from signal");
+        assert!(
+            metrics.contains("gst_element_latency_count_count{element=\"middle\""),
+            "Expected latency for middle in:\n{metrics}"
+        );
+        assert!(tracer.emit_by_name::<u64>("request-span-count", &[]) > 0);
+    }
+
+    #[test]
+    fn given_observability_tracer_when_initialized_then_no_other_tracers_are_active() {
+        setup_test();
+
+        let types = gst::active_tracers()
+            .into_iter()
+            .map(|t| t.type_().name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(types, ["observabilitytracer"]);
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "observability(port={PROM_PORT},http-server=false,dry-run=true)"
+        ));
+    }
+}
@@ -20,6 +20,8 @@ mod otellogbridge;
 mod oteltracer;
 mod pyroscopespanprocessor;
 
+pub use oteltracer::{OtelTracerCore, TelemetryTracer};
+
 // ───────────────── plugin boilerplate ──────────────────
 pub fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     oteltracer::register(plugin)?;
@@ -1,11 +1,16 @@
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::sync::{Once, RwLock};
 use std::thread;
 
+use glib::translate::{FromGlib, IntoGlib};
 use glib::GStr;
 use gst::DebugCategory;
 use gst::DebugLevel;
 use gstreamer as gst;
 use gstreamer::DebugMessage;
 use gstreamer::LoggedObject;
+use gsttracercommon::element_filter::Filter;
 use opentelemetry::logs::Severity;
 
 pub trait LogBridge: Send + Sync + 'static {
@@ -35,16 +40,64 @@ use opentelemetry::logs::{AnyValue, Logger};
 use opentelemetry::Key;
 use opentelemetry::KeyValue;
 use opentelemetry_otlp::LogExporter;
-use opentelemetry_sdk::logs::SdkLoggerProvider;
+use opentelemetry_sdk::logs::{BatchConfig, BatchLogProcessor, SdkLoggerProvider};
 use opentelemetry_sdk::Resource;
 
 pub struct StructuredBridge<L: Logger> {
     logger: L,
+    /// Messages more verbose than this are dropped before building a record.
+    min_level: DebugLevel,
+    /// Messages of categories this does not select are dropped, unless they are errors.
+    categories: Filter,
+    /// Whether `categories` selects a category, by name, so patterns are matched once each.
+    category_decisions: RwLock<HashMap<String, bool>>,
 }
 
 impl<L: Logger> StructuredBridge<L> {
-    pub fn new(logger: L) -> Self {
-        StructuredBridge { logger }
+    pub fn new(logger: L, min_level: DebugLevel, categories: Filter) -> Self {
+        StructuredBridge {
+            logger,
+            min_level,
+            categories,
+            category_decisions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Whether messages of `category` at `level` are exported; errors always are.
+    fn category_allowed(&self, category: &DebugCategory, level: DebugLevel) -> bool {
+        if self.categories.is_empty() || level == DebugLevel::Error {
+            return true;
+        }
+        let name = category.name();
+        if let Some(&allowed) = self.category_decisions.read().unwrap().get(name) {
+            return allowed;
+        }
+        let allowed = self.categories.matches(name);
+        self.category_decisions
+            .write()
+            .unwrap()
+            .insert(name.to_string(), allowed);
+        allowed
+    }
+}
+
+/// Parse a debug level by name (`warning`) or number (`2`), as in `GST_DEBUG`.
+pub fn parse_debug_level(level: &str) -> Option<DebugLevel> {
+    let level = level.trim().to_ascii_lowercase();
+    match level.as_str() {
+        "none" => Some(DebugLevel::None),
+        "error" => Some(DebugLevel::Error),
+        "warning" => Some(DebugLevel::Warning),
+        "fixme" => Some(DebugLevel::Fixme),
+        "info" => Some(DebugLevel::Info),
+        "debug" => Some(DebugLevel::Debug),
+        "log" => Some(DebugLevel::Log),
+        "trace" => Some(DebugLevel::Trace),
+        "memdump" => Some(DebugLevel::Memdump),
+        _ => match level.parse::<i32>() {
+            Ok(n @ 0..=9) => Some(unsafe { DebugLevel::from_glib(n) }),
+            _ => None,
+        },
     }
 }
 fn severity_of_debug_level(level: DebugLevel) -> Severity {
@@ -58,8 +111,70 @@ fn severity_of_debug_level(level: DebugLevel) -> Severity {
         DebugLevel::Debug => Severity::Debug,
         DebugLevel::Trace => Severity::Trace,
         DebugLevel::Memdump => Severity::Trace,
-        _ => todo!(),
+        // `DebugLevel` is non-exhaustive; don't take the logging thread down over a new level.
+        _ => {
+            static UNKNOWN_LEVEL: Once = Once::new();
+            // Logging through GStreamer here would re-enter this log function.
+            UNKNOWN_LEVEL.call_once(|| {
+                eprintln!(
+                    "otel-tracer: unknown debug level {level:?}, mapping it to debug severity"
+                )
+            });
+            Severity::Debug
+        }
+    }
+}
+
+/// GStreamer's own name for `level`, as printed in `GST_DEBUG` output.
+fn severity_text_of_debug_level(level: DebugLevel) -> &'static str {
+    match level {
+        DebugLevel::None => "NONE",
+        DebugLevel::Error => "ERROR",
+        DebugLevel::Warning => "WARN",
+        DebugLevel::Fixme => "FIXME",
+        DebugLevel::Info => "INFO",
+        DebugLevel::Debug => "DEBUG",
+        DebugLevel::Log => "LOG",
+        DebugLevel::Trace => "TRACE",
+        DebugLevel::Memdump => "MEMDUMP",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Path of a logged `GstObject` in the form of `gst_object_get_path_string`, e.g.
+/// `/pipeline0/identity0.src`, or `None` for other objects.
+///
+/// Built from the name and parent fields without taking object locks, like `GST_OBJECT_NAME`, as
+/// messages are often logged while holding them.
+///
+/// # Safety
+///
+/// `obj` must point to a live `GObject`, which holds for the object of a message being logged.
+unsafe fn object_path(obj: *mut glib::gobject_ffi::GObject) -> Option<String> {
+    let is_a = |object: *mut gst::ffi::GstObject, type_: glib::ffi::GType| {
+        glib::gobject_ffi::g_type_check_instance_is_a(object as *mut _, type_) != glib::ffi::GFALSE
+    };
+    let mut object = obj as *mut gst::ffi::GstObject;
+    if !is_a(object, gst::ffi::gst_object_get_type()) {
+        return None;
     }
+    let mut components = vec![];
+    while !object.is_null() {
+        let name = (*object).name;
+        let name = if name.is_null() {
+            "(NULL)".into()
+        } else {
+            CStr::from_ptr(name).to_string_lossy()
+        };
+        let separator = if is_a(object, gst::ffi::gst_pad_get_type()) {
+            "."
+        } else {
+            "/"
+        };
+        components.push(format!("{separator}{name}"));
+        object = (*object).parent;
+    }
+    Some(components.into_iter().rev().collect())
 }
 
 impl<L: Logger + 'static + Send + Sync> LogBridge for StructuredBridge<L> {
@@ -71,17 +186,20 @@ impl<L: Logger + 'static + Send + Sync> LogBridge for StructuredBridge<L> {
         function: &GStr,
         line: u32,
         message: &DebugMessage,
-        _obj: Option<&LoggedObject>,
+        obj: Option<&LoggedObject>,
         trace_id: &str,
         span_id: &str,
     ) {
+        if level.into_glib() > self.min_level.into_glib() || !self.category_allowed(category, level)
+        {
+            return;
+        }
         let mut record = self.logger.create_log_record();
         let debug_level = severity_of_debug_level(level);
         record.set_severity_number(debug_level);
+        record.set_severity_text(severity_text_of_debug_level(level));
         record.set_timestamp(std::time::SystemTime::now());
 
-        // TODO - not sure how to comply with 'static lifetime
-        // record.set_severity_text(&level.to_owned().to_string());
         record.set_body(
             // Convert GStr to String, or use empty string if None
             // This is a workaround for the fact that GStr does not implement Debug
@@ -114,13 +232,15 @@ impl<L: Logger + 'static + Send + Sync> LogBridge for StructuredBridge<L> {
         record.add_attribute(Key::new("code.file"), file.to_string());
         record.add_attribute(Key::new("code.function"), function.to_string());
         record.add_attribute(Key::new("code.line"), AnyValue::Int(line as i64));
+        if let Some(path) = obj.and_then(|o| unsafe { object_path(o.as_ptr()) }) {
+            record.add_attribute(Key::new("gst.object.path"), path);
+        }
 
         self.logger.emit(record);
     }
 }
 pub struct PlaintextBridge;
 
-#[allow(dead_code)]
 impl PlaintextBridge {
     pub fn new() -> Self {
         PlaintextBridge
@@ -161,9 +281,15 @@ impl LogBridge for PlaintextBridge {
         // the actual message text
         let msg = message.get().map(|s| s.to_string()).unwrap_or_default();
 
+        // `<path>` of the logged object, as after the function name in `GST_DEBUG` output
+        let object = obj
+            .and_then(|o| unsafe { object_path(o.as_ptr()) })
+            .map(|path| format!("<{path}>"))
+            .unwrap_or_default();
+
         // final formatted line
         eprintln!(
-            "{} {:?} {} {} {} {}{} {}:{}:{}: {}",
+            "{} {:?} {} {} {} {}{} {}:{}:{}:{} {}",
             timestamp,
             obj.map(|o| o.as_ptr()).unwrap_or(core::ptr::null_mut()),
             trace_id,
@@ -174,27 +300,80 @@ impl LogBridge for PlaintextBridge {
             file,
             line,
             function,
+            object,
             msg,
         );
     }
 }
 
-pub fn init_logs_otlp() -> SdkLoggerProvider {
-    // 1. Build an OTLP LogExporter over gRPC
-    let exporter = LogExporter::builder()
-        .with_http()
-        .build() // use HTTP
-        .expect("failed to build OTLP exporter");
-
-    // 3. Provider
-
+pub fn init_logs_otlp(
+    service_name: &str,
+    exporter: LogExporter,
+    batch_config: BatchConfig,
+) -> SdkLoggerProvider {
     SdkLoggerProvider::builder()
         .with_resource(
             Resource::builder_empty()
-                .with_attribute(KeyValue::new("service.name", "gst.pyroscope"))
+                .with_attribute(KeyValue::new("service.name", service_name.to_string()))
+                .build(),
+        )
+        .with_log_processor(
+            BatchLogProcessor::builder(exporter)
+                .with_batch_config(batch_config)
                 .build(),
         )
-        .with_batch_exporter(exporter)
-        // .with_log_processor(BatchLogProcessor::builder(exporter).build())
         .build()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        object_path, parse_debug_level, severity_of_debug_level, severity_text_of_debug_level,
+    };
+    use glib::translate::ToGlibPtr;
+    use gstreamer::{self as gst, prelude::*, DebugLevel};
+    use opentelemetry::logs::Severity;
+
+    #[test]
+    fn unknown_debug_level_maps_to_debug() {
+        assert_eq!(severity_of_debug_level(DebugLevel::Warning), Severity::Warn);
+        assert_eq!(
+            severity_of_debug_level(DebugLevel::__Unknown(42)),
+            Severity::Debug
+        );
+        assert_eq!(severity_text_of_debug_level(DebugLevel::Fixme), "FIXME");
+        assert_eq!(
+            severity_text_of_debug_level(DebugLevel::__Unknown(42)),
+            "UNKNOWN"
+        );
+    }
+
+    #[test]
+    fn object_path_follows_parents_and_pads() {
+        gst::init().unwrap();
+        let pipeline = gst::Pipeline::builder().name("pipeline0").build();
+        let identity = gst::ElementFactory::make("identity")
+            .name("id")
+            .build()
+            .unwrap();
+        pipeline.add(&identity).unwrap();
+        let src = identity.static_pad("src").unwrap();
+
+        let path = |obj: &glib::Object| unsafe { object_path(obj.to_glib_none().0) };
+        assert_eq!(
+            path(identity.upcast_ref()).as_deref(),
+            Some("/pipeline0/id")
+        );
+        assert_eq!(path(src.upcast_ref()).as_deref(), Some("/pipeline0/id.src"));
+        assert_eq!(path(&glib::Object::with_type(glib::Type::OBJECT)), None);
+    }
+
+    #[test]
+    fn parse_debug_level_accepts_names_and_numbers() {
+        assert_eq!(parse_debug_level("info"), Some(DebugLevel::Info));
+        assert_eq!(parse_debug_level("WARNING"), Some(DebugLevel::Warning));
+        assert_eq!(parse_debug_level("5"), Some(DebugLevel::Debug));
+        assert_eq!(parse_debug_level("10"), None);
+        assert_eq!(parse_debug_level("loud"), None);
+    }
+}
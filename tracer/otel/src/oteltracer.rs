@@ -1,16 +1,34 @@
 // Derived from gstlatency.c: tracing module that logs processing latency stats
-// Now uses OTLP exporter for both traces and metrics, removing Prometheus-specific HTTP server
+// Now uses OTLP exporter for both traces and (with `metrics=true`) metrics, removing Prometheus-specific HTTP server
 
 use glib::subclass::prelude::*;
 use glib::Quark;
 use gst::prelude::*;
 use gst::subclass::prelude::*;
 use gstreamer as gst;
+use gsttracercommon::element_filter::Filter;
+use gsttracercommon::sigterm::on_sigterm;
+use gsttracercommon::tracer_flags::{LatencyFlags, TracedTypes};
 use opentelemetry::global::BoxedSpan;
-use std::sync::{LazyLock, OnceLock};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock};
+use std::time::Duration;
 // OpenTelemetry and OTLP exporter
-use opentelemetry::trace::{Span, SpanContext, Tracer};
+use opentelemetry::metrics::{Histogram, MeterProvider};
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{Link, Span, SpanContext, Status, Tracer};
 use opentelemetry::{global, KeyValue};
+use opentelemetry_sdk::logs::{
+    BatchConfig as LogBatchConfig, BatchConfigBuilder as LogBatchConfigBuilder, SdkLoggerProvider,
+};
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{
+    BatchConfigBuilder, BatchSpanProcessor, SdkTracerProvider, SpanData, SpanExporter,
+    SpanProcessor,
+};
 use opentelemetry_sdk::Resource;
 
 use opentelemetry::logs::LoggerProvider;
@@ -18,20 +36,23 @@ use opentelemetry::logs::LoggerProvider;
 /// GStreamer Tracer subclass
 mod imp {
     use crate::{
-        otellogbridge::{init_logs_otlp, LogBridge, StructuredBridge},
-        pyroscopespanprocessor::imp::PyroscopeSpanProcessor,
+        otellogbridge::{
+            init_logs_otlp, parse_debug_level, LogBridge, PlaintextBridge, StructuredBridge,
+        },
+        pyroscopespanprocessor::imp::{PyroscopeConfig, PyroscopeSpanProcessor},
     };
 
     use super::*;
     use glib::{
         ffi::{gpointer, GFALSE, GTRUE},
-        translate::{FromGlib, FromGlibPtrBorrow, IntoGlib, ToGlibPtr},
+        translate::{FromGlib, FromGlibPtrBorrow, FromGlibPtrNone, IntoGlib, ToGlibPtr},
     };
     use gobject_sys::GCallback;
 
     use gstreamer_sys::{GstBuffer, GstMeta};
     use opentelemetry::trace::TraceContextExt;
     use std::{os::raw::c_void, ptr};
+    use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue, MetadataMap};
 
     /// GStreamer debug category for logs
     static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
@@ -42,51 +63,1086 @@ mod imp {
         )
     });
 
-    static INIT_ONCE: OnceLock<global::BoxedTracer> = OnceLock::new();
+    static INIT_ONCE: OnceLock<SdkTracerProvider> = OnceLock::new();
+    /// Whether spans, metrics and logs are exported, decided once by `init_otlp`.
+    static EXPORTING: OnceLock<bool> = OnceLock::new();
+    /// Only set with `metrics=true`.
+    static METER_PROVIDER: OnceLock<SdkMeterProvider> = OnceLock::new();
+    /// Only set with `log-bridge=structured` while exporting.
+    static LOGGER_PROVIDER: OnceLock<SdkLoggerProvider> = OnceLock::new();
+    /// Time from `pad-push-pre` to `pad-push-post` in nanoseconds, only set with `metrics=true`.
+    static LATENCY_HISTOGRAM: OnceLock<Histogram<u64>> = OnceLock::new();
+    /// Spans started for pushes, including per-buffer spans of lists. The tracer provider is
+    /// global, so the count is shared by all `otel-tracer` instances.
+    static SPANS_STARTED: AtomicU64 = AtomicU64::new(0);
+    /// Parent of spans that have no other, set by the application through
+    /// `request-set-parent-context`. Shared by all `otel-tracer` instances like the provider.
+    static APPLICATION_PARENT: RwLock<Option<SpanContext>> = RwLock::new(None);
+    /// Only set with `exporter=memory`.
+    #[cfg(feature = "test-util")]
+    static MEMORY_EXPORTER: OnceLock<opentelemetry_sdk::trace::InMemorySpanExporter> =
+        OnceLock::new();
+    /// The tonic exporter needs a tokio runtime to drive its channel.
+    static GRPC_RUNTIME: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("otel-grpc")
+            .enable_all()
+            .build()
+            .expect("Failed to create runtime for the OTLP gRPC exporter")
+    });
     static QUARK_SINK_SPAN: LazyLock<u32> =
         LazyLock::new(|| Quark::from_str("otel-trace").into_glib());
+    /// Pushes seen on a src pad since the last traced one, for `span-every-n`.
+    static QUARK_PUSH_COUNT: LazyLock<u32> =
+        LazyLock::new(|| Quark::from_str("otel-push-count").into_glib());
+    /// Buffers pushed on a src pad since it was last linked, for `buffer.seq`.
+    static QUARK_BUFFER_SEQ: LazyLock<u32> =
+        LazyLock::new(|| Quark::from_str("otel-buffer-seq").into_glib());
+    /// Cached `filter` and `elements`/`factories` decision for a src pad.
+    static QUARK_TRACED: LazyLock<u32> =
+        LazyLock::new(|| Quark::from_str("otel-traced").into_glib());
+    /// Per-buffer spans of a buffer list push, ended together with the list span.
+    static QUARK_SINK_LIST_SPANS: LazyLock<u32> =
+        LazyLock::new(|| Quark::from_str("otel-trace-list").into_glib());
+    /// Last `traceparent` pushed downstream from a src pad.
+    static QUARK_TRACEPARENT_SENT: LazyLock<u32> =
+        LazyLock::new(|| Quark::from_str("otel-traceparent-sent").into_glib());
+    /// Remote parent received through a `traceparent` event on a src pad.
+    static QUARK_REMOTE_PARENT: LazyLock<u32> =
+        LazyLock::new(|| Quark::from_str("otel-remote-parent").into_glib());
+    /// Span of an element with `span-granularity=element`.
+    static QUARK_ELEMENT_SESSION: LazyLock<u32> =
+        LazyLock::new(|| Quark::from_str("otel-element-session").into_glib());
+    /// Held while creating an element's session, which pushes from several streaming threads
+    /// may race to do.
+    static ELEMENT_SESSION_INIT: Mutex<()> = Mutex::new(());
+    /// Name of the top-level bin an element is in, for the `pipeline.name` attribute.
+    static QUARK_PIPELINE_NAME: LazyLock<u32> =
+        LazyLock::new(|| Quark::from_str("otel-pipeline-name").into_glib());
+    /// Held while caching an element's pipeline name, which several streaming threads may race
+    /// to do.
+    static PIPELINE_NAME_INIT: Mutex<()> = Mutex::new(());
+    /// Name of the custom downstream event carrying a W3C `traceparent`.
+    const TRACEPARENT_EVENT: &str = "otel-traceparent";
     static PIPELINE_INIT_ONCE: OnceLock<()> = OnceLock::new();
 
+    thread_local! {
+        /// Name and id of the current thread, formatted once per streaming thread rather than
+        /// on every push.
+        static THREAD_ATTRIBUTES: (Arc<str>, Arc<str>) = {
+            let current = std::thread::current();
+            (
+                current.name().unwrap_or("unnamed").into(),
+                format!("{:?}", current.id()).into(),
+            )
+        };
+    }
+
     #[derive(Debug)]
     struct GstSpanSink<'a> {
         // guard deallocation ends span
         #[allow(dead_code)]
         guard: opentelemetry::ContextGuard,
         span: opentelemetry::trace::SpanRef<'a>,
+        start_ts: u64,
+        /// Element and pad attributes for the latency histogram, empty without `metrics=true`.
+        metric_attributes: Vec<KeyValue>,
+    }
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    enum OtlpProtocol {
+        #[default]
+        Http,
+        Grpc,
+    }
+
+    /// A placeholder of `span-name-template`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum SpanNameField {
+        SrcElement,
+        SrcPad,
+        SinkPad,
+        SinkElement,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum SpanNameSegment {
+        Literal(String),
+        Field(SpanNameField),
+    }
+
+    /// Span name of a push, parsed once from `span-name-template` and filled in on span start.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub(super) struct SpanNameTemplate(Vec<SpanNameSegment>);
+
+    impl SpanNameTemplate {
+        const DEFAULT: &'static str = "pad-push-{src_element}-{src_pad}-{sink_pad}-{sink_element}";
+
+        /// Unknown placeholders are kept as literal text.
+        pub(super) fn parse(template: &str) -> Self {
+            let mut segments = vec![];
+            let mut rest = template;
+            while let Some(start) = rest.find('{') {
+                let Some(len) = rest[start..].find('}') else {
+                    break;
+                };
+                let field = match &rest[start + 1..start + len] {
+                    "src_element" => SpanNameField::SrcElement,
+                    "src_pad" => SpanNameField::SrcPad,
+                    "sink_pad" => SpanNameField::SinkPad,
+                    "sink_element" => SpanNameField::SinkElement,
+                    _ => {
+                        segments.push(SpanNameSegment::Literal(rest[..=start + len].into()));
+                        rest = &rest[start + len + 1..];
+                        continue;
+                    }
+                };
+                if start > 0 {
+                    segments.push(SpanNameSegment::Literal(rest[..start].into()));
+                }
+                segments.push(SpanNameSegment::Field(field));
+                rest = &rest[start + len + 1..];
+            }
+            if !rest.is_empty() {
+                segments.push(SpanNameSegment::Literal(rest.into()));
+            }
+            Self(segments)
+        }
+
+        pub(super) fn render(
+            &self,
+            src_element: &str,
+            src_pad: &str,
+            sink_pad: &str,
+            sink_element: &str,
+        ) -> String {
+            let mut name = String::new();
+            for segment in &self.0 {
+                name.push_str(match segment {
+                    SpanNameSegment::Literal(literal) => literal,
+                    SpanNameSegment::Field(SpanNameField::SrcElement) => src_element,
+                    SpanNameSegment::Field(SpanNameField::SrcPad) => src_pad,
+                    SpanNameSegment::Field(SpanNameField::SinkPad) => sink_pad,
+                    SpanNameSegment::Field(SpanNameField::SinkElement) => sink_element,
+                });
+            }
+            name
+        }
+    }
+
+    impl Default for SpanNameTemplate {
+        fn default() -> Self {
+            Self::parse(Self::DEFAULT)
+        }
+    }
+
+    /// Parses `k1=v1,k2=v2`, skipping entries without a `=`.
+    pub(super) fn parse_key_values(v: &str) -> Vec<(String, String)> {
+        v.split(',')
+            .filter_map(|attr| {
+                let mut parts = attr.splitn(2, '=');
+                if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+                    Some((key.trim().to_string(), value.trim().to_string()))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Bucket boundaries of the latency histogram in nanoseconds, from 1µs growing 4x up to ~1s.
+    fn default_histogram_buckets() -> Vec<f64> {
+        (0..11).map(|i| 1_000.0 * 4f64.powi(i)).collect()
+    }
+
+    /// Parses comma-separated histogram bucket boundaries, which must be positive and strictly
+    /// increasing.
+    pub(super) fn parse_histogram_buckets(v: &str) -> Result<Vec<f64>, String> {
+        let buckets = v
+            .split(',')
+            .map(|bound| {
+                bound
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("{} is not a number", bound.trim()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(bound) = buckets.iter().find(|b| !b.is_finite() || **b <= 0.0) {
+            return Err(format!("{bound} is not positive"));
+        }
+        if buckets.windows(2).any(|w| w[0] >= w[1]) {
+            return Err("boundaries are not strictly increasing".into());
+        }
+        Ok(buckets)
+    }
+
+    /// Headers sent with every OTLP export request, e.g. a collector API key.
+    ///
+    /// Values are credentials, so `Debug` only shows the header names.
+    #[derive(Clone, Default, PartialEq, Eq)]
+    pub(super) struct ExportHeaders(pub(super) Vec<(String, String)>);
+
+    impl std::fmt::Debug for ExportHeaders {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_map()
+                .entries(self.0.iter().map(|(key, _)| (key, "<redacted>")))
+                .finish()
+        }
+    }
+
+    impl ExportHeaders {
+        pub(super) fn http(&self) -> HashMap<String, String> {
+            self.0.iter().cloned().collect()
+        }
+
+        /// gRPC metadata keys must be lowercase ASCII; invalid headers are skipped with a warning.
+        fn grpc(&self) -> MetadataMap {
+            let mut metadata = MetadataMap::new();
+            for (name, value) in &self.0 {
+                let key = AsciiMetadataKey::from_bytes(name.to_lowercase().as_bytes());
+                match (key, value.parse::<AsciiMetadataValue>()) {
+                    (Ok(key), Ok(value)) => {
+                        metadata.insert(key, value);
+                    }
+                    _ => gst::warning!(CAT, "skipping invalid gRPC header {}", name),
+                }
+            }
+            metadata
+        }
+    }
+
+    /// Where spans are exported to.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    enum SpanExporterKind {
+        /// An OTLP collector, or any backend ingesting OTLP directly such as Jaeger.
+        #[default]
+        Otlp,
+        /// A Zipkin collector's `/api/v2/spans` endpoint.
+        Zipkin,
+        /// Kept in memory for tests to read back through `request-finished-spans`.
+        #[cfg(feature = "test-util")]
+        Memory,
+    }
+
+    impl SpanExporterKind {
+        /// Whether spans stay in this process, so there is no collector to probe.
+        fn in_memory(self) -> bool {
+            #[cfg(feature = "test-util")]
+            if self == Self::Memory {
+                return true;
+            }
+            false
+        }
+    }
+
+    /// How buffer list pushes are traced.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    enum ListSpans {
+        /// One span per list, propagated through the first buffer.
+        #[default]
+        List,
+        /// A child span of the list span for each buffer in the list.
+        Buffer,
+    }
+
+    /// What a span covers.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    enum SpanGranularity {
+        /// One span per buffer push.
+        #[default]
+        Buffer,
+        /// One span per element, from its first buffer to EOS or unlink.
+        Element,
+    }
+
+    /// Where GStreamer debug messages go once a pipeline is created.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    enum LogBridgeKind {
+        /// OTLP log records carrying the trace and span ids.
+        #[default]
+        Structured,
+        /// `GST_DEBUG`-like lines on stderr annotated with the trace and span ids.
+        Plaintext,
+        /// Leave the default GStreamer log handler in place.
+        None,
+    }
+
+    #[derive(Debug)]
+    struct Settings {
+        /// `service.name` resource attribute reported with spans, logs and profiles.
+        service_name: String,
+        /// Extra resource attributes, `service.name` always comes from `service_name`.
+        resource_attributes: Vec<(String, String)>,
+        exporter: SpanExporterKind,
+        /// Exporter endpoint; when unset the exporter's default (or `OTEL_EXPORTER_OTLP_*`) is used.
+        endpoint: Option<String>,
+        /// Sent with OTLP span, metric and log exports.
+        headers: ExportHeaders,
+        /// How long an OTLP export may take before it is abandoned.
+        export_timeout: Duration,
+        protocol: OtlpProtocol,
+        /// Maximum spans per export batch; when unset the SDK (or `OTEL_BSP_*`) default is used.
+        batch_size: Option<usize>,
+        /// Delay between batch exports; when unset the SDK (or `OTEL_BSP_*`) default is used.
+        schedule_delay: Option<Duration>,
+        /// Maximum logs per export batch; when unset the SDK (or `OTEL_BLRP_*`) default is used.
+        log_batch_size: Option<usize>,
+        /// Delay between log batch exports; when unset the SDK (or `OTEL_BLRP_*`) default is used.
+        log_flush_interval: Option<Duration>,
+        list_spans: ListSpans,
+        span_granularity: SpanGranularity,
+        span_name_template: SpanNameTemplate,
+        /// Only every n-th push on a pad starts a span.
+        span_every_n: u64,
+        /// Spans shorter than this are dropped rather than exported.
+        min_span_duration: Duration,
+        log_bridge: LogBridgeKind,
+        /// Minimum level of GStreamer debug messages exported as OTLP logs.
+        log_level: gst::DebugLevel,
+        /// Only debug messages of categories this selects are exported as OTLP logs, besides errors.
+        log_categories: Filter,
+        /// Export a latency histogram alongside the spans.
+        metrics: bool,
+        /// Bucket boundaries of the latency histogram, in nanoseconds.
+        histogram_buckets: Vec<f64>,
+        /// Create spans for context propagation but export nothing, without probing a collector.
+        dry_run: bool,
+        /// Flush spans, metrics and logs when the process receives `SIGTERM`.
+        flush_on_sigterm: bool,
+        /// Only pads of elements with these names are traced, unless empty.
+        elements: Vec<String>,
+        /// Only pads of elements from these factories are traced, unless empty.
+        factories: Vec<String>,
+        /// Only pads of elements whose name this selects are traced.
+        filter: Filter,
+        /// With only `pipeline` set, only pushes out of source elements are traced.
+        flags: LatencyFlags,
+        /// Objects whose push hooks are registered.
+        filters: TracedTypes,
+        /// Pyroscope agent started with the tracer provider.
+        pyroscope: PyroscopeConfig,
+    }
+
+    impl Default for Settings {
+        fn default() -> Self {
+            Self {
+                service_name: "gst.otel".into(),
+                resource_attributes: vec![],
+                exporter: SpanExporterKind::default(),
+                endpoint: None,
+                headers: ExportHeaders::default(),
+                // Short, so an unreachable collector fails fast instead of stalling exports
+                export_timeout: Duration::from_millis(2000),
+                protocol: OtlpProtocol::default(),
+                batch_size: None,
+                schedule_delay: None,
+                log_batch_size: None,
+                log_flush_interval: None,
+                list_spans: ListSpans::default(),
+                span_granularity: SpanGranularity::default(),
+                span_name_template: SpanNameTemplate::default(),
+                span_every_n: 1,
+                min_span_duration: Duration::ZERO,
+                log_bridge: LogBridgeKind::default(),
+                log_level: std::env::var("GST_OTEL_LOG_LEVEL")
+                    .ok()
+                    .and_then(|level| parse_debug_level(&level))
+                    .unwrap_or(gst::DebugLevel::Info),
+                log_categories: Filter::default(),
+                metrics: false,
+                histogram_buckets: default_histogram_buckets(),
+                dry_run: false,
+                flush_on_sigterm: false,
+                elements: vec![],
+                factories: vec![],
+                filter: Filter::default(),
+                flags: LatencyFlags::default(),
+                filters: TracedTypes::default(),
+                pyroscope: PyroscopeConfig::default(),
+            }
+        }
+    }
+
+    impl Settings {
+        fn update_from_params(&mut self, tracer: &gst::Tracer, params: String) {
+            let s = match gst::Structure::from_str(&format!("otel-tracer,{params}")) {
+                Ok(s) => s,
+                Err(err) => {
+                    gst::warning!(
+                        CAT,
+                        obj = tracer,
+                        "failed to parse tracer parameters: {}",
+                        err
+                    );
+                    return;
+                }
+            };
+            if let Ok(v) = s.get::<String>("service-name") {
+                self.service_name = v;
+            }
+            if let Ok(v) = s.get::<String>("resource-attributes") {
+                self.resource_attributes = parse_key_values(&v);
+            }
+            if let Ok(v) = s.get::<String>("headers") {
+                self.headers = ExportHeaders(parse_key_values(&v));
+            }
+            if let Ok(v) = s.get::<String>("exporter") {
+                match v.as_str() {
+                    "otlp" => self.exporter = SpanExporterKind::Otlp,
+                    // Jaeger ingests OTLP natively; the dedicated Jaeger exporter is discontinued
+                    "jaeger" => {
+                        gst::warning!(
+                            CAT,
+                            obj = tracer,
+                            "there is no native jaeger exporter, exporting over OTLP; point \
+                             endpoint at jaeger's OTLP port"
+                        );
+                        self.exporter = SpanExporterKind::Otlp;
+                    }
+                    "zipkin" => self.exporter = SpanExporterKind::Zipkin,
+                    #[cfg(feature = "test-util")]
+                    "memory" => self.exporter = SpanExporterKind::Memory,
+                    _ => gst::warning!(
+                        CAT,
+                        obj = tracer,
+                        "unknown exporter {}, expected otlp, jaeger or zipkin",
+                        v
+                    ),
+                }
+            }
+            if let Ok(v) = s.get::<String>("endpoint") {
+                self.endpoint = Some(v);
+            }
+            if let Ok(v) = s.get::<String>("protocol") {
+                match v.as_str() {
+                    "http" => self.protocol = OtlpProtocol::Http,
+                    "grpc" => self.protocol = OtlpProtocol::Grpc,
+                    _ => gst::warning!(
+                        CAT,
+                        obj = tracer,
+                        "unknown protocol {}, expected http or grpc",
+                        v
+                    ),
+                }
+            }
+            if let Ok(v) = s.get::<i32>("batch-size") {
+                self.batch_size = Some(v.max(1) as usize);
+            }
+            if let Ok(v) = s.get::<i32>("export-timeout-ms") {
+                self.export_timeout = Duration::from_millis(v.max(1) as u64);
+            }
+            if let Ok(v) = s.get::<i32>("schedule-delay-ms") {
+                self.schedule_delay = Some(Duration::from_millis(v.max(0) as u64));
+            }
+            if let Ok(v) = s.get::<i32>("log-batch-size") {
+                self.log_batch_size = Some(v.max(1) as usize);
+            }
+            if let Ok(v) = s.get::<i32>("log-flush-interval-ms") {
+                self.log_flush_interval = Some(Duration::from_millis(v.max(0) as u64));
+            }
+            if let Ok(v) = s.get::<String>("span-name-template") {
+                self.span_name_template = SpanNameTemplate::parse(&v);
+            }
+            if let Ok(v) = s.get::<i32>("span-every-n") {
+                self.span_every_n = v.max(1) as u64;
+            }
+            if let Ok(v) = s.get::<i32>("min-span-duration-ms") {
+                self.min_span_duration = Duration::from_millis(v.max(0) as u64);
+            }
+            if let Ok(v) = s.get::<String>("log-bridge") {
+                match v.as_str() {
+                    "structured" => self.log_bridge = LogBridgeKind::Structured,
+                    "plaintext" => self.log_bridge = LogBridgeKind::Plaintext,
+                    "none" => self.log_bridge = LogBridgeKind::None,
+                    _ => gst::warning!(
+                        CAT,
+                        obj = tracer,
+                        "unknown log-bridge {}, expected structured, plaintext or none",
+                        v
+                    ),
+                }
+            }
+            // `log-level=5` parses as an int, `log-level=info` as a string
+            let log_level = s
+                .get::<String>("log-level")
+                .or_else(|_| s.get::<i32>("log-level").map(|v| v.to_string()));
+            if let Ok(v) = log_level {
+                match parse_debug_level(&v) {
+                    Some(level) => self.log_level = level,
+                    None => gst::warning!(CAT, obj = tracer, "unknown log-level {}", v),
+                }
+            }
+            if let Ok(v) = s.get::<String>("log-categories") {
+                self.log_categories = Filter::new(&v);
+            }
+            if let Ok(v) = s.get::<bool>("metrics") {
+                self.metrics = v;
+            }
+            if let Ok(v) = s.get::<String>("otel-histogram-buckets") {
+                match parse_histogram_buckets(&v) {
+                    Ok(buckets) => self.histogram_buckets = buckets,
+                    Err(err) => gst::warning!(
+                        CAT,
+                        obj = tracer,
+                        "ignoring otel-histogram-buckets {}: {}",
+                        v,
+                        err
+                    ),
+                }
+            }
+            if let Ok(v) = s.get::<bool>("dry-run") {
+                self.dry_run = v;
+            }
+            if let Ok(v) = s.get::<bool>("flush-on-sigterm") {
+                self.flush_on_sigterm = v;
+            }
+            if let Ok(v) = s.get::<String>("elements") {
+                self.elements = v.split(',').map(|name| name.trim().to_string()).collect();
+            }
+            if let Ok(v) = s.get::<String>("factories") {
+                self.factories = v.split(',').map(|name| name.trim().to_string()).collect();
+            }
+            if let Ok(v) = s.get::<String>("filter") {
+                self.filter = Filter::new(&v);
+            }
+            if let Ok(v) = s.get::<String>("pyroscope-server-url") {
+                self.pyroscope.server_url = Some(v);
+            }
+            if let Ok(v) = s.get::<i32>("pyroscope-sample-rate") {
+                match u32::try_from(v) {
+                    Ok(rate) if rate > 0 => self.pyroscope.sample_rate = Some(rate),
+                    _ => gst::warning!(
+                        CAT,
+                        obj = tracer,
+                        "ignoring pyroscope-sample-rate {}, expected a positive rate",
+                        v
+                    ),
+                }
+            }
+            if let Ok(v) = s.get::<String>("pyroscope-tags") {
+                self.pyroscope.tags = Some(parse_key_values(&v));
+            }
+            if let Ok(v) = s.get::<String>("flags") {
+                match LatencyFlags::parse(&v) {
+                    Ok(flags) => self.flags = flags,
+                    Err(flag) => gst::warning!(
+                        CAT,
+                        obj = tracer,
+                        "unknown flag {}, expected pipeline, element or reported",
+                        flag
+                    ),
+                }
+            }
+            if let Ok(v) = s.get::<String>("filters") {
+                match TracedTypes::parse(&v) {
+                    Ok(filters) => self.filters = filters,
+                    Err(name) => gst::warning!(
+                        CAT,
+                        obj = tracer,
+                        "unknown filter {}, expected GstBuffer, GstBufferList or GstEvent",
+                        name
+                    ),
+                }
+            }
+            if let Ok(v) = s.get::<String>("list-spans") {
+                match v.as_str() {
+                    "list" => self.list_spans = ListSpans::List,
+                    "buffer" => self.list_spans = ListSpans::Buffer,
+                    _ => gst::warning!(
+                        CAT,
+                        obj = tracer,
+                        "unknown list-spans {}, expected list or buffer",
+                        v
+                    ),
+                }
+            }
+            if let Ok(v) = s.get::<String>("span-granularity") {
+                match v.as_str() {
+                    "buffer" => self.span_granularity = SpanGranularity::Buffer,
+                    "element" => self.span_granularity = SpanGranularity::Element,
+                    _ => gst::warning!(
+                        CAT,
+                        obj = tracer,
+                        "unknown span-granularity {}, expected buffer or element",
+                        v
+                    ),
+                }
+            }
+        }
+
+        /// Whether `element` passes the `filter`, the `elements`/`factories` allowlists and,
+        /// with `flags=pipeline`, is a source.
+        fn allows(&self, element: &gst::Element) -> bool {
+            if !self.filter.matches(&element.name()) {
+                return false;
+            }
+            if self.flags.pipeline
+                && !self.flags.element
+                && !element.element_flags().contains(gst::ElementFlags::SOURCE)
+            {
+                return false;
+            }
+            if self.elements.is_empty() && self.factories.is_empty() {
+                return true;
+            }
+            self.elements.iter().any(|name| *name == element.name())
+                || element
+                    .factory()
+                    .is_some_and(|f| self.factories.iter().any(|name| *name == f.name()))
+        }
+    }
+
+    fn build_span_exporter(settings: &Settings) -> opentelemetry_otlp::SpanExporter {
+        use opentelemetry_otlp::{WithExportConfig, WithHttpConfig, WithTonicConfig};
+
+        match settings.protocol {
+            OtlpProtocol::Http => {
+                let builder = opentelemetry_otlp::SpanExporter::builder()
+                    .with_http()
+                    .with_headers(settings.headers.http())
+                    .with_timeout(settings.export_timeout);
+                match &settings.endpoint {
+                    Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                    None => builder.build(),
+                }
+            }
+            OtlpProtocol::Grpc => {
+                let _guard = GRPC_RUNTIME.enter();
+                let builder = opentelemetry_otlp::SpanExporter::builder()
+                    .with_tonic()
+                    .with_metadata(settings.headers.grpc())
+                    .with_timeout(settings.export_timeout);
+                match &settings.endpoint {
+                    Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                    None => builder.build(),
+                }
+            }
+        }
+        .expect("Failed to create OTLP exporter")
+    }
+
+    /// Zipkin has no gRPC transport, so `protocol` does not apply. Fails on a malformed
+    /// `endpoint`.
+    fn build_zipkin_exporter(
+        settings: &Settings,
+    ) -> Result<opentelemetry_zipkin::ZipkinExporter, impl std::fmt::Display> {
+        let builder = opentelemetry_zipkin::ZipkinExporter::builder();
+        match &settings.endpoint {
+            Some(endpoint) => builder.with_collector_endpoint(endpoint).build(),
+            None => builder.build(),
+        }
+    }
+
+    /// With HTTP a traces endpoint is mapped to the matching metrics path; any other endpoint
+    /// is left to the exporter's default (or `OTEL_EXPORTER_OTLP_METRICS_ENDPOINT`).
+    fn build_metric_exporter(settings: &Settings) -> opentelemetry_otlp::MetricExporter {
+        use opentelemetry_otlp::{WithExportConfig, WithHttpConfig, WithTonicConfig};
+
+        match settings.protocol {
+            OtlpProtocol::Http => {
+                let builder = opentelemetry_otlp::MetricExporter::builder()
+                    .with_http()
+                    .with_headers(settings.headers.http())
+                    .with_timeout(settings.export_timeout);
+                let endpoint = settings
+                    .endpoint
+                    .as_deref()
+                    .and_then(|endpoint| endpoint.strip_suffix("/v1/traces"));
+                match endpoint {
+                    Some(base) => builder.with_endpoint(format!("{base}/v1/metrics")).build(),
+                    None => builder.build(),
+                }
+            }
+            OtlpProtocol::Grpc => {
+                let _guard = GRPC_RUNTIME.enter();
+                let builder = opentelemetry_otlp::MetricExporter::builder()
+                    .with_tonic()
+                    .with_metadata(settings.headers.grpc())
+                    .with_timeout(settings.export_timeout);
+                match &settings.endpoint {
+                    Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                    None => builder.build(),
+                }
+            }
+        }
+        .expect("Failed to create OTLP metric exporter")
+    }
+
+    /// Like [`build_metric_exporter`], with HTTP mapping a traces endpoint to the matching logs
+    /// path (or leaving it to `OTEL_EXPORTER_OTLP_LOGS_ENDPOINT`).
+    fn build_log_exporter(settings: &Settings) -> opentelemetry_otlp::LogExporter {
+        use opentelemetry_otlp::{WithExportConfig, WithHttpConfig, WithTonicConfig};
+
+        match settings.protocol {
+            OtlpProtocol::Http => {
+                let builder = opentelemetry_otlp::LogExporter::builder()
+                    .with_http()
+                    .with_headers(settings.headers.http())
+                    .with_timeout(settings.export_timeout);
+                let endpoint = settings
+                    .endpoint
+                    .as_deref()
+                    .and_then(|endpoint| endpoint.strip_suffix("/v1/traces"));
+                match endpoint {
+                    Some(base) => builder.with_endpoint(format!("{base}/v1/logs")).build(),
+                    None => builder.build(),
+                }
+            }
+            OtlpProtocol::Grpc => {
+                let _guard = GRPC_RUNTIME.enter();
+                let builder = opentelemetry_otlp::LogExporter::builder()
+                    .with_tonic()
+                    .with_metadata(settings.headers.grpc())
+                    .with_timeout(settings.export_timeout);
+                match &settings.endpoint {
+                    Some(endpoint) => builder.with_endpoint(endpoint).build(),
+                    None => builder.build(),
+                }
+            }
+        }
+        .expect("Failed to create OTLP log exporter")
+    }
+
+    fn build_resource(settings: &Settings) -> Resource {
+        Resource::builder()
+            .with_attributes(
+                settings
+                    .resource_attributes
+                    .iter()
+                    .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+            )
+            // Added last so it takes precedence over a `service.name` attribute
+            .with_attribute(KeyValue::new("service.name", settings.service_name.clone()))
+            .build()
+    }
+
+    /// Spans are exported in batches off the streaming threads; a longer schedule delay or
+    /// larger batch lowers export overhead at the cost of spans showing up later.
+    fn build_batch_processor<E: SpanExporter + 'static>(
+        settings: &Settings,
+        exporter: E,
+    ) -> BatchSpanProcessor {
+        let mut config = BatchConfigBuilder::default();
+        if let Some(batch_size) = settings.batch_size {
+            config = config.with_max_export_batch_size(batch_size);
+        }
+        if let Some(schedule_delay) = settings.schedule_delay {
+            config = config.with_scheduled_delay(schedule_delay);
+        }
+        BatchSpanProcessor::builder(exporter)
+            .with_batch_config(config.build())
+            .build()
+    }
+
+    /// Forwards to `inner` only the spans lasting at least `min`, so fast pushes do not drown
+    /// out the slow ones in the backend.
+    ///
+    /// Spans cannot be discarded once started, so they are dropped as they end instead; their
+    /// children are still exported, parented to a span the backend never receives.
+    #[derive(Debug)]
+    pub(super) struct MinDurationSpanProcessor<P> {
+        pub(super) inner: P,
+        pub(super) min: Duration,
+    }
+
+    impl<P: SpanProcessor> SpanProcessor for MinDurationSpanProcessor<P> {
+        fn on_start(&self, span: &mut opentelemetry_sdk::trace::Span, cx: &opentelemetry::Context) {
+            self.inner.on_start(span, cx);
+        }
+
+        fn on_end(&self, span: SpanData) {
+            let duration = span
+                .end_time
+                .duration_since(span.start_time)
+                .unwrap_or_default();
+            if duration >= self.min {
+                self.inner.on_end(span);
+            }
+        }
+
+        fn force_flush(&self) -> opentelemetry_sdk::error::OTelSdkResult {
+            self.inner.force_flush()
+        }
+
+        fn shutdown_with_timeout(
+            &self,
+            timeout: Duration,
+        ) -> opentelemetry_sdk::error::OTelSdkResult {
+            self.inner.shutdown_with_timeout(timeout)
+        }
+
+        fn set_resource(&mut self, resource: &Resource) {
+            self.inner.set_resource(resource);
+        }
+    }
+
+    /// Logs are exported in batches like spans, tuned separately as log-heavy pipelines may
+    /// want larger batches than spans.
+    fn build_log_batch_config(settings: &Settings) -> LogBatchConfig {
+        let mut config = LogBatchConfigBuilder::default();
+        if let Some(batch_size) = settings.log_batch_size {
+            config = config.with_max_export_batch_size(batch_size);
+        }
+        if let Some(flush_interval) = settings.log_flush_interval {
+            config = config.with_scheduled_delay(flush_interval);
+        }
+        config.build()
+    }
+
+    /// Upper bound on how long a flush may block EOS handling or shutdown.
+    const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Export any buffered spans, metrics and logs, so short pipelines are not lost.
+    ///
+    /// The flush runs on a helper thread and is abandoned after [`FLUSH_TIMEOUT`], so an
+    /// unreachable collector cannot hang the pipeline or the process on exit.
+    fn flush_spans() {
+        let Some(tracer_provider) = INIT_ONCE.get() else {
+            return;
+        };
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        let spawned = std::thread::Builder::new()
+            .name("otel-flush".into())
+            .spawn(move || {
+                let mut result = tracer_provider.force_flush();
+                if let Some(meter_provider) = METER_PROVIDER.get() {
+                    result = result.and(meter_provider.force_flush());
+                }
+                if let Some(logger_provider) = LOGGER_PROVIDER.get() {
+                    result = result.and(logger_provider.force_flush());
+                }
+                let _ = done_tx.send(result);
+            });
+        if let Err(err) = spawned {
+            gst::warning!(CAT, "Failed to spawn span flush thread: {}", err);
+            return;
+        }
+        match done_rx.recv_timeout(FLUSH_TIMEOUT) {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => gst::warning!(CAT, "Failed to flush spans: {}", err),
+            Err(_) => gst::warning!(CAT, "Timed out flushing spans after {:?}", FLUSH_TIMEOUT),
+        }
+    }
+
+    /// Spans kept by `exporter=memory` so far, flushing any still batched first.
+    ///
+    /// Each span becomes a `span` structure with its `name`, `trace-id`, `span-id`,
+    /// `parent-span-id` and `links`, an array of `link` structures with the `trace-id` and
+    /// `span-id` linked to, plus a field per attribute, so tests linking the plugin can read them.
+    #[cfg(feature = "test-util")]
+    fn finished_spans() -> gst::Array {
+        flush_spans();
+        let spans = MEMORY_EXPORTER
+            .get()
+            .and_then(|exporter| exporter.get_finished_spans().ok())
+            .unwrap_or_default();
+        gst::Array::from_values(spans.iter().map(|span| {
+            let context = &span.span_context;
+            let mut s = gst::Structure::builder("span")
+                .field("name", span.name.as_ref())
+                .field("trace-id", context.trace_id().to_string())
+                .field("span-id", context.span_id().to_string())
+                .field("parent-span-id", span.parent_span_id.to_string())
+                .field(
+                    "links",
+                    gst::Array::from_values(span.links.links.iter().map(|link| {
+                        gst::Structure::builder("link")
+                            .field("trace-id", link.span_context.trace_id().to_string())
+                            .field("span-id", link.span_context.span_id().to_string())
+                            .build()
+                            .to_send_value()
+                    })),
+                )
+                .build();
+            for kv in &span.attributes {
+                match &kv.value {
+                    opentelemetry::Value::I64(v) => s.set(kv.key.as_str(), *v),
+                    opentelemetry::Value::F64(v) => s.set(kv.key.as_str(), *v),
+                    opentelemetry::Value::Bool(v) => s.set(kv.key.as_str(), *v),
+                    v => s.set(kv.key.as_str(), v.to_string()),
+                }
+            }
+            s.to_send_value()
+        }))
+    }
+
+    /// Upper bound on the one-shot connect probe to the collector when exporters are set up.
+    const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// The `host:port` to connect to for an endpoint URL, with the scheme's default port.
+    pub(super) fn endpoint_address(endpoint: &str) -> Option<String> {
+        let (rest, default_port) = match endpoint.strip_prefix("https://") {
+            Some(rest) => (rest, 443),
+            None => (endpoint.strip_prefix("http://").unwrap_or(endpoint), 80),
+        };
+        let authority = rest.split('/').next().filter(|a| !a.is_empty())?;
+        let authority = authority.rsplit('@').next()?;
+        // The colons of a bracketed IPv6 literal are not a port separator
+        let has_port = match (authority.rfind(':'), authority.rfind(']')) {
+            (Some(colon), Some(bracket)) => colon > bracket,
+            (colon, None) => colon.is_some(),
+            (None, Some(_)) => false,
+        };
+        Some(if has_port {
+            authority.to_string()
+        } else {
+            format!("{authority}:{default_port}")
+        })
+    }
+
+    /// Whether a TCP connection to the span endpoint opens within [`PROBE_TIMEOUT`].
+    fn collector_reachable(settings: &Settings) -> bool {
+        let (env_vars, default_endpoint): (&[&str], _) =
+            match (settings.exporter, settings.protocol) {
+                (SpanExporterKind::Zipkin, _) => (
+                    &["OTEL_EXPORTER_ZIPKIN_ENDPOINT"],
+                    "http://127.0.0.1:9411/api/v2/spans",
+                ),
+                #[cfg(feature = "test-util")]
+                (SpanExporterKind::Memory, _) => return true,
+                (SpanExporterKind::Otlp, protocol) => (
+                    &[
+                        "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+                        "OTEL_EXPORTER_OTLP_ENDPOINT",
+                    ],
+                    match protocol {
+                        OtlpProtocol::Http => "http://localhost:4318",
+                        OtlpProtocol::Grpc => "http://localhost:4317",
+                    },
+                ),
+            };
+        let endpoint = settings
+            .endpoint
+            .clone()
+            .or_else(|| env_vars.iter().find_map(|var| std::env::var(var).ok()))
+            .unwrap_or_else(|| default_endpoint.to_string());
+        let Some(address) = endpoint_address(&endpoint) else {
+            gst::warning!(CAT, "Cannot probe invalid endpoint {}", endpoint);
+            return false;
+        };
+        match std::net::ToSocketAddrs::to_socket_addrs(address.as_str()) {
+            Ok(mut addrs) => {
+                addrs.any(|addr| std::net::TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok())
+            }
+            Err(err) => {
+                gst::warning!(CAT, "Cannot resolve collector {}: {}", address, err);
+                false
+            }
+        }
     }
 
-    /// Initialize both OTLP trace and metric exporters once
-    fn init_otlp() -> global::BoxedTracer {
+    /// Initialize both OTLP trace and metric exporters once, returning whether they export.
+    ///
+    /// With `dry-run`, or when the collector cannot be reached, spans are still created for
+    /// context propagation but nothing is exported, so the media path is never held up by it.
+    fn init_otlp(settings: &Settings) -> bool {
         INIT_ONCE.get_or_init(|| {
-            // First, create a OTLP exporter builder. Configure it as you need.
-            let otlp_exporter = opentelemetry_otlp::SpanExporter::builder()
-                .with_http()
-                .build()
-                .expect("Failed to create OTLP exporter");
+            let exporting = if settings.dry_run {
+                gst::info!(CAT, "dry-run set, not exporting spans, metrics or logs");
+                false
+            } else if settings.exporter.in_memory() {
+                gst::info!(
+                    CAT,
+                    "keeping spans in memory, not exporting metrics or logs"
+                );
+                false
+            } else if !collector_reachable(settings) {
+                gst::warning!(
+                    CAT,
+                    "Collector unreachable, not exporting spans, metrics or logs"
+                );
+                false
+            } else {
+                true
+            };
+            let _ = EXPORTING.set(exporting);
 
             let pyroscope_processor = PyroscopeSpanProcessor::default();
-            pyroscope_processor.create_first_agent(vec![("service.name", "gst.pyroscope")]);
+            pyroscope_processor.create_first_agent(
+                &settings.pyroscope,
+                vec![("service.name", settings.service_name.as_str())],
+            );
 
             // Tracing pipeline
-            let tracer_provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+            let mut tracer_provider = SdkTracerProvider::builder()
                 .with_sampler(opentelemetry_sdk::trace::Sampler::ParentBased(Box::new(
                     opentelemetry_sdk::trace::Sampler::TraceIdRatioBased(1.0),
                 )))
                 .with_span_processor(pyroscope_processor)
-                .with_resource(
-                    Resource::builder()
-                        .with_attributes(vec![KeyValue::new("service.name", "gst.pyroscope")])
-                        .build(),
-                )
-                .with_batch_exporter(otlp_exporter)
-                .build();
-            global::set_tracer_provider(tracer_provider);
-
-            gst::info!(CAT, "OTLP exporters initialized");
+                .with_resource(build_resource(settings));
+            let processor = if !exporting && !settings.exporter.in_memory() {
+                None
+            } else {
+                match settings.exporter {
+                    SpanExporterKind::Otlp => Some(build_batch_processor(
+                        settings,
+                        build_span_exporter(settings),
+                    )),
+                    // A malformed endpoint must not take the application down
+                    SpanExporterKind::Zipkin => match build_zipkin_exporter(settings) {
+                        Ok(exporter) => Some(build_batch_processor(settings, exporter)),
+                        Err(err) => {
+                            gst::error!(
+                                CAT,
+                                "Failed to create Zipkin exporter, not exporting spans: {}",
+                                err
+                            );
+                            None
+                        }
+                    },
+                    #[cfg(feature = "test-util")]
+                    SpanExporterKind::Memory => Some(build_batch_processor(
+                        settings,
+                        MEMORY_EXPORTER.get_or_init(Default::default).clone(),
+                    )),
+                }
+            };
+            if let Some(processor) = processor {
+                tracer_provider = if settings.min_span_duration.is_zero() {
+                    tracer_provider.with_span_processor(processor)
+                } else {
+                    tracer_provider.with_span_processor(MinDurationSpanProcessor {
+                        inner: processor,
+                        min: settings.min_span_duration,
+                    })
+                };
+            }
+            let tracer_provider = tracer_provider.build();
+            global::set_tracer_provider(tracer_provider.clone());
+
+            // Metrics pipeline
+            if settings.metrics && exporting {
+                let meter_provider = SdkMeterProvider::builder()
+                    .with_periodic_exporter(build_metric_exporter(settings))
+                    .with_resource(build_resource(settings))
+                    .build();
+                let histogram = meter_provider
+                    .meter("otel-tracer")
+                    .u64_histogram("gst.element.latency")
+                    .with_unit("ns")
+                    .with_description(
+                        "Time from pushing a buffer on a src pad until the push returns",
+                    )
+                    .with_boundaries(settings.histogram_buckets.clone())
+                    .build();
+                global::set_meter_provider(meter_provider.clone());
+                let _ = METER_PROVIDER.set(meter_provider);
+                let _ = LATENCY_HISTOGRAM.set(histogram);
+                meter_provider
+                    .meter("otel-tracer")
+                    .u64_observable_counter("gst.tracer.spans")
+                    .with_description("Spans started for pushes")
+                    .with_callback(|observer| {
+                        observer.observe(SPANS_STARTED.load(Ordering::Relaxed), &[])
+                    })
+                    .build();
+            }
 
-            global::tracer("otel-tracer")
+            gst::info!(CAT, "OTLP exporters initialized with {:?}", settings);
+            tracer_provider
         });
-        global::tracer("otel-tracer")
+        EXPORTING.get().copied().unwrap_or(false)
     }
     #[repr(C)]
     pub struct GstOtelSpanBuf {
@@ -230,111 +1286,296 @@ mod imp {
         })
     }
 
+    /// The state and hook bodies of `otel-tracer`, usable by a tracer that registers the hooks
+    /// itself.
     #[derive(Default)]
-    pub struct OtelTracerImpl;
-
-    #[glib::object_subclass]
-    impl ObjectSubclass for OtelTracerImpl {
-        const NAME: &'static str = "otel-tracer";
-        type Type = TelemetryTracer;
-        type ParentType = gst::Tracer;
+    pub struct OtelTracerCore {
+        settings: RwLock<Settings>,
+        /// Copy of `Settings::span_every_n`, read on every push without taking the lock.
+        span_every_n: AtomicU64,
+        /// Whether `Settings::span_granularity` is `Element`, read on every push and event.
+        element_spans: AtomicBool,
     }
 
-    impl ObjectImpl for OtelTracerImpl {
-        fn constructed(&self) {
-            self.parent_constructed();
-            let binding = self.obj();
-            let tracer_obj: &gst::Tracer = binding.upcast_ref();
+    impl OtelTracerCore {
+        /// Apply the `params` of `tracer` on construction.
+        pub fn configure(&self, tracer: &gst::Tracer) {
+            if let Some(params) = tracer.property::<Option<String>>("params") {
+                let mut settings = self.settings.write().unwrap();
+                settings.update_from_params(tracer, params);
+            }
+            let settings = self.settings.read().unwrap();
+            self.span_every_n
+                .store(settings.span_every_n, Ordering::Relaxed);
+            self.element_spans.store(
+                settings.span_granularity == SpanGranularity::Element,
+                Ordering::Relaxed,
+            );
+            if settings.flush_on_sigterm {
+                // Bounded by FLUSH_TIMEOUT, within the usual 30s termination grace period
+                on_sigterm(flush_spans);
+            }
+        }
 
-            // this registers the API type
-            // gst_span_buf_api_get_type();
-            // this registers the actual GstMetaInfo (size + init/free/transform)
-            // gst_span_buf_get_info();
+        /// Objects whose push hooks should be registered, per the `filters` parameter.
+        pub fn filters(&self) -> TracedTypes {
+            self.settings.read().unwrap().filters
+        }
 
-            gst::info!(CAT, "OtelTracerImpl constructed");
+        /// Number of spans started so far, by any tracer.
+        pub fn span_count(&self) -> u64 {
+            SPANS_STARTED.load(Ordering::Relaxed)
+        }
 
-            self.register_hook(TracerHook::ElementNew);
+        /// Parent spans of sources on `traceparent`, or on nothing when `None`.
+        ///
+        /// Returns whether `traceparent` was valid.
+        pub fn set_parent_context(&self, traceparent: Option<&str>) -> bool {
+            set_application_parent(traceparent)
+        }
 
-            // Omit ffi hooks for now, we will use safe Rust API to start with
-            //   as its easier to implement & we can use the unsafe API for performance-critical parts later.
-            unsafe extern "C" fn do_push_buffer_pre(
-                _tracer: *mut gst::Tracer,
-                ts: u64,
-                pad: *mut gst::ffi::GstPad,
-                buf_ptr: *mut gst::ffi::GstBuffer,
-            ) {
-                // gst::info!(
-                //     CAT,
-                //     "do_push_buffer_pre called for pad {} at ts {} with buffer {:?}",
-                //     gst::Pad::from_glib_borrow(pad).name(),
-                //     ts,
-                //     gst::Buffer::from_glib_borrow(buffer)
-                // );
-                // This function is called before a buffer is pushed to a pad.
-                // We will use it to start a span for the pad.
-                let pad = gst::Pad::from_glib_borrow(pad);
-                let buffer = gst::Buffer::from_glib_borrow(buf_ptr);
-                pad_push_pre(ts, &pad, &buffer, buf_ptr);
+        /// Whether pushes from `pad` are traced, per the `filter`, `elements`/`factories` and
+        /// `flags`.
+        ///
+        /// The decision is resolved once per pad and cached in its qdata.
+        fn is_traced(&self, pad: *mut gst::ffi::GstPad) -> bool {
+            const TRACED: usize = 1;
+            const SKIPPED: usize = 2;
+            unsafe {
+                let obj = pad as *mut gobject_sys::GObject;
+                match glib::gobject_ffi::g_object_get_qdata(obj, *QUARK_TRACED) as usize {
+                    TRACED => return true,
+                    SKIPPED => return false,
+                    _ => {}
+                }
+                // Not cached until the pad has a parent element to decide on
+                let Some(element) = gst::Pad::from_glib_borrow(pad).parent_element() else {
+                    return false;
+                };
+                let traced = self.settings.read().unwrap().allows(&element);
+                let decision = if traced { TRACED } else { SKIPPED };
+                glib::gobject_ffi::g_object_set_qdata(obj, *QUARK_TRACED, decision as gpointer);
+                traced
             }
+        }
 
-            // unsafe extern "C" fn do_push_event_pre(
-            //     _tracer: *mut gst::Tracer,
-            //     event_ptr: *mut gst::ffi::GstEvent,
-            //     pad: *mut gst::ffi::GstPad,
-            // ) {
-            // }
-
-            unsafe extern "C" fn do_push_buffer_post(
-                _tracer: *mut gst::Tracer,
-                ts: u64,
-                pad: *mut gst::ffi::GstPad,
-            ) {
-                // gst::info!(
-                //     CAT,
-                //     "do_push_buffer_post called for pad {} at ts {}",
-                //     gst::Pad::from_glib_borrow(pad).name(),
-                //     ts
-                // );
-                let peer = gst::ffi::gst_pad_get_peer(pad);
-                let peer_pad = gst::Pad::from_glib_borrow(peer);
-                let self_pad = gst::Pad::from_glib_borrow(pad);
-                pad_push_post(ts, &peer_pad, &self_pad);
+        /// Count a push on `pad` and tell whether it should be traced: the first and then
+        /// every `span-every-n`th push.
+        fn sample_push(&self, pad: *mut gst::ffi::GstPad) -> bool {
+            let n = self.span_every_n.load(Ordering::Relaxed);
+            if n <= 1 {
+                return true;
             }
-
+            // A src pad is pushed from a single streaming thread; the counter lives in the
+            // qdata pointer itself to avoid an allocation.
             unsafe {
-                let obj = tracer_obj.to_glib_none().0;
-                gst::ffi::gst_tracing_register_hook(
+                let obj = pad as *mut gobject_sys::GObject;
+                let count = glib::gobject_ffi::g_object_get_qdata(obj, *QUARK_PUSH_COUNT) as u64;
+                glib::gobject_ffi::g_object_set_qdata(
                     obj,
-                    c"pad-push-pre".as_ptr() as *const _,
-                    std::mem::transmute::<*const (), GCallback>(do_push_buffer_pre as *const ()),
+                    *QUARK_PUSH_COUNT,
+                    ((count + 1) % n) as usize as gpointer,
                 );
-                // gst::ffi::gst_tracing_register_hook(
-                //     obj,
-                //     c"pad-push-event-pre".as_ptr() as *const _,
-                //     std::mem::transmute::<_, GCallback>(do_push_event_pre as *const ()),
-                // );
-                gst::ffi::gst_tracing_register_hook(
-                    obj,
-                    c"pad-push-post".as_ptr() as *const _,
-                    std::mem::transmute::<*const (), GCallback>(do_push_buffer_post as *const ()),
+                count == 0
+            }
+        }
+
+        /// Handle the pad-push-pre hook, starting a span for the push of `buf_ptr` from `pad`.
+        ///
+        /// # Safety
+        ///
+        /// `pad` and `buf_ptr` must be those the hook was called with.
+        pub unsafe fn pad_push_pre(
+            &self,
+            ts: u64,
+            pad: *mut gst::ffi::GstPad,
+            buf_ptr: *mut gst::ffi::GstBuffer,
+        ) {
+            if !self.is_traced(pad) {
+                return;
+            }
+            if self.element_spans.load(Ordering::Relaxed) {
+                let size = gst::ffi::gst_buffer_get_size(buf_ptr) as u64;
+                element_push(self, ts, &gst::Pad::from_glib_borrow(pad), 1, size);
+                return;
+            }
+            let seq = next_buffer_seq(pad, 1);
+            if !self.sample_push(pad) {
+                return;
+            }
+            let pad = gst::Pad::from_glib_borrow(pad);
+            let buffer = gst::Buffer::from_glib_borrow(buf_ptr);
+            let settings = self.settings.read().unwrap();
+            pad_push_pre(
+                ts,
+                &pad,
+                &buffer,
+                buf_ptr,
+                None,
+                seq,
+                &settings.span_name_template,
+            );
+        }
+
+        /// Handle the pad-push-list-pre hook, starting a span for the push of `list_ptr` from
+        /// `pad`.
+        ///
+        /// # Safety
+        ///
+        /// `pad` and `list_ptr` must be those the hook was called with.
+        pub unsafe fn pad_push_list_pre(
+            &self,
+            ts: u64,
+            pad: *mut gst::ffi::GstPad,
+            list_ptr: *mut gst::ffi::GstBufferList,
+        ) {
+            let length = gst::ffi::gst_buffer_list_length(list_ptr) as usize;
+            if length == 0 || !self.is_traced(pad) {
+                return;
+            }
+            if self.element_spans.load(Ordering::Relaxed) {
+                let size = gst::BufferListRef::from_ptr(list_ptr).calculate_size() as u64;
+                element_push(
+                    self,
+                    ts,
+                    &gst::Pad::from_glib_borrow(pad),
+                    length as u64,
+                    size,
                 );
+                return;
+            }
+            let seq = next_buffer_seq(pad, length as u64);
+            if !self.sample_push(pad) {
+                return;
+            }
+            // The list span is propagated through the first buffer, like a single push.
+            let buf_ptr = gst::ffi::gst_buffer_list_get(list_ptr, 0);
+            let pad = gst::Pad::from_glib_borrow(pad);
+            let buffer = gst::Buffer::from_glib_borrow(buf_ptr);
+            let settings = self.settings.read().unwrap();
+            pad_push_pre(
+                ts,
+                &pad,
+                &buffer,
+                buf_ptr,
+                Some(length),
+                seq,
+                &settings.span_name_template,
+            );
+
+            if settings.list_spans == ListSpans::Buffer {
+                start_list_buffer_spans(ts, &pad, gst::BufferListRef::from_ptr(list_ptr));
             }
         }
-    }
 
-    impl GstObjectImpl for OtelTracerImpl {}
-    impl TracerImpl for OtelTracerImpl {
-        fn element_new(&self, _ts: u64, element: &gst::Element) {
+        /// Handle the pad-push-event-pre hook, carrying traceparents across process boundaries
+        /// and recording events on the spans they pass through.
+        ///
+        /// # Safety
+        ///
+        /// `pad` and `event_ptr` must be those the hook was called with.
+        pub unsafe fn pad_push_event_pre(
+            &self,
+            ts: u64,
+            pad: *mut gst::ffi::GstPad,
+            event_ptr: *mut gst::ffi::GstEvent,
+        ) {
+            let event_type = (*event_ptr).type_;
+            if event_type == gst::ffi::GST_EVENT_CUSTOM_DOWNSTREAM {
+                let pad = gst::Pad::from_glib_borrow(pad);
+                let event = gst::Event::from_glib_borrow(event_ptr);
+                pad_push_event_pre(&pad, &event);
+            } else if SPAN_EVENT_TYPES.contains(&event_type) {
+                if !self.is_traced(pad) {
+                    return;
+                }
+                let pad = gst::Pad::from_glib_borrow(pad);
+                if !self.element_spans.load(Ordering::Relaxed) {
+                    add_span_event(ts, &pad, event_ptr);
+                } else if event_type == gst::ffi::GST_EVENT_EOS {
+                    element_eos(ts, &pad);
+                }
+            }
+        }
+
+        /// Handle the pad-push-post and pad-push-list-post hooks, ending the span of the push
+        /// from `pad` with its result `res`.
+        ///
+        /// # Safety
+        ///
+        /// `pad` must be the pad the hook was called with.
+        pub unsafe fn pad_push_post(
+            &self,
+            ts: u64,
+            pad: *mut gst::ffi::GstPad,
+            res: gst::ffi::GstFlowReturn,
+        ) {
+            let self_pad = gst::Pad::from_glib_borrow(pad);
+            if self_pad.is::<gst::ProxyPad>() {
+                return;
+            }
+            let Some(peer_pad) = real_peer(&self_pad) else {
+                // Unlinked during the push; `pad-unlink-post` already ended the span.
+                return;
+            };
+            pad_push_post(ts, &peer_pad, &self_pad, gst::FlowReturn::from_glib(res));
+        }
+
+        /// Handle the pad-unlink-post hook, ending the span left open on `sink_pad`.
+        ///
+        /// # Safety
+        ///
+        /// `src_pad` and `sink_pad` must be those the hook was called with.
+        pub unsafe fn pad_unlink_post(
+            &self,
+            ts: u64,
+            src_pad: *mut gst::ffi::GstPad,
+            sink_pad: *mut gst::ffi::GstPad,
+        ) {
+            // Relinked pads start a new sequence
+            reset_buffer_seq(src_pad);
+            // Spans are kept on the real pad behind a ghost pad
+            let sink_pad = real_pad(gst::Pad::from_glib_none(sink_pad));
+            if self.element_spans.load(Ordering::Relaxed) {
+                // The element was cut off from upstream, e.g. a branch being removed
+                if let Some(element) = sink_pad.parent_element() {
+                    element_session(&element).end(ts);
+                }
+                return;
+            }
+            end_sink_span(ts, &sink_pad, vec![], Some("pad unlinked".into()));
+        }
+
+        /// Handle the element-new hook, setting up the exporters and the log bridge when the
+        /// first pipeline is created.
+        pub fn element_new(&self, element: &gst::Element) {
             // Not performance sensitive; so we use the safe hook instead.
             if element.is::<gst::Pipeline>() {
                 PIPELINE_INIT_ONCE.get_or_init(|| {
-                    init_otlp();
-
-                    let log_provider = init_logs_otlp();
-                    let logger = log_provider.logger("otel-tracer");
+                    let settings = self.settings.read().unwrap();
+                    let exporting = init_otlp(&settings);
 
                     // Create a bridge to handle GStreamer logs
-                    let bridge_clone = Box::new(StructuredBridge::new(logger));
+                    let bridge_clone: Box<dyn LogBridge> = match settings.log_bridge {
+                        // Keep the default log handler rather than exporting logs nowhere
+                        LogBridgeKind::Structured if !exporting => return,
+                        LogBridgeKind::Structured => {
+                            let log_provider = init_logs_otlp(
+                                &settings.service_name,
+                                build_log_exporter(&settings),
+                                build_log_batch_config(&settings),
+                            );
+                            let logger = log_provider.logger("otel-tracer");
+                            let _ = LOGGER_PROVIDER.set(log_provider);
+                            Box::new(StructuredBridge::new(
+                                logger,
+                                settings.log_level,
+                                settings.log_categories.clone(),
+                            ))
+                        }
+                        LogBridgeKind::Plaintext => Box::new(PlaintextBridge::new()),
+                        LogBridgeKind::None => return,
+                    };
 
                     gst::log::remove_default_log_function();
                     gst::log::add_log_function(move |cat, lvl, file, func, line, obj, msg| {
@@ -350,12 +1591,228 @@ mod imp {
                             .span_id()
                             .to_string();
 
-                        bridge_clone.log_message(
-                            &cat, lvl, file, func, line, msg, obj, &trace_id, &span_id,
-                        );
-                    });
-                });
-            }
+                        bridge_clone.log_message(
+                            &cat, lvl, file, func, line, msg, obj, &trace_id, &span_id,
+                        );
+                    });
+                });
+            }
+        }
+
+        /// Handle the element-post-message-pre hook, flushing spans when a pipeline reaches EOS.
+        pub fn element_post_message_pre(&self, element: &gst::Element, msg: &gst::Message) {
+            if msg.type_() == gst::MessageType::Eos && element.is::<gst::Pipeline>() {
+                gst::debug!(
+                    CAT,
+                    obj = element,
+                    "Pipeline {} reached EOS",
+                    element.name()
+                );
+                flush_spans();
+            }
+        }
+
+        /// Flush the spans started so far.
+        pub fn dispose(&self) {
+            flush_spans();
+        }
+
+        /// Register the pad hooks of `tracer_obj`, a `TelemetryTracer`, for the objects in
+        /// `types`.
+        fn register_hooks(tracer_obj: &gst::Tracer, types: TracedTypes) {
+            unsafe extern "C" fn do_push_buffer_pre(
+                tracer: *mut gst::ffi::GstTracer,
+                ts: u64,
+                pad: *mut gst::ffi::GstPad,
+                buf_ptr: *mut gst::ffi::GstBuffer,
+            ) {
+                OtelTracerImpl::core_from_ptr(tracer).pad_push_pre(ts, pad, buf_ptr);
+            }
+
+            unsafe extern "C" fn do_push_list_pre(
+                tracer: *mut gst::ffi::GstTracer,
+                ts: u64,
+                pad: *mut gst::ffi::GstPad,
+                list_ptr: *mut gst::ffi::GstBufferList,
+            ) {
+                OtelTracerImpl::core_from_ptr(tracer).pad_push_list_pre(ts, pad, list_ptr);
+            }
+
+            unsafe extern "C" fn do_push_event_pre(
+                tracer: *mut gst::ffi::GstTracer,
+                ts: u64,
+                pad: *mut gst::ffi::GstPad,
+                event_ptr: *mut gst::ffi::GstEvent,
+            ) {
+                OtelTracerImpl::core_from_ptr(tracer).pad_push_event_pre(ts, pad, event_ptr);
+            }
+
+            unsafe extern "C" fn do_push_buffer_post(
+                tracer: *mut gst::ffi::GstTracer,
+                ts: u64,
+                pad: *mut gst::ffi::GstPad,
+                res: gst::ffi::GstFlowReturn,
+            ) {
+                OtelTracerImpl::core_from_ptr(tracer).pad_push_post(ts, pad, res);
+            }
+
+            unsafe extern "C" fn do_pad_unlink_post(
+                tracer: *mut gst::ffi::GstTracer,
+                ts: u64,
+                src_pad: *mut gst::ffi::GstPad,
+                sink_pad: *mut gst::ffi::GstPad,
+                _result: glib::ffi::gboolean,
+            ) {
+                OtelTracerImpl::core_from_ptr(tracer).pad_unlink_post(ts, src_pad, sink_pad);
+            }
+
+            unsafe {
+                let obj = tracer_obj.to_glib_none().0;
+                if types.buffers {
+                    gst::ffi::gst_tracing_register_hook(
+                        obj,
+                        c"pad-push-pre".as_ptr() as *const _,
+                        std::mem::transmute::<*const (), GCallback>(
+                            do_push_buffer_pre as *const (),
+                        ),
+                    );
+                    gst::ffi::gst_tracing_register_hook(
+                        obj,
+                        c"pad-push-post".as_ptr() as *const _,
+                        std::mem::transmute::<*const (), GCallback>(
+                            do_push_buffer_post as *const (),
+                        ),
+                    );
+                }
+                if types.buffer_lists {
+                    gst::ffi::gst_tracing_register_hook(
+                        obj,
+                        c"pad-push-list-pre".as_ptr() as *const _,
+                        std::mem::transmute::<*const (), GCallback>(do_push_list_pre as *const ()),
+                    );
+                    // The list post hook has the same signature as the buffer one
+                    gst::ffi::gst_tracing_register_hook(
+                        obj,
+                        c"pad-push-list-post".as_ptr() as *const _,
+                        std::mem::transmute::<*const (), GCallback>(
+                            do_push_buffer_post as *const (),
+                        ),
+                    );
+                }
+                // Also carries traceparents across process boundaries
+                if types.events {
+                    gst::ffi::gst_tracing_register_hook(
+                        obj,
+                        c"pad-push-event-pre".as_ptr() as *const _,
+                        std::mem::transmute::<*const (), GCallback>(do_push_event_pre as *const ()),
+                    );
+                }
+                gst::ffi::gst_tracing_register_hook(
+                    obj,
+                    c"pad-unlink-post".as_ptr() as *const _,
+                    std::mem::transmute::<*const (), GCallback>(do_pad_unlink_post as *const ()),
+                );
+            }
+        }
+    }
+
+    #[derive(Default)]
+    pub struct OtelTracerImpl {
+        core: OtelTracerCore,
+    }
+
+    impl OtelTracerImpl {
+        /// # Safety
+        ///
+        /// `tracer` must point to a live `TelemetryTracer`, which holds for the duration of any
+        /// hook registered in `OtelTracerCore::register_hooks`.
+        unsafe fn core_from_ptr<'a>(tracer: *mut gst::ffi::GstTracer) -> &'a OtelTracerCore {
+            let tracer = gst::Tracer::from_glib_borrow(tracer);
+            let core: *const OtelTracerCore = &tracer
+                .unsafe_cast_ref::<super::TelemetryTracer>()
+                .imp()
+                .core;
+            &*core
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for OtelTracerImpl {
+        const NAME: &'static str = "otel-tracer";
+        type Type = TelemetryTracer;
+        type ParentType = gst::Tracer;
+    }
+
+    impl ObjectImpl for OtelTracerImpl {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<glib::subclass::Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                #[allow(unused_mut)]
+                let mut signals = vec![
+                    glib::subclass::Signal::builder("request-span-count")
+                        .flags(glib::SignalFlags::ACTION)
+                        .return_type::<u64>()
+                        .class_handler(|_, args| {
+                            let tracer = args[0].get::<TelemetryTracer>().expect("signal arg");
+                            Some(tracer.imp().core.span_count().to_value())
+                        })
+                        .build(),
+                    glib::subclass::Signal::builder("request-set-parent-context")
+                        .flags(glib::SignalFlags::ACTION)
+                        .param_types([String::static_type()])
+                        .return_type::<bool>()
+                        .class_handler(|_, args| {
+                            let tracer = args[0].get::<TelemetryTracer>().expect("signal arg");
+                            let traceparent = args[1].get::<Option<String>>().ok().flatten();
+                            let set = tracer.imp().core.set_parent_context(traceparent.as_deref());
+                            Some(set.to_value())
+                        })
+                        .build(),
+                ];
+                #[cfg(feature = "test-util")]
+                signals.push(
+                    glib::subclass::Signal::builder("request-finished-spans")
+                        .flags(glib::SignalFlags::ACTION)
+                        .return_type::<gst::Array>()
+                        .class_handler(|_, _| Some(finished_spans().to_value()))
+                        .build(),
+                );
+                signals
+            })
+        }
+
+        fn constructed(&self) {
+            self.parent_constructed();
+            let binding = self.obj();
+            let tracer_obj: &gst::Tracer = binding.upcast_ref();
+
+            // this registers the API type
+            // gst_span_buf_api_get_type();
+            // this registers the actual GstMetaInfo (size + init/free/transform)
+            // gst_span_buf_get_info();
+
+            self.core.configure(tracer_obj);
+
+            gst::info!(CAT, "OtelTracerImpl constructed");
+
+            self.register_hook(TracerHook::ElementNew);
+            self.register_hook(TracerHook::ElementPostMessagePre);
+            OtelTracerCore::register_hooks(tracer_obj, self.core.filters());
+        }
+
+        fn dispose(&self) {
+            self.core.dispose();
+        }
+    }
+
+    impl GstObjectImpl for OtelTracerImpl {}
+    impl TracerImpl for OtelTracerImpl {
+        fn element_new(&self, _ts: u64, element: &gst::Element) {
+            self.core.element_new(element);
+        }
+
+        fn element_post_message_pre(&self, _ts: u64, element: &gst::Element, msg: &gst::Message) {
+            self.core.element_post_message_pre(element, msg);
         }
     }
 
@@ -365,11 +1822,42 @@ mod imp {
         drop(value)
     }
 
+    /// The pad a buffer pushed on `pad` really arrives at, looking through bin ghost pads.
+    ///
+    /// A ghost sink pad resolves to its target inside the bin, and the internal proxy pad of a
+    /// ghost src pad resolves to the ghost pad's peer outside it. Pushes from proxy pads are not
+    /// traced themselves, so a buffer crossing a bin gets one span per pair of real elements.
+    pub(super) fn real_peer(pad: &gst::Pad) -> Option<gst::Pad> {
+        pad.peer().map(real_pad)
+    }
+
+    fn real_pad(pad: gst::Pad) -> gst::Pad {
+        if let Some(ghost) = pad.downcast_ref::<gst::GhostPad>() {
+            return match ghost.target() {
+                Some(target) => real_pad(target),
+                None => pad,
+            };
+        }
+        if pad.is::<gst::ProxyPad>() {
+            let outer_peer = pad
+                .parent()
+                .and_then(|parent| parent.downcast::<gst::GhostPad>().ok())
+                .and_then(|ghost| ghost.peer());
+            if let Some(outer_peer) = outer_peer {
+                return real_pad(outer_peer);
+            }
+        }
+        pad
+    }
+
     fn pad_push_pre(
         ts: u64,
         pad: &gstreamer::Pad,
         buffer: &gst::Buffer,
         buf_ptr: *mut gst::ffi::GstBuffer,
+        list_length: Option<usize>,
+        seq: u64,
+        span_name_template: &SpanNameTemplate,
     ) {
         // To start with simple logic:
         // First, we check if conditions are met to start a span.
@@ -399,13 +1887,13 @@ mod imp {
         //     pad.name(),
         //     buffer
         // );
-        if pad.direction() != gstreamer::PadDirection::Src {
+        if pad.direction() != gstreamer::PadDirection::Src || pad.is::<gst::ProxyPad>() {
             return;
         }
 
         // TODO - separate change - if child span present on 'this pads' qdata, end it here
 
-        if let Some(peer) = pad.peer() {
+        if let Some(peer) = real_peer(pad) {
             //
             // Just a reminder to myself on how to do this so I can use it later
             //
@@ -455,15 +1943,15 @@ mod imp {
                     peer.name(),
                     peer.parent().map(|p| p.name()).unwrap_or("unknown".into()),
                 );
-                let tracer = init_otlp();
-                let span_name = format!(
-                    "pad-push-{}-{}-{}-{}",
-                    pad.parent()
+                let tracer = global::tracer("otel-tracer");
+                let span_name = span_name_template.render(
+                    &pad.parent()
                         .map(|p| p.name().to_string())
                         .unwrap_or("unknown".to_string()),
-                    pad.name(),
-                    peer.name(),
-                    peer.parent()
+                    &pad.name(),
+                    &peer.name(),
+                    &peer
+                        .parent()
                         .map(|p| p.name().to_string())
                         .unwrap_or("unknown".to_string()),
                 );
@@ -483,7 +1971,12 @@ mod imp {
                     // TODO - if we have a span in the buffer, use that, if not, we can only start a span if this is a
                     //        source pad.
 
-                    buffer_span
+                    // A traceparent received through an event wins: buffers leaving a
+                    // deserializer (e.g. `gdpdepay`) carry the meta of the local source.
+                    // The application's context only parents spans that would be roots.
+                    remote_parent(pad)
+                        .or(buffer_span)
+                        .or_else(application_parent)
                         .map(|span| {
                             // Use the span's context
                             opentelemetry::Context::current().with_remote_span_context(span.clone())
@@ -536,7 +2029,18 @@ mod imp {
                 }
                 let ctx = o_ctx.unwrap();
 
-                let mut span = tracer.start_with_context(span_name, &ctx);
+                let upstream = ctx.span().span_context().clone();
+                let mut span = if upstream.is_valid() && is_fan_out(pad) {
+                    // Each branch of a `tee` gets its own trace, linked back to the shared upstream
+                    // span rather than chained under it.
+                    tracer
+                        .span_builder(span_name)
+                        .with_links(vec![Link::with_context(upstream)])
+                        .start_with_context(&tracer, &opentelemetry::Context::new())
+                } else {
+                    tracer.start_with_context(span_name, &ctx)
+                };
+                SPANS_STARTED.fetch_add(1, Ordering::Relaxed);
                 let _guard = ctx.attach();
                 if span.is_recording() {
                     // Set the spans attributes
@@ -546,10 +2050,24 @@ mod imp {
                         .map(|p| p.name().to_string())
                         .unwrap_or("unknown".to_string());
                     let src_pad_name_v = pad_c.name().to_owned().to_string();
+                    let pipeline_name_v = pad_c
+                        .parent_element()
+                        .map(|element| pipeline_name(&element))
+                        .unwrap_or("unknown".to_string());
                     let sink_pad_element_v = peer
                         .parent()
                         .map(|p| p.name().to_string())
                         .unwrap_or("unknown".to_string());
+                    let metric_attributes = if LATENCY_HISTOGRAM.get().is_some() {
+                        vec![
+                            KeyValue::new("src_pad.element", src_pad_element_v.clone()),
+                            KeyValue::new("src_pad.name", src_pad_name_v.clone()),
+                            KeyValue::new("sink_pad.element", sink_pad_element_v.clone()),
+                            KeyValue::new("sink_pad.name", peer.name().to_string()),
+                        ]
+                    } else {
+                        vec![]
+                    };
 
                     gst::trace!(
                         CAT,
@@ -563,12 +2081,7 @@ mod imp {
                             .span_context()
                             .trace_id(),
                     );
-                    let current = std::thread::current();
-                    let thread_name = current
-                        .name()
-                        .map(|n| n.to_string())
-                        .unwrap_or_else(|| "unnamed".into());
-                    let thread_id = format!("{:?}", current.id());
+                    let (thread_name, thread_id) = thread_attributes();
 
                     span.set_attributes(vec![
                         KeyValue::new("src_pad.element", src_pad_element_v),
@@ -576,12 +2089,23 @@ mod imp {
                         KeyValue::new("ts.start", ts as i64),
                         // i64 is not ideal but its all KeyValue supports
                         KeyValue::new("buffer.id", buffer.as_ptr() as i64),
+                        KeyValue::new("buffer.seq", seq as i64),
                         KeyValue::new("buffer.size", buffer.size() as i64),
+                        KeyValue::new("buffer.pts", clock_time_attr(buffer.pts())),
+                        KeyValue::new("buffer.dts", clock_time_attr(buffer.dts())),
+                        KeyValue::new("buffer.duration", clock_time_attr(buffer.duration())),
                         KeyValue::new("sink_pad.element", sink_pad_element_v),
                         KeyValue::new("sink_pad.name", peer.name().to_string()),
                         KeyValue::new("src_pad.thread.name", thread_name),
                         KeyValue::new("src_pad.thread.id", thread_id),
+                        KeyValue::new("pipeline.name", pipeline_name_v),
                     ]);
+                    if let Some(length) = list_length {
+                        span.set_attribute(KeyValue::new("buffer_list.length", length as i64));
+                    }
+                    if let Some(media_type) = pad_media_type(&pad_c) {
+                        span.set_attribute(KeyValue::new("stream.media_type", media_type));
+                    }
 
                     // Box the span and store it in the pad's qdata
                     // TODO - this is messy, not sure if there's a better way to set the span and then send the span ref.
@@ -591,6 +2115,8 @@ mod imp {
                     let boxed_span = Box::new(GstSpanSink {
                         guard,
                         span: span_to_send,
+                        start_ts: ts,
+                        metric_attributes,
                     });
 
                     gst::trace!(
@@ -635,11 +2161,644 @@ mod imp {
                             peer.parent().map(|p| p.name()).unwrap_or("unknown".into()),
                         );
                     }
+
+                    if pad
+                        .parent_element()
+                        .is_some_and(|e| e.element_flags().contains(gst::ElementFlags::SOURCE))
+                    {
+                        let ctx_t_s = opentelemetry::Context::current();
+                        push_traceparent(pad, ctx_t_s.span().span_context());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether `pad` is one of several src pads of its element, e.g. a `tee` branch.
+    fn is_fan_out(pad: &gst::Pad) -> bool {
+        pad.parent_element().is_some_and(|e| e.num_src_pads() > 1)
+    }
+
+    /// `thread.name` and `thread.id` attribute values of the current thread.
+    pub(super) fn thread_attributes() -> (Arc<str>, Arc<str>) {
+        THREAD_ATTRIBUTES.with(|attributes| attributes.clone())
+    }
+
+    /// A buffer timestamp as nanoseconds, or -1 for `GST_CLOCK_TIME_NONE`.
+    pub(super) fn clock_time_attr(time: Option<gst::ClockTime>) -> i64 {
+        time.map_or(-1, |t| t.nseconds() as i64)
+    }
+
+    /// The span of an element with `span-granularity=element`, kept in the element's qdata.
+    ///
+    /// Once ended, a new span starts with the element's next buffer, e.g. after a flushing seek
+    /// back from EOS.
+    #[derive(Default)]
+    struct ElementSession {
+        span: Mutex<Option<ElementSpan>>,
+    }
+
+    /// An open element span with the buffers and bytes the element pushed and received so far,
+    /// set as attributes when it ends.
+    struct ElementSpan {
+        span: BoxedSpan,
+        buffers_pushed: u64,
+        bytes_pushed: u64,
+        buffers_received: u64,
+        bytes_received: u64,
+        /// Pads that pushed or received EOS; the span ends once all pads of the element have.
+        eos_pads: u32,
+    }
+
+    impl ElementSpan {
+        fn start(ts: u64, element: &gst::Element, parent: &opentelemetry::Context) -> Self {
+            let tracer = global::tracer("otel-tracer");
+            let mut span = tracer.start_with_context(element.name().to_string(), parent);
+            SPANS_STARTED.fetch_add(1, Ordering::Relaxed);
+            span.set_attributes(vec![
+                KeyValue::new("element.name", element.name().to_string()),
+                KeyValue::new(
+                    "element.factory",
+                    element
+                        .factory()
+                        .map(|f| f.name().to_string())
+                        .unwrap_or("unknown".to_string()),
+                ),
+                KeyValue::new("ts.start", ts as i64),
+                KeyValue::new("pipeline.name", pipeline_name(element)),
+            ]);
+            Self {
+                span,
+                buffers_pushed: 0,
+                bytes_pushed: 0,
+                buffers_received: 0,
+                bytes_received: 0,
+                eos_pads: 0,
+            }
+        }
+    }
+
+    impl Drop for ElementSpan {
+        // Also reached when the element is finalized with its span still open
+        fn drop(&mut self) {
+            self.span.set_attributes(vec![
+                KeyValue::new("buffers.pushed", self.buffers_pushed as i64),
+                KeyValue::new("bytes.pushed", self.bytes_pushed as i64),
+                KeyValue::new("buffers.received", self.buffers_received as i64),
+                KeyValue::new("bytes.received", self.bytes_received as i64),
+            ]);
+            self.span.end();
+        }
+    }
+
+    impl ElementSession {
+        /// Start the span of `element` unless open, update its counts and return its context.
+        fn record(
+            &self,
+            ts: u64,
+            element: &gst::Element,
+            parent: &opentelemetry::Context,
+            update: impl FnOnce(&mut ElementSpan),
+        ) -> SpanContext {
+            let mut span = self.span.lock().unwrap();
+            let span = span.get_or_insert_with(|| ElementSpan::start(ts, element, parent));
+            update(span);
+            span.span.span_context().clone()
+        }
+
+        /// Count a pad of `element` reaching EOS, ending the span once all of them have.
+        fn eos(&self, ts: u64, element: &gst::Element) {
+            let mut span = self.span.lock().unwrap();
+            let Some(open) = span.as_mut() else {
+                return;
+            };
+            open.eos_pads += 1;
+            if open.eos_pads >= element.num_pads() as u32 {
+                open.span.set_attribute(KeyValue::new("ts.end", ts as i64));
+                *span = None;
+            }
+        }
+
+        fn end(&self, ts: u64) {
+            if let Some(mut span) = self.span.lock().unwrap().take() {
+                span.span.set_attribute(KeyValue::new("ts.end", ts as i64));
+            }
+        }
+    }
+
+    /// The session of `element`, created on first use and freed with the element.
+    fn element_session(element: &gst::Element) -> &ElementSession {
+        let obj: *mut gst::ffi::GstElement = element.to_glib_none().0;
+        let obj = obj as *mut gobject_sys::GObject;
+        unsafe {
+            let mut session = glib::gobject_ffi::g_object_get_qdata(obj, *QUARK_ELEMENT_SESSION)
+                as *const ElementSession;
+            if session.is_null() {
+                let _init = ELEMENT_SESSION_INIT.lock().unwrap();
+                session = glib::gobject_ffi::g_object_get_qdata(obj, *QUARK_ELEMENT_SESSION)
+                    as *const ElementSession;
+                if session.is_null() {
+                    session = Box::into_raw(Box::<ElementSession>::default());
+                    glib::gobject_ffi::g_object_set_qdata_full(
+                        obj,
+                        *QUARK_ELEMENT_SESSION,
+                        session as *mut c_void,
+                        Some(drop_value::<ElementSession>),
+                    );
+                }
+            }
+            &*session
+        }
+    }
+
+    /// Takes the sequence number of the next `count` buffers pushed on `pad`, returning that of
+    /// the first. A src pad is pushed from a single streaming thread, so like `sample_push` the
+    /// counter lives in the qdata pointer itself.
+    pub(super) fn next_buffer_seq(pad: *mut gst::ffi::GstPad, count: u64) -> u64 {
+        unsafe {
+            let obj = pad as *mut gobject_sys::GObject;
+            let seq = glib::gobject_ffi::g_object_get_qdata(obj, *QUARK_BUFFER_SEQ) as u64;
+            glib::gobject_ffi::g_object_set_qdata(
+                obj,
+                *QUARK_BUFFER_SEQ,
+                seq.wrapping_add(count) as usize as gpointer,
+            );
+            seq
+        }
+    }
+
+    pub(super) fn reset_buffer_seq(pad: *mut gst::ffi::GstPad) {
+        unsafe {
+            glib::gobject_ffi::g_object_set_qdata(
+                pad as *mut gobject_sys::GObject,
+                *QUARK_BUFFER_SEQ,
+                ptr::null_mut(),
+            );
+        }
+    }
+
+    /// Name of the caps structure negotiated on `pad`, e.g. `video/x-raw`, so traces can be split
+    /// by stream type. `None` until caps are negotiated.
+    pub(super) fn pad_media_type(pad: &gst::Pad) -> Option<String> {
+        let caps = pad.current_caps()?;
+        caps.structure(0).map(|s| s.name().to_string())
+    }
+
+    /// Name of the top-level bin `element` is in, usually its pipeline.
+    ///
+    /// Cached on the element once it is in a bin, so pushes do not walk up the hierarchy.
+    pub(super) fn pipeline_name(element: &gst::Element) -> String {
+        let obj: *mut gst::ffi::GstElement = element.to_glib_none().0;
+        let obj = obj as *mut gobject_sys::GObject;
+        unsafe {
+            let cached =
+                glib::gobject_ffi::g_object_get_qdata(obj, *QUARK_PIPELINE_NAME) as *const String;
+            if !cached.is_null() {
+                return (*cached).clone();
+            }
+        }
+
+        let mut top = element.clone().upcast::<gst::Object>();
+        while let Some(parent) = top.parent() {
+            top = parent;
+        }
+        let name = top.name().to_string();
+        // Not in a bin yet, so the pipeline is still to come
+        if top == *element.upcast_ref::<gst::Object>() {
+            return name;
+        }
+
+        let _init = PIPELINE_NAME_INIT.lock().unwrap();
+        unsafe {
+            if glib::gobject_ffi::g_object_get_qdata(obj, *QUARK_PIPELINE_NAME).is_null() {
+                glib::gobject_ffi::g_object_set_qdata_full(
+                    obj,
+                    *QUARK_PIPELINE_NAME,
+                    Box::into_raw(Box::new(name.clone())) as *mut c_void,
+                    Some(drop_value::<String>),
+                );
+            }
+        }
+        name
+    }
+
+    /// Count `buffers` buffers of `bytes` in total pushed from `pad` on the span of its element
+    /// and on that of the element they arrive at, starting either on its first buffer.
+    ///
+    /// The receiving element's span starts as a child of the pushing element's, so the trace
+    /// follows the pipeline topology down from its sources.
+    fn element_push(core: &OtelTracerCore, ts: u64, pad: &gst::Pad, buffers: u64, bytes: u64) {
+        if pad.direction() != gst::PadDirection::Src || pad.is::<gst::ProxyPad>() {
+            return;
+        }
+        let Some(src_element) = pad.parent_element() else {
+            return;
+        };
+        let parent = remote_parent(pad)
+            .or_else(application_parent)
+            .map(|span| opentelemetry::Context::current().with_remote_span_context(span))
+            .unwrap_or_else(opentelemetry::Context::current);
+        let src_span = element_session(&src_element).record(ts, &src_element, &parent, |span| {
+            span.buffers_pushed += buffers;
+            span.bytes_pushed += bytes;
+        });
+
+        let Some(peer) = real_peer(pad).filter(|peer| core.is_traced(peer.to_glib_none().0)) else {
+            return;
+        };
+        let Some(sink_element) = peer.parent_element() else {
+            return;
+        };
+        let parent = opentelemetry::Context::new().with_remote_span_context(src_span);
+        element_session(&sink_element).record(ts, &sink_element, &parent, |span| {
+            span.buffers_received += buffers;
+            span.bytes_received += bytes;
+        });
+    }
+
+    /// Count EOS pushed from `pad` on its element and on the element it arrives at.
+    fn element_eos(ts: u64, pad: &gst::Pad) {
+        if pad.direction() != gst::PadDirection::Src || pad.is::<gst::ProxyPad>() {
+            return;
+        }
+        let sink_element = real_peer(pad).and_then(|peer| peer.parent_element());
+        for element in pad.parent_element().into_iter().chain(sink_element) {
+            element_session(&element).eos(ts, &element);
+        }
+    }
+
+    /// Start a child of the list span on `pad`'s peer for each buffer in `list`.
+    ///
+    /// The spans are kept on the peer pad and ended with the list span in `end_sink_span`.
+    fn start_list_buffer_spans(ts: u64, pad: &gst::Pad, list: &gst::BufferListRef) {
+        let Some(peer) = real_peer(pad) else {
+            return;
+        };
+        let pad_ffi: *mut gstreamer_sys::GstPad = peer.to_glib_none().0;
+        let list_span_context = unsafe {
+            let list_span = glib::gobject_ffi::g_object_get_qdata(
+                pad_ffi as *mut gobject_sys::GObject,
+                *QUARK_SINK_SPAN,
+            ) as *const GstSpanSink;
+            let existing = glib::gobject_ffi::g_object_get_qdata(
+                pad_ffi as *mut gobject_sys::GObject,
+                *QUARK_SINK_LIST_SPANS,
+            );
+            if list_span.is_null() || !existing.is_null() {
+                return;
+            }
+            (*list_span).span.span_context().clone()
+        };
+
+        let ctx = opentelemetry::Context::current().with_remote_span_context(list_span_context);
+        let tracer = global::tracer("otel-tracer");
+        let spans: Vec<BoxedSpan> = list
+            .iter()
+            .enumerate()
+            .map(|(index, buffer)| {
+                let mut span =
+                    tracer.start_with_context(format!("pad-push-list-buffer-{index}"), &ctx);
+                span.set_attributes(vec![
+                    KeyValue::new("ts.start", ts as i64),
+                    KeyValue::new("buffer.id", buffer.as_ptr() as i64),
+                    KeyValue::new("buffer.size", buffer.size() as i64),
+                    KeyValue::new("buffer.pts", clock_time_attr(buffer.pts())),
+                    KeyValue::new("buffer.dts", clock_time_attr(buffer.dts())),
+                    KeyValue::new("buffer.duration", clock_time_attr(buffer.duration())),
+                    KeyValue::new("buffer_list.index", index as i64),
+                ]);
+                if buffer.meta::<GstOtelSpanBuf>().is_none() {
+                    GstOtelSpanBuf::add_ptr(
+                        buffer.as_ptr() as *mut _,
+                        span.span_context().to_owned(),
+                    );
+                }
+                span
+            })
+            .collect();
+        SPANS_STARTED.fetch_add(spans.len() as u64, Ordering::Relaxed);
+
+        unsafe {
+            glib::gobject_ffi::g_object_set_qdata_full(
+                pad_ffi as *mut gobject_sys::GObject,
+                *QUARK_SINK_LIST_SPANS,
+                Box::into_raw(Box::new(spans)) as *mut c_void,
+                Some(drop_value::<Vec<BoxedSpan>>),
+            );
+        }
+    }
+
+    /// Serialize `span_context` as a W3C `traceparent` header value.
+    pub(super) fn traceparent_from_span_context(span_context: &SpanContext) -> Option<String> {
+        let mut carrier = HashMap::new();
+        TraceContextPropagator::new().inject_context(
+            &opentelemetry::Context::new().with_remote_span_context(span_context.clone()),
+            &mut carrier,
+        );
+        carrier.remove("traceparent")
+    }
+
+    /// Parse a W3C `traceparent` header value back into a remote `SpanContext`.
+    pub(super) fn span_context_from_traceparent(traceparent: &str) -> Option<SpanContext> {
+        let carrier = HashMap::from([("traceparent".to_string(), traceparent.to_string())]);
+        let span_context = TraceContextPropagator::new()
+            .extract(&carrier)
+            .span()
+            .span_context()
+            .clone();
+        span_context.is_valid().then_some(span_context)
+    }
+
+    /// Announce the trace of a source's spans downstream, so a pipeline split across
+    /// processes (e.g. payloaded with `gdppay`) can continue it on the other side.
+    ///
+    /// The event is only pushed when the context differs from the last one sent on `pad`.
+    fn push_traceparent(pad: &gst::Pad, span_context: &SpanContext) {
+        let Some(traceparent) = traceparent_from_span_context(span_context) else {
+            return;
+        };
+        let pad_ffi: *mut gstreamer_sys::GstPad = pad.to_glib_none().0;
+        unsafe {
+            let last_sent = glib::gobject_ffi::g_object_get_qdata(
+                pad_ffi as *mut gobject_sys::GObject,
+                *QUARK_TRACEPARENT_SENT,
+            ) as *const String;
+            if !last_sent.is_null() && *last_sent == traceparent {
+                return;
+            }
+        }
+
+        let event = gst::event::CustomDownstream::new(
+            gst::Structure::builder(TRACEPARENT_EVENT)
+                .field("traceparent", &traceparent)
+                .build(),
+        );
+        unsafe {
+            glib::gobject_ffi::g_object_set_qdata_full(
+                pad_ffi as *mut gobject_sys::GObject,
+                *QUARK_TRACEPARENT_SENT,
+                Box::into_raw(Box::new(traceparent)) as *mut c_void,
+                Some(drop_value::<String>),
+            );
+        }
+        if !pad.push_event(event) {
+            gst::trace!(
+                CAT,
+                "traceparent event not handled downstream of {}",
+                pad.name()
+            );
+        }
+    }
+
+    /// Remember a `traceparent` received on `pad`, so buffers pushed from it without a span
+    /// meta are parented to the remote span.
+    fn pad_push_event_pre(pad: &gst::Pad, event: &gst::Event) {
+        let Some(traceparent) = event
+            .structure()
+            .filter(|s| s.has_name(TRACEPARENT_EVENT))
+            .and_then(|s| s.get::<String>("traceparent").ok())
+        else {
+            return;
+        };
+        let pad_ffi: *mut gstreamer_sys::GstPad = pad.to_glib_none().0;
+        unsafe {
+            // Skip the event on the pad that sent it.
+            let last_sent = glib::gobject_ffi::g_object_get_qdata(
+                pad_ffi as *mut gobject_sys::GObject,
+                *QUARK_TRACEPARENT_SENT,
+            ) as *const String;
+            if !last_sent.is_null() && *last_sent == traceparent {
+                return;
+            }
+        }
+        let Some(span_context) = span_context_from_traceparent(&traceparent) else {
+            gst::debug!(
+                CAT,
+                "Ignoring invalid traceparent {} on {}",
+                traceparent,
+                pad.name()
+            );
+            return;
+        };
+        gst::trace!(
+            CAT,
+            "Received traceparent {} on {}",
+            traceparent,
+            pad.name()
+        );
+        unsafe {
+            glib::gobject_ffi::g_object_set_qdata_full(
+                pad_ffi as *mut gobject_sys::GObject,
+                *QUARK_REMOTE_PARENT,
+                Box::into_raw(Box::new(span_context)) as *mut c_void,
+                Some(drop_value::<SpanContext>),
+            );
+        }
+    }
+
+    /// Events recorded as span events, for debugging seeking, flushing, clipping and
+    /// renegotiation.
+    const SPAN_EVENT_TYPES: [gst::ffi::GstEventType; 6] = [
+        gst::ffi::GST_EVENT_CAPS,
+        gst::ffi::GST_EVENT_SEGMENT,
+        gst::ffi::GST_EVENT_FLUSH_START,
+        gst::ffi::GST_EVENT_FLUSH_STOP,
+        gst::ffi::GST_EVENT_EOS,
+        gst::ffi::GST_EVENT_SEEK,
+    ];
+
+    /// Add `event` as a span event named after its type, e.g. `segment`.
+    ///
+    /// The event goes to the push span active on `pad`'s peer, or otherwise to the span active
+    /// on the streaming thread, e.g. while an element pushes a new segment from its chain
+    /// function. Events sent with no span active are not recorded.
+    unsafe fn add_span_event(ts: u64, pad: &gst::Pad, event_ptr: *mut gst::ffi::GstEvent) {
+        let name = std::ffi::CStr::from_ptr(gst::ffi::gst_event_type_get_name((*event_ptr).type_))
+            .to_string_lossy()
+            .into_owned();
+        let mut attributes = vec![
+            KeyValue::new("event.ts", ts as i64),
+            KeyValue::new(
+                "event.pad",
+                format!(
+                    "{}:{}",
+                    pad.parent().map(|p| p.name()).unwrap_or("unknown".into()),
+                    pad.name()
+                ),
+            ),
+        ];
+        attributes.extend(event_attributes(event_ptr));
+
+        let span_ptr = real_peer(pad)
+            .filter(|peer| peer.direction() == gst::PadDirection::Sink)
+            .map(|peer| {
+                let peer_ffi: *mut gstreamer_sys::GstPad = peer.to_glib_none().0;
+                glib::gobject_ffi::g_object_get_qdata(
+                    peer_ffi as *mut gobject_sys::GObject,
+                    *QUARK_SINK_SPAN,
+                ) as *const GstSpanSink
+            })
+            .unwrap_or(ptr::null());
+        if let Some(span_sink) = span_ptr.as_ref() {
+            span_sink.span.add_event(name, attributes);
+        } else {
+            let ctx = opentelemetry::Context::current();
+            if ctx.has_active_span() {
+                ctx.span().add_event(name, attributes);
+            }
+        }
+    }
+
+    /// Caps strings longer than this are truncated, e.g. caps listing every supported format.
+    const MAX_CAPS_ATTRIBUTE_LEN: usize = 512;
+
+    /// Truncates `value` to at most `max_len` bytes on a char boundary, marking the cut with `…`.
+    pub(super) fn truncate_attribute(mut value: String, max_len: usize) -> String {
+        if value.len() > max_len {
+            let mut end = max_len;
+            while !value.is_char_boundary(end) {
+                end -= 1;
+            }
+            value.truncate(end);
+            value.push('…');
+        }
+        value
+    }
+
+    /// Caps, segment, seek and flush-stop fields; positions are in the event's format, -1 when
+    /// unset.
+    unsafe fn event_attributes(event_ptr: *mut gst::ffi::GstEvent) -> Vec<KeyValue> {
+        let format_name = |format: gst::ffi::GstFormat| {
+            std::ffi::CStr::from_ptr(gst::ffi::gst_format_get_name(format))
+                .to_string_lossy()
+                .into_owned()
+        };
+        match (*event_ptr).type_ {
+            gst::ffi::GST_EVENT_CAPS => {
+                let mut caps: *mut gst::ffi::GstCaps = ptr::null_mut();
+                gst::ffi::gst_event_parse_caps(event_ptr, &mut caps);
+                if caps.is_null() {
+                    return vec![];
                 }
+                let caps = gst::CapsRef::from_ptr(caps);
+                let mut attributes = vec![KeyValue::new(
+                    "caps",
+                    truncate_attribute(caps.to_string(), MAX_CAPS_ATTRIBUTE_LEN),
+                )];
+                if let Some(structure) = caps.structure(0) {
+                    attributes.push(KeyValue::new(
+                        "caps.media_type",
+                        structure.name().to_string(),
+                    ));
+                }
+                attributes
+            }
+            gst::ffi::GST_EVENT_SEGMENT => {
+                let mut segment: *const gst::ffi::GstSegment = ptr::null();
+                gst::ffi::gst_event_parse_segment(event_ptr, &mut segment);
+                let Some(segment) = segment.as_ref() else {
+                    return vec![];
+                };
+                vec![
+                    KeyValue::new("segment.format", format_name(segment.format)),
+                    KeyValue::new("segment.rate", segment.rate),
+                    KeyValue::new("segment.applied_rate", segment.applied_rate),
+                    // GST_CLOCK_TIME_NONE wraps to -1, as for buffer timestamps
+                    KeyValue::new("segment.start", segment.start as i64),
+                    KeyValue::new("segment.stop", segment.stop as i64),
+                    KeyValue::new("segment.time", segment.time as i64),
+                    KeyValue::new("segment.position", segment.position as i64),
+                    KeyValue::new("segment.base", segment.base as i64),
+                ]
+            }
+            gst::ffi::GST_EVENT_SEEK => {
+                let mut rate = 0.0;
+                let mut format = gst::ffi::GST_FORMAT_UNDEFINED;
+                let mut flags = 0;
+                let mut start_type = gst::ffi::GST_SEEK_TYPE_NONE;
+                let mut start = 0;
+                let mut stop_type = gst::ffi::GST_SEEK_TYPE_NONE;
+                let mut stop = 0;
+                gst::ffi::gst_event_parse_seek(
+                    event_ptr,
+                    &mut rate,
+                    &mut format,
+                    &mut flags,
+                    &mut start_type,
+                    &mut start,
+                    &mut stop_type,
+                    &mut stop,
+                );
+                vec![
+                    KeyValue::new("seek.format", format_name(format)),
+                    KeyValue::new("seek.rate", rate),
+                    KeyValue::new("seek.flags", flags as i64),
+                    KeyValue::new(
+                        "seek.start",
+                        if start_type == gst::ffi::GST_SEEK_TYPE_NONE {
+                            -1
+                        } else {
+                            start
+                        },
+                    ),
+                    KeyValue::new(
+                        "seek.stop",
+                        if stop_type == gst::ffi::GST_SEEK_TYPE_NONE {
+                            -1
+                        } else {
+                            stop
+                        },
+                    ),
+                ]
+            }
+            gst::ffi::GST_EVENT_FLUSH_STOP => {
+                let mut reset_time = GFALSE;
+                gst::ffi::gst_event_parse_flush_stop(event_ptr, &mut reset_time);
+                vec![KeyValue::new("flush_stop.reset_time", reset_time != GFALSE)]
             }
+            _ => vec![],
+        }
+    }
+
+    fn application_parent() -> Option<SpanContext> {
+        APPLICATION_PARENT.read().unwrap().clone()
+    }
+
+    /// Parents spans started from now on that have no other parent to `traceparent`, or
+    /// makes them roots again when it is `None`. Returns false for an invalid `traceparent`,
+    /// leaving the parent unchanged.
+    fn set_application_parent(traceparent: Option<&str>) -> bool {
+        let parent = match traceparent {
+            Some(traceparent) => match span_context_from_traceparent(traceparent) {
+                Some(span_context) => Some(span_context),
+                None => {
+                    gst::warning!(CAT, "Ignoring invalid parent traceparent {}", traceparent);
+                    return false;
+                }
+            },
+            None => None,
+        };
+        gst::debug!(CAT, "Setting application parent to {:?}", parent);
+        *APPLICATION_PARENT.write().unwrap() = parent;
+        true
+    }
+
+    fn remote_parent(pad: &gst::Pad) -> Option<SpanContext> {
+        let pad_ffi: *mut gstreamer_sys::GstPad = pad.to_glib_none().0;
+        unsafe {
+            let span_context = glib::gobject_ffi::g_object_get_qdata(
+                pad_ffi as *mut gobject_sys::GObject,
+                *QUARK_REMOTE_PARENT,
+            ) as *const SpanContext;
+            span_context.as_ref().cloned()
         }
     }
-    fn pad_push_post(ts: u64, peer_pad: &gstreamer::Pad, _self_pad: &gstreamer::Pad) {
+    fn pad_push_post(
+        ts: u64,
+        peer_pad: &gstreamer::Pad,
+        _self_pad: &gstreamer::Pad,
+        flow: gst::FlowReturn,
+    ) {
         // To start with simple logic:
         // First, we check if conditions are met to start a span.
         // Currently, those conditions are:
@@ -665,8 +2824,87 @@ mod imp {
             return;
         }
 
+        end_sink_span(
+            ts,
+            peer_pad,
+            vec![
+                KeyValue::new("flow.return", flow_name(flow)),
+                KeyValue::new("flow.description", flow_description(flow)),
+                KeyValue::new("buffer.dropped", flow.into_result().is_err()),
+            ],
+            flow_error(flow),
+        );
+    }
+
+    /// GStreamer's name for a flow return, e.g. `not-linked`.
+    pub(super) fn flow_name(flow: gst::FlowReturn) -> String {
+        unsafe { std::ffi::CStr::from_ptr(gst::ffi::gst_flow_get_name(flow.into_glib())) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// What a flow return means for the pushed buffer, for the `flow.description` attribute.
+    pub(super) fn flow_description(flow: gst::FlowReturn) -> &'static str {
+        match flow {
+            gst::FlowReturn::Ok => "buffer accepted",
+            gst::FlowReturn::CustomSuccess
+            | gst::FlowReturn::CustomSuccess1
+            | gst::FlowReturn::CustomSuccess2 => "buffer accepted with an element-specific success",
+            gst::FlowReturn::NotLinked => "buffer dropped, the pad is not linked",
+            gst::FlowReturn::Flushing => "buffer dropped, the pad is flushing",
+            gst::FlowReturn::Eos => "buffer dropped, downstream is at end of stream",
+            gst::FlowReturn::NotNegotiated => "buffer refused, caps were not negotiated",
+            gst::FlowReturn::Error => "buffer refused with a fatal error",
+            gst::FlowReturn::NotSupported => "buffer refused, operation not supported",
+            gst::FlowReturn::CustomError
+            | gst::FlowReturn::CustomError1
+            | gst::FlowReturn::CustomError2 => "buffer refused with an element-specific error",
+            _ => "unknown flow return",
+        }
+    }
+
+    /// The span error for a failed push, if `flow` is one.
+    ///
+    /// Flushing is part of normal seeking and shutdown, and EOS of a stream ending downstream
+    /// (e.g. after `num-buffers`), so neither is treated as a failure; their spans are marked
+    /// with `buffer.dropped` instead.
+    pub(super) fn flow_error(flow: gst::FlowReturn) -> Option<String> {
+        match flow.into_result() {
+            Ok(_) | Err(gst::FlowError::Flushing) | Err(gst::FlowError::Eos) => None,
+            Err(_) => Some(flow_name(flow)),
+        }
+    }
+
+    /// End the span stored on `peer_pad`, if any, and clear it from the pad's qdata.
+    ///
+    /// `error` marks the span as failed, e.g. for an error flow return or a pad unlinked
+    /// while a span was still open.
+    fn end_sink_span(
+        ts: u64,
+        peer_pad: &gstreamer::Pad,
+        attributes: Vec<KeyValue>,
+        error: Option<String>,
+    ) {
         // Get the pad's qdata
         let sink_pad_ffi: *mut gstreamer_sys::GstPad = peer_pad.to_glib_none().0;
+
+        // Per-buffer spans of a list push end with the list span
+        let list_spans = unsafe {
+            glib::gobject_ffi::g_object_steal_qdata(
+                sink_pad_ffi as *mut gobject_sys::GObject,
+                *QUARK_SINK_LIST_SPANS,
+            )
+        } as *mut Vec<BoxedSpan>;
+        if !list_spans.is_null() {
+            for mut span in *unsafe { Box::from_raw(list_spans) } {
+                span.set_attribute(KeyValue::new("ts.end", ts as i64));
+                span.set_attributes(attributes.clone());
+                if let Some(error) = &error {
+                    span.set_status(Status::error(error.clone()));
+                }
+                span.end();
+            }
+        }
         let span_ptr = unsafe {
             glib::gobject_ffi::g_object_get_qdata(
                 sink_pad_ffi as *mut gobject_sys::GObject,
@@ -700,20 +2938,26 @@ mod imp {
                             .unwrap_or("unknown".into())
                     );
 
-                    let current = std::thread::current();
-                    let thread_name = current
-                        .name()
-                        .map(|n| n.to_string())
-                        .unwrap_or_else(|| "unnamed".into());
-                    let thread_id = format!("{:?}", current.id());
+                    let (thread_name, thread_id) = thread_attributes();
                     // Set the end time
                     (*span_ptr).span.set_attributes(vec![
                         KeyValue::new("ts.end", ts as i64),
                         KeyValue::new("sink_pad.thread.name", thread_name),
                         KeyValue::new("sink_pad.thread.id", thread_id),
                     ]);
+                    (*span_ptr).span.set_attributes(attributes);
+                    if let Some(error) = error {
+                        (*span_ptr).span.set_status(Status::error(error));
+                    }
                     (*span_ptr).span.end();
 
+                    if let Some(histogram) = LATENCY_HISTOGRAM.get() {
+                        histogram.record(
+                            ts.saturating_sub((*span_ptr).start_ts),
+                            &(*span_ptr).metric_attributes,
+                        );
+                    }
+
                     // Last chance to log the span
                     gst::trace!(
                         CAT,
@@ -721,14 +2965,6 @@ mod imp {
                         peer_pad.name(),
                         (*span_ptr)
                     );
-
-                    // Deallocate the span through glib.
-                    // This will also drop the guard.
-                    glib::gobject_ffi::g_object_set_qdata(
-                        sink_pad_ffi as *mut gobject_sys::GObject,
-                        *QUARK_SINK_SPAN,
-                        std::ptr::null_mut(),
-                    );
                 } else {
                     gst::trace!(
                         CAT,
@@ -740,6 +2976,14 @@ mod imp {
                             .unwrap_or("unknown".into())
                     );
                 }
+
+                // Deallocate the span through glib, recording or not, so it can't leak.
+                // This will also drop the guard.
+                glib::gobject_ffi::g_object_set_qdata(
+                    sink_pad_ffi as *mut gobject_sys::GObject,
+                    *QUARK_SINK_SPAN,
+                    std::ptr::null_mut(),
+                );
             }
         } else {
             gst::trace!(
@@ -756,6 +3000,8 @@ mod imp {
     }
 }
 
+pub use imp::OtelTracerCore;
+
 glib::wrapper! {
     pub struct TelemetryTracer(ObjectSubclass<imp::OtelTracerImpl>)
         @extends gst::Tracer, gst::Object;
@@ -773,3 +3019,258 @@ unsafe impl gst::MetaAPI for imp::GstOtelSpanBuf {
         imp::gst_span_buf_api_get_type()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::imp::{
+        clock_time_attr, endpoint_address, flow_description, flow_error, flow_name,
+        next_buffer_seq, pad_media_type, parse_histogram_buckets, parse_key_values, pipeline_name,
+        reset_buffer_seq, span_context_from_traceparent, thread_attributes,
+        traceparent_from_span_context, truncate_attribute, ExportHeaders, MinDurationSpanProcessor,
+        SpanNameTemplate,
+    };
+    use gst::prelude::*;
+    use gstreamer as gst;
+    use opentelemetry::trace::{
+        Span, SpanContext, SpanId, TraceFlags, TraceId, TraceState, Tracer, TracerProvider,
+    };
+    use opentelemetry_sdk::trace::{SdkTracerProvider, SpanData, SpanProcessor};
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime},
+    };
+
+    #[test]
+    fn endpoint_address_defaults_port_by_scheme() {
+        for (endpoint, address) in [
+            ("http://collector:4318/v1/traces", Some("collector:4318")),
+            ("https://api.honeycomb.io", Some("api.honeycomb.io:443")),
+            ("http://[::1]/v1/traces", Some("[::1]:80")),
+            ("http://[::1]:4317", Some("[::1]:4317")),
+            ("http://", None),
+        ] {
+            assert_eq!(endpoint_address(endpoint).as_deref(), address, "{endpoint}");
+        }
+    }
+
+    #[test]
+    fn histogram_buckets_must_be_positive_and_increasing() {
+        assert_eq!(
+            parse_histogram_buckets("1000, 1e4,100000"),
+            Ok(vec![1_000.0, 10_000.0, 100_000.0])
+        );
+        assert!(parse_histogram_buckets("1000,1000").is_err());
+        assert!(parse_histogram_buckets("10000,1000").is_err());
+        assert!(parse_histogram_buckets("0,1000").is_err());
+        assert!(parse_histogram_buckets("1000,fast").is_err());
+        assert!(parse_histogram_buckets("1000,inf").is_err());
+    }
+
+    #[test]
+    fn export_headers_are_parsed_and_redacted() {
+        let headers = ExportHeaders(parse_key_values(
+            "x-honeycomb-team=secret,Authorization=Basic a2V5OnZhbHVl==",
+        ));
+        assert_eq!(
+            headers.http().get("Authorization").map(String::as_str),
+            Some("Basic a2V5OnZhbHVl==")
+        );
+        let logged = format!("{headers:?}");
+        assert!(logged.contains("x-honeycomb-team"));
+        assert!(!logged.contains("secret") && !logged.contains("a2V5"));
+    }
+
+    #[test]
+    fn span_name_template_substitutes_placeholders() {
+        assert_eq!(
+            SpanNameTemplate::default().render("src", "src_0", "sink", "sink_el"),
+            "pad-push-src-src_0-sink-sink_el"
+        );
+        assert_eq!(
+            SpanNameTemplate::parse("{src_element}→{sink_element}").render("a", "src", "sink", "b"),
+            "a→b"
+        );
+        // Unknown placeholders and unterminated braces are kept as is
+        assert_eq!(
+            SpanNameTemplate::parse("{pipeline}/{sink_pad} {src_pad").render("a", "s", "k", "b"),
+            "{pipeline}/k {src_pad"
+        );
+    }
+
+    #[test]
+    fn traceparent_round_trips_span_context() {
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        );
+        let traceparent = traceparent_from_span_context(&span_context).unwrap();
+        assert_eq!(
+            traceparent,
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+
+        let remote = span_context_from_traceparent(&traceparent).unwrap();
+        assert!(remote.is_remote());
+        assert_eq!(remote.trace_id(), span_context.trace_id());
+        assert_eq!(remote.span_id(), span_context.span_id());
+        assert!(span_context_from_traceparent("not-a-traceparent").is_none());
+        assert!(traceparent_from_span_context(&SpanContext::empty_context()).is_none());
+    }
+
+    #[test]
+    fn flow_error_ignores_ok_and_flushing() {
+        assert_eq!(flow_name(gst::FlowReturn::NotLinked), "not-linked");
+        assert_eq!(flow_error(gst::FlowReturn::Ok), None);
+        assert_eq!(flow_error(gst::FlowReturn::Flushing), None);
+        assert_eq!(flow_error(gst::FlowReturn::Eos), None);
+        assert_eq!(
+            flow_error(gst::FlowReturn::NotNegotiated).as_deref(),
+            Some("not-negotiated")
+        );
+        assert_eq!(
+            flow_description(gst::FlowReturn::Eos),
+            "buffer dropped, downstream is at end of stream"
+        );
+        assert_eq!(flow_error(gst::FlowReturn::Error).as_deref(), Some("error"));
+        assert_eq!(
+            flow_error(gst::FlowReturn::NotLinked).as_deref(),
+            Some("not-linked")
+        );
+    }
+
+    #[test]
+    fn thread_attributes_match_the_current_thread() {
+        let handle = std::thread::Builder::new()
+            .name("streaming".into())
+            .spawn(|| {
+                let (name, id) = thread_attributes();
+                assert_eq!(thread_attributes().0, name);
+                (name, id, format!("{:?}", std::thread::current().id()))
+            })
+            .unwrap();
+        let (name, id, expected_id) = handle.join().unwrap();
+        assert_eq!(&*name, "streaming");
+        assert_eq!(&*id, expected_id);
+        assert_ne!(thread_attributes().1, id);
+    }
+
+    #[test]
+    fn truncate_attribute_cuts_on_a_char_boundary() {
+        assert_eq!(truncate_attribute("video/x-raw".into(), 64), "video/x-raw");
+        assert_eq!(truncate_attribute("video/x-raw".into(), 5), "video…");
+        // 'é' spans bytes 1..3, so a cut at 2 falls back to 1
+        assert_eq!(truncate_attribute("héllo".into(), 2), "h…");
+    }
+
+    #[test]
+    fn clock_time_attr_maps_none_to_minus_one() {
+        assert_eq!(clock_time_attr(None), -1);
+        assert_eq!(clock_time_attr(Some(gst::ClockTime::ZERO)), 0);
+        assert_eq!(
+            clock_time_attr(Some(gst::ClockTime::from_mseconds(40))),
+            40_000_000
+        );
+    }
+
+    #[test]
+    fn pipeline_name_is_that_of_the_top_level_bin() {
+        gst::init().unwrap();
+        let pipeline = gst::Pipeline::with_name("outer_pipeline");
+        let bin = gst::Bin::with_name("inner_bin");
+        let identity = gst::ElementFactory::make("identity").build().unwrap();
+        assert_eq!(pipeline_name(&identity), identity.name());
+
+        bin.add(&identity).unwrap();
+        pipeline.add(&bin).unwrap();
+        assert_eq!(pipeline_name(&identity), "outer_pipeline");
+    }
+
+    #[test]
+    fn buffer_seq_counts_pushed_buffers_until_reset() {
+        gst::init().unwrap();
+        let pad = gst::Pad::builder(gst::PadDirection::Src)
+            .name("src")
+            .build();
+        let pad_ptr = pad.as_ptr();
+        assert_eq!(next_buffer_seq(pad_ptr, 1), 0);
+        assert_eq!(next_buffer_seq(pad_ptr, 1), 1);
+        // A list of 3 buffers takes 3 numbers
+        assert_eq!(next_buffer_seq(pad_ptr, 3), 2);
+        assert_eq!(next_buffer_seq(pad_ptr, 1), 5);
+
+        reset_buffer_seq(pad_ptr);
+        assert_eq!(next_buffer_seq(pad_ptr, 1), 0);
+    }
+
+    #[test]
+    fn pad_media_type_is_omitted_until_caps_are_negotiated() {
+        gst::init().unwrap();
+        let pad = gst::Pad::builder(gst::PadDirection::Src)
+            .name("src")
+            .build();
+        assert_eq!(pad_media_type(&pad), None);
+
+        pad.set_active(true).unwrap();
+        let caps = gst::Caps::builder("video/x-raw")
+            .field("width", 320)
+            .build();
+        pad.store_sticky_event(&gst::event::Caps::new(&caps))
+            .unwrap();
+        assert_eq!(pad_media_type(&pad).as_deref(), Some("video/x-raw"));
+    }
+
+    /// Names of the spans that reached the end of the processor chain.
+    #[derive(Debug, Default, Clone)]
+    struct EndedSpans(Arc<Mutex<Vec<String>>>);
+
+    impl SpanProcessor for EndedSpans {
+        fn on_start(
+            &self,
+            _span: &mut opentelemetry_sdk::trace::Span,
+            _cx: &opentelemetry::Context,
+        ) {
+        }
+
+        fn on_end(&self, span: SpanData) {
+            self.0.lock().unwrap().push(span.name.to_string());
+        }
+
+        fn force_flush(&self) -> opentelemetry_sdk::error::OTelSdkResult {
+            Ok(())
+        }
+
+        fn shutdown_with_timeout(
+            &self,
+            _timeout: Duration,
+        ) -> opentelemetry_sdk::error::OTelSdkResult {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn spans_shorter_than_min_span_duration_are_dropped() {
+        let ended = EndedSpans::default();
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(MinDurationSpanProcessor {
+                inner: ended.clone(),
+                min: Duration::from_millis(1),
+            })
+            .build();
+        let tracer = provider.tracer("min-span-duration");
+        let start = SystemTime::now();
+        for (name, took) in [
+            ("fast", Duration::from_micros(100)),
+            ("slow", Duration::from_millis(5)),
+        ] {
+            tracer
+                .span_builder(name)
+                .with_start_time(start)
+                .start(&tracer)
+                .end_with_timestamp(start + took);
+        }
+        assert_eq!(*ended.0.lock().unwrap(), ["slow"]);
+    }
+}
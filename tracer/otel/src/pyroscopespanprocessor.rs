@@ -14,6 +14,17 @@ pub(crate) mod imp {
             Some("OTLP tracer with metrics"),
         )
     });
+    /// Agent settings from the `pyroscope-*` tracer params. Unset ones fall back to the
+    /// `GST_PYROSCOPE_*` environment variables, then to the defaults.
+    #[derive(Debug, Default, Clone, PartialEq)]
+    pub(crate) struct PyroscopeConfig {
+        pub server_url: Option<String>,
+        /// Sampling rate in Hz.
+        pub sample_rate: Option<u32>,
+        /// Replace the tags of `GST_PYROSCOPE_TAGS` when set.
+        pub tags: Option<Vec<(String, String)>>,
+    }
+
     #[derive(Debug, Default)]
     pub(crate) struct PyroscopeSpanProcessor {
         agent: std::sync::RwLock<Option<PyroscopeAgent<PyroscopeAgentRunning>>>,
@@ -24,7 +35,7 @@ pub(crate) mod imp {
             Self::default()
         }
 
-        pub fn create_first_agent(&self, tags: Vec<(&str, &str)>) {
+        pub fn create_first_agent(&self, config: &PyroscopeConfig, tags: Vec<(&str, &str)>) {
             // First, check with a read lock
             {
                 let agent_read = self.agent.read().unwrap();
@@ -36,7 +47,11 @@ pub(crate) mod imp {
             let mut agent_write = self.agent.write().unwrap();
             if agent_write.is_none() {
                 gst::debug!(CAT, "Creating new Pyroscope agent");
-                *agent_write = Some(self.create_pyroscope_agent(tags));
+                // Profiling is best-effort; keep tracing without it rather than failing the pipeline.
+                match self.create_pyroscope_agent(config, tags) {
+                    Ok(agent) => *agent_write = Some(agent),
+                    Err(err) => gst::warning!(CAT, "Failed to start Pyroscope agent: {}", err),
+                }
             }
         }
 
@@ -56,11 +71,30 @@ pub(crate) mod imp {
 
         fn create_pyroscope_agent(
             &self,
+            config: &PyroscopeConfig,
             tags: Vec<(&str, &str)>,
-        ) -> PyroscopeAgent<PyroscopeAgentRunning> {
-            // Messy config, should probably allow for setting through element properties.
-            let url = std::env::var("GST_PYROSCOPE_SERVER_URL")
-                .unwrap_or_else(|_| "http://localhost:4040".into());
+        ) -> pyroscope::Result<PyroscopeAgent<PyroscopeAgentRunning>> {
+            let url = config
+                .server_url
+                .clone()
+                .or_else(|| std::env::var("GST_PYROSCOPE_SERVER_URL").ok())
+                .unwrap_or_else(|| "http://localhost:4040".into());
+            let extra_tags = config.tags.clone().unwrap_or_else(|| {
+                std::env::var("GST_PYROSCOPE_TAGS")
+                    .map(|tags| {
+                        tags.split(',')
+                            .filter_map(|tag| tag.split_once('='))
+                            .map(|(key, value)| (key.to_string(), value.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            });
+            let sample_rate = config.sample_rate.unwrap_or_else(|| {
+                std::env::var("GST_PYROSCOPE_SAMPLE_RATE")
+                    .ok()
+                    .and_then(|rate| rate.parse().ok())
+                    .unwrap_or(100)
+            });
             gst::debug!(CAT, "Creating Pyroscope agent with URL: {}", url);
             PyroscopeAgent::builder(
                 url,
@@ -77,33 +111,16 @@ pub(crate) mod imp {
                 ]
                 .into_iter()
                 .chain(
-                    std::env::var("GST_PYROSCOPE_TAGS")
-                        .unwrap_or_else(|_| String::new())
-                        .split(',')
-                        .filter_map(|tag| {
-                            let mut parts = tag.splitn(2, '=');
-                            if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
-                                Some((key, value))
-                            } else {
-                                None
-                            }
-                        }),
+                    extra_tags
+                        .iter()
+                        .map(|(key, value)| (key.as_str(), value.as_str())),
                 )
                 .chain(tags)
                 .collect(),
             )
-            .backend(pprof_backend(
-                PprofConfig::new().sample_rate(
-                    std::env::var("GST_PYROSCOPE_SAMPLE_RATE")
-                        .unwrap_or_else(|_| "100".into())
-                        .parse()
-                        .unwrap_or(100),
-                ),
-            ))
-            .build()
-            .unwrap()
+            .backend(pprof_backend(PprofConfig::new().sample_rate(sample_rate)))
+            .build()?
             .start()
-            .unwrap()
         }
     }
     impl SpanProcessor for PyroscopeSpanProcessor {
@@ -115,8 +132,7 @@ pub(crate) mod imp {
                 span.set_attribute(KeyValue::new("pyroscope.profile.id", s_str.clone()));
                 // python version
                 // pyroscope.add_thread_tag(threading.get_ident(), PROFILE_ID_PYROSCOPE_TAG_KEY, s_str)
-                if let Ok(agent) = self.agent.write().as_mut() {
-                    let a = agent.as_ref().unwrap();
+                if let Ok(Some(a)) = self.agent.write().as_deref() {
                     a.add_thread_tag(
                         thread_id::get() as u64,
                         Tag::new("span_id".to_owned(), s_str),
@@ -132,8 +148,7 @@ pub(crate) mod imp {
                 // python version
                 // pyroscope.remove_thread_tag(threading.get_ident(), PROFILE_ID_PYROSCOPE_TAG_KEY, s_str)
                 let s_str = span.span_context.span_id().to_string();
-                if let Ok(agent) = self.agent.write().as_mut() {
-                    let a = agent.as_ref().unwrap();
+                if let Ok(Some(a)) = self.agent.write().as_deref() {
                     a.remove_thread_tag(
                         thread_id::get() as u64,
                         Tag::new("span_id".to_owned(), s_str),
@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod tests {
+    use gst::glib::translate::IntoGlib;
+    use gst::prelude::*;
+    use gstreamer as gst;
+    use gsttracercommon::test_util as common;
+
+    #[test]
+    fn given_elements_allowlist_when_pipeline_runs_then_only_listed_elements_traced() {
+        setup_test();
+
+        let pipeline = gst::parse::launch(
+            "fakesrc name=traced num-buffers=5 ! identity name=skipped ! fakesink",
+        )
+        .expect("Failed to create pipeline from launch string")
+        .downcast::<gst::Pipeline>()
+        .expect("Failed to downcast to gst::Pipeline");
+        common::run_to_eos(&pipeline);
+
+        // The decision cached on each src pad: 1 traced, 2 skipped
+        let decision = |name: &str| {
+            let src_pad = pipeline
+                .by_name(name)
+                .and_then(|e| e.static_pad("src"))
+                .expect("Expected element with a src pad");
+            (unsafe {
+                gst::glib::gobject_ffi::g_object_get_qdata(
+                    src_pad.as_ptr() as *mut _,
+                    gst::glib::Quark::from_str("otel-traced").into_glib(),
+                )
+            }) as usize
+        };
+        assert_eq!(decision("traced"), 1);
+        assert_eq!(decision("skipped"), 2);
+    }
+
+    fn setup_test() {
+        common::setup_tracers("otel-tracer(elements=traced)");
+    }
+}
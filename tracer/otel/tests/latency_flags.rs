@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use gst::glib::translate::IntoGlib;
+    use gst::prelude::*;
+    use gstreamer as gst;
+    use gsttracercommon::test_util as common;
+
+    #[test]
+    fn given_pipeline_flag_when_pipeline_runs_then_only_source_pushes_traced() {
+        setup_test();
+        let span_count =
+            || common::tracer("otel-tracer").emit_by_name::<u64>("request-span-count", &[]);
+        let before = span_count();
+
+        let pipeline = gst::parse::launch(
+            "fakesrc name=source num-buffers=10 ! identity name=middle ! fakesink",
+        )
+        .expect("Failed to create pipeline from launch string")
+        .downcast::<gst::Pipeline>()
+        .expect("Failed to downcast to gst::Pipeline");
+        common::run_to_eos(&pipeline);
+
+        // The decision cached on each src pad: 1 traced, 2 skipped
+        let decision = |name: &str| {
+            let src_pad = pipeline
+                .by_name(name)
+                .and_then(|e| e.static_pad("src"))
+                .expect("Expected element with a src pad");
+            (unsafe {
+                gst::glib::gobject_ffi::g_object_get_qdata(
+                    src_pad.as_ptr() as *mut _,
+                    gst::glib::Quark::from_str("otel-traced").into_glib(),
+                )
+            }) as usize
+        };
+        assert_eq!(decision("source"), 1);
+        assert_eq!(decision("middle"), 2);
+        // One span per buffer leaving the source, none for identity's pushes
+        assert_eq!(span_count(), before + 10);
+    }
+
+    fn setup_test() {
+        common::setup_tracers("otel-tracer(flags=pipeline)");
+    }
+}
@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod tests {
+    use gstreamer as gst;
+    use gsttracercommon::test_util as common;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    const COLLECTOR_PORT: u16 = 19115;
+
+    #[test]
+    fn given_traces_endpoint_when_logs_bridged_then_logs_exported_to_matching_path() {
+        // Stand in for the collector before the tracer probes it, answering every export
+        let listener = TcpListener::bind(("127.0.0.1", COLLECTOR_PORT))
+            .expect("Failed to bind fake OTLP collector");
+        let (requests_tx, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let requests_tx = requests_tx.clone();
+                thread::spawn(move || {
+                    let mut request_line = String::new();
+                    if BufReader::new(&stream).read_line(&mut request_line).is_ok() {
+                        let _ = requests_tx.send(request_line);
+                        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+                    }
+                });
+            }
+        });
+        setup_test();
+
+        let pipeline = gst::parse::launch("fakesrc num-buffers=10 ! identity ! fakesink")
+            .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+        // The bridge is installed with the pipeline and exports within the log flush interval
+        let cat = gst::DebugCategory::get("GST_TRACER").expect("Expected the tracer category");
+        gst::warning!(cat, "bridged to the collector");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut seen = Vec::new();
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            let Ok(request_line) = requests.recv_timeout(remaining) else {
+                break;
+            };
+            if request_line.starts_with("POST /v1/logs ") {
+                return;
+            }
+            seen.push(request_line);
+        }
+        panic!("Expected a log export to /v1/logs, got {seen:?}");
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "otel-tracer(endpoint=\"http://127.0.0.1:{COLLECTOR_PORT}/v1/traces\",log-flush-interval-ms=100)"
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use gst::prelude::*;
+    use gstreamer as gst;
+    use gsttracercommon::test_util as common;
+
+    #[test]
+    fn given_memory_exporter_when_bin_pipeline_runs_then_spans_nest_per_buffer() {
+        setup_test();
+
+        // The bin's ghost pads sit between every element, spans are still per real pad pair
+        let pipeline = gst::parse::launch(
+            "fakesrc name=mem_src num-buffers=3 ! ( name=mem_bin identity name=mem_id ) ! fakesink name=mem_sink",
+        )
+        .expect("Failed to create pipeline from launch string")
+        .downcast::<gst::Pipeline>()
+        .expect("Failed to downcast to gst::Pipeline");
+        pipeline.set_property("name", "memory");
+        common::run_to_eos(&pipeline);
+
+        let spans = finished_spans("memory");
+        let into_identity = pushes_from(&spans, "mem_src");
+        let into_sink = pushes_from(&spans, "mem_id");
+        assert_eq!(into_identity.len(), 3);
+        assert_eq!(into_sink.len(), 3);
+
+        for span in &into_identity {
+            assert_eq!(span.get::<String>("sink_pad.element").unwrap(), "mem_id");
+            assert_eq!(
+                span.get::<String>("parent-span-id").unwrap(),
+                "0000000000000000"
+            );
+        }
+        // identity pushes from within its chain function, so each push nests in the one into it
+        for span in &into_sink {
+            assert_eq!(span.get::<String>("sink_pad.element").unwrap(), "mem_sink");
+            let parent = span.get::<String>("parent-span-id").unwrap();
+            let upstream = into_identity
+                .iter()
+                .find(|s| s.get::<String>("span-id").unwrap() == parent)
+                .expect("Expected the push into identity as parent");
+            assert_eq!(
+                upstream.get::<String>("trace-id").unwrap(),
+                span.get::<String>("trace-id").unwrap()
+            );
+            assert_eq!(
+                upstream.get::<i64>("buffer.seq").unwrap(),
+                span.get::<i64>("buffer.seq").unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn given_tee_pipeline_when_run_then_branch_spans_linked_to_upstream() {
+        setup_test();
+
+        let pipeline = gst::parse::launch(
+            "fakesrc name=tee_src num-buffers=100 ! tee name=t ! queue name=branch_a ! fakesink t. ! queue name=branch_b ! fakesink",
+        )
+        .expect("Failed to create pipeline from launch string")
+        .downcast::<gst::Pipeline>()
+        .expect("Failed to downcast to gst::Pipeline");
+        pipeline.set_property("name", "tee");
+        common::run_to_eos(&pipeline);
+
+        let tee = pipeline.by_name("t").expect("Expected the tee");
+        assert_eq!(tee.num_src_pads(), 2);
+        let span_quark = gst::glib::Quark::from_str("otel-trace");
+        for name in ["branch_a", "branch_b"] {
+            let sink_pad = pipeline
+                .by_name(name)
+                .and_then(|e| e.static_pad("sink"))
+                .expect("Expected queue with a sink pad");
+            assert!(
+                unsafe { sink_pad.qdata::<u8>(span_quark) }.is_none(),
+                "Expected the linked branch span on {name} to be ended"
+            );
+        }
+
+        let spans = finished_spans("tee");
+        let into_tee = pushes_from(&spans, "tee_src");
+        assert_eq!(into_tee.len(), 100);
+        for branch in ["branch_a", "branch_b"] {
+            let into_branch = pushes_from(&spans, "t")
+                .into_iter()
+                .filter(|s| s.get::<String>("sink_pad.element").as_deref() == Ok(branch))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                into_branch.len(),
+                100,
+                "Expected a span per buffer into {branch}"
+            );
+            // Each branch starts its own trace, linked to the push into the tee
+            for span in &into_branch {
+                assert_eq!(
+                    span.get::<String>("parent-span-id").unwrap(),
+                    "0000000000000000"
+                );
+                let links = span.get::<gst::Array>("links").unwrap();
+                assert_eq!(links.len(), 1, "Expected a single link on {branch}");
+                let link = links[0].get::<gst::Structure>().unwrap();
+                let upstream = into_tee
+                    .iter()
+                    .find(|s| s.get::<String>("span-id") == link.get::<String>("span-id"))
+                    .expect("Expected the push into the tee as link");
+                assert_eq!(
+                    upstream.get::<String>("trace-id").unwrap(),
+                    link.get::<String>("trace-id").unwrap()
+                );
+                assert_ne!(
+                    upstream.get::<String>("trace-id").unwrap(),
+                    span.get::<String>("trace-id").unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn given_nested_bins_when_run_then_spans_name_real_elements() {
+        setup_test();
+
+        // Linking into a `( ... )` bin creates ghost pads; the inner bin nests them twice
+        let pipeline = gst::parse::launch(
+            "fakesrc name=bin_src num-buffers=100 ! ( name=outer ( name=inner_bin identity name=inner ) ) ! fakesink name=bin_sink",
+        )
+        .expect("Failed to create pipeline from launch string")
+        .downcast::<gst::Pipeline>()
+        .expect("Failed to downcast to gst::Pipeline");
+        pipeline.set_property("name", "bin");
+        common::run_to_eos(&pipeline);
+
+        let outer = pipeline.by_name("outer").expect("Expected the outer bin");
+        let outer_sink = outer.static_pad("sink").expect("Expected a ghost sink pad");
+        assert!(outer_sink.is::<gst::GhostPad>());
+
+        // Spans are kept on the real sink pads, never on the ghost pads in between
+        let span_quark = gst::glib::Quark::from_str("otel-trace");
+        let inner_sink = pipeline
+            .by_name("inner")
+            .and_then(|e| e.static_pad("sink"))
+            .expect("Expected identity with a sink pad");
+        let fakesink_sink = pipeline
+            .by_name("bin_sink")
+            .and_then(|e| e.static_pad("sink"))
+            .expect("Expected fakesink with a sink pad");
+        assert!(fakesink_sink
+            .peer()
+            .is_some_and(|peer| peer.is::<gst::GhostPad>()));
+        for pad in [&outer_sink, &inner_sink, &fakesink_sink] {
+            assert!(
+                unsafe { pad.qdata::<u8>(span_quark) }.is_none(),
+                "Expected no span left on {}",
+                pad.name()
+            );
+        }
+
+        // and they name the elements on either side of the ghost pads, never the bins
+        let spans = finished_spans("bin");
+        for (src, sink) in [("bin_src", "inner"), ("inner", "bin_sink")] {
+            let pushes = pushes_from(&spans, src);
+            assert_eq!(pushes.len(), 100, "Expected a span per push from {src}");
+            for span in pushes {
+                assert_eq!(span.get::<String>("sink_pad.element").unwrap(), sink);
+            }
+        }
+        assert!(spans.iter().all(|s| {
+            ["src_pad.element", "sink_pad.element"].iter().all(|field| {
+                !matches!(s.get::<String>(field).as_deref(), Ok("outer" | "inner_bin"))
+            })
+        }));
+    }
+
+    /// The spans of the pipeline named `pipeline` kept by the memory exporter so far.
+    fn finished_spans(pipeline: &str) -> Vec<gst::Structure> {
+        common::tracer("otel-tracer")
+            .emit_by_name::<gst::Array>("request-finished-spans", &[])
+            .iter()
+            .map(|v| v.get::<gst::Structure>().unwrap())
+            .filter(|s| s.get::<String>("pipeline.name").as_deref() == Ok(pipeline))
+            .collect()
+    }
+
+    /// The spans of pushes from `element`.
+    fn pushes_from<'a>(spans: &'a [gst::Structure], element: &str) -> Vec<&'a gst::Structure> {
+        spans
+            .iter()
+            .filter(|s| s.get::<String>("src_pad.element").as_deref() == Ok(element))
+            .collect()
+    }
+
+    fn setup_test() {
+        common::setup_tracers("otel-tracer(exporter=memory)");
+    }
+}
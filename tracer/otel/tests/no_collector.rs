@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+    use gstreamer as gst;
+    use gsttracercommon::test_util as common;
+    use std::time::{Duration, Instant};
+
+    /// Blackholed rather than refused, so the connect probe runs into its timeout.
+    const UNREACHABLE_ENDPOINT: &str = "http://10.255.255.1:4318/v1/traces";
+
+    #[test]
+    fn given_unreachable_collector_when_pipeline_runs_then_nothing_exported() {
+        setup_test();
+
+        let started = Instant::now();
+        let pipeline = gst::parse::launch("fakesrc num-buffers=100 ! identity ! fakesink")
+            .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        // Exporting would hold the EOS flush until it gives up after 5s; the failed probe
+        // leaves only its own 500ms
+        assert!(
+            started.elapsed() < Duration::from_secs(4),
+            "Expected the pipeline to finish quickly, took {:?}",
+            started.elapsed()
+        );
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "otel-tracer(endpoint=\"{UNREACHABLE_ENDPOINT}\",export-timeout-ms=10000)"
+        ));
+    }
+}
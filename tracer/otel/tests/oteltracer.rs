@@ -4,10 +4,8 @@ mod tests {
     #[allow(unused_imports)]
     use gstoteltracer::*;
     use gstreamer as gst;
-    use std::{
-        env::{self, consts::ARCH},
-        path::Path,
-    };
+    use gsttracercommon::test_util as common;
+    use std::time::{Duration, Instant};
 
     #[test]
     fn given_basic_pipeline_when_run_otel_then_metrics_captured() {
@@ -17,6 +15,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn given_pipeline_when_run_otel_then_span_count_increases() {
+        setup_test();
+        let span_count = || {
+            gst::active_tracers()
+                .iter()
+                .find(|t| t.type_().name() == "otel-tracer")
+                .expect("Expected an active otel-tracer")
+                .emit_by_name::<u64>("request-span-count", &[])
+        };
+        let before = span_count();
+        help_run_gstreamer_tests("span_count", "fakesrc num-buffers=20 ! identity ! fakesink");
+        // Other tests may run pipelines concurrently, so only a lower bound holds
+        assert!(span_count() >= before + 40);
+    }
+
     #[test]
     fn given_mthread_pipeline_when_run_otel_then_traces_captured() {
         help_run_gstreamer_tests(
@@ -26,38 +40,143 @@ mod tests {
     }
 
     #[test]
-    fn given_pipeline_with_bin_element_when_run_otel_then_traces_captured() {
-        // TODO will need to create a custom bin element, probably can't use help_run_gstreamer_tests directly
+    fn given_error_flow_return_when_run_otel_then_no_span_left_on_pad() {
+        setup_test();
+        // `error-after` makes identity return GST_FLOW_ERROR from its chain function
+        let pipeline = create_pipeline(
+            "error_flow",
+            "fakesrc num-buffers=10 ! identity name=failing error-after=3 ! fakesink name=failing_sink",
+        );
+        pipeline
+            .set_state(gst::State::Playing)
+            .expect("Unable to set the pipeline to Playing");
+        let msg = pipeline
+            .bus()
+            .unwrap()
+            .timed_pop_filtered(
+                gst::ClockTime::NONE,
+                &[gst::MessageType::Eos, gst::MessageType::Error],
+            )
+            .expect("Expected the pipeline to finish");
+        let gst::MessageView::Error(err) = msg.view() else {
+            panic!("Expected the pipeline to fail, got {msg:?}");
+        };
+        assert_eq!(
+            err.src().map(|s| s.name()).as_deref(),
+            Some("failing"),
+            "Expected the error from the failing identity"
+        );
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        let span_quark = gst::glib::Quark::from_str("otel-trace");
+        for name in ["failing", "failing_sink"] {
+            let sink_pad = pipeline
+                .by_name(name)
+                .and_then(|e| e.static_pad("sink"))
+                .expect("Expected element with a sink pad");
+            assert!(
+                unsafe { sink_pad.qdata::<u8>(span_quark) }.is_none(),
+                "Expected no span left on {name}'s sink pad"
+            );
+        }
+    }
+
+    #[test]
+    fn given_buffer_list_pipeline_when_run_otel_then_no_span_left_on_pad() {
+        // rtpvrawpay pushes a buffer list per frame
+        let pipeline = help_run_gstreamer_tests(
+            "buffer_list",
+            "videotestsrc num-buffers=5 ! video/x-raw,width=320,height=240 ! rtpvrawpay ! fakesink name=list_sink",
+        );
+
+        let sink_pad = pipeline
+            .by_name("list_sink")
+            .and_then(|e| e.static_pad("sink"))
+            .expect("Expected fakesink with a sink pad");
+        assert!(
+            unsafe { sink_pad.qdata::<u8>(gst::glib::Quark::from_str("otel-trace")) }.is_none(),
+            "Expected the list span to be ended by the list post hook"
+        );
+    }
+
+    #[test]
+    fn given_flushing_seek_when_run_otel_then_events_do_not_disturb_spans() {
+        setup_test();
+        let pipeline = gst::parse::launch(
+            "videotestsrc num-buffers=60 ! video/x-raw,framerate=30/1 ! identity name=seeked ! fakesink sync=false",
+        )
+        .expect("Failed to create pipeline from launch string")
+        .downcast::<gst::Pipeline>()
+        .expect("Failed to downcast to gst::Pipeline");
+        pipeline
+            .set_state(gst::State::Paused)
+            .expect("Unable to set the pipeline to Paused");
+        let bus = pipeline.bus().unwrap();
+        bus.timed_pop_filtered(gst::ClockTime::NONE, &[gst::MessageType::AsyncDone])
+            .expect("Expected the pipeline to preroll");
+
+        // The seek goes upstream, answered by flush-start, flush-stop and a new segment
+        pipeline
+            .seek_simple(
+                gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+                gst::ClockTime::from_seconds(1),
+            )
+            .expect("Expected the seek to be handled");
+        pipeline
+            .set_state(gst::State::Playing)
+            .expect("Unable to set the pipeline to Playing");
+        let msg = bus
+            .timed_pop_filtered(
+                gst::ClockTime::NONE,
+                &[gst::MessageType::Eos, gst::MessageType::Error],
+            )
+            .expect("Expected the pipeline to finish");
+        assert!(matches!(msg.view(), gst::MessageView::Eos(..)));
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        let sink_pad = pipeline
+            .by_name("seeked")
+            .and_then(|e| e.static_pad("sink"))
+            .expect("Expected identity with a sink pad");
+        assert!(
+            unsafe { sink_pad.qdata::<u8>(gst::glib::Quark::from_str("otel-trace")) }.is_none(),
+            "Expected no span left on the seeked element's sink pad"
+        );
     }
 
-    fn help_run_gstreamer_tests(name: &str, pipeline: &str) {
-        // Translates to directory containing this modules' Cargo.toml file.
-        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
-        // Set environment variables for the tracer
-        env::set_var("GST_TRACERS", "otel-tracer");
-        env::set_var(
-            "GST_DEBUG",
-            "fakesink:5,identity:5,GST_TRACER:5,otel-tracer:7",
+    /// Compare across commits with `just test bench_otel -- --nocapture`; every push starts a
+    /// span with the `src_pad.thread.*` attributes.
+    #[test]
+    fn bench_otel_tracer_through_pipeline() {
+        setup_test();
+        let pipeline = create_pipeline(
+            "bench",
+            "fakesrc num-buffers=10000 ! identity ! identity ! identity ! fakesink",
+        );
+        pipeline
+            .set_state(gst::State::Playing)
+            .expect("Unable to set the pipeline to Playing");
+
+        // Start wall-clock timer
+        let start = Instant::now();
+        common::wait_for_eos(&pipeline);
+        let elapsed = start.elapsed();
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        println!(
+            "otel-tracer: {:?} for 40000 pushes, {:?} per push",
+            elapsed,
+            elapsed / 40000
         );
-        let root_manifest_dir = manifest_dir.parent().unwrap().parent().unwrap();
-        let debug_plugin_path = root_manifest_dir.join("target/debug");
-        let release_plugin_path = root_manifest_dir.join("target/release");
-        let debug_plugin_with_target = debug_plugin_path.join(format!("{ARCH}-unknown-linux-gnu"));
-        let release_plugin_with_target =
-            release_plugin_path.join(format!("{ARCH}-unknown-linux-gnu"));
-        env::set_var(
-            "GST_PLUGIN_PATH",
-            format!(
-                "{}:{}:{}:{}",
-                release_plugin_with_target.to_str().unwrap(),
-                release_plugin_path.to_str().unwrap(),
-                debug_plugin_with_target.to_str().unwrap(),
-                debug_plugin_path.to_str().unwrap(),
-            ),
+        assert!(
+            elapsed < Duration::from_secs(5),
+            "Pipeline benchmark took too long: {:?}",
+            elapsed
         );
+    }
 
-        // Initialize GStreamer
-        gst::init().expect("Failed to initialize GStreamer");
+    fn setup_test() {
+        common::setup_tracers("otel-tracer");
 
         // Verify that our element is registered:
         assert!(
@@ -66,8 +185,9 @@ mod tests {
                 .any(|f| f.name() == "otel-tracer"),
             "Expected to find the `otel-tracer` element after registration"
         );
+    }
 
-        // Create the pipeline
+    fn create_pipeline(name: &str, pipeline: &str) -> gst::Pipeline {
         // This is a kludge to get around a real issue where metrics are reused
         // across multiple pipelines which use the same element and pad names.
         //
@@ -76,36 +196,15 @@ mod tests {
         let pipeline_el =
             gst::parse::launch(pipeline).expect("Failed to create pipeline from launch string");
         pipeline_el.set_property("name", name);
-        let pipeline = pipeline_el
+        pipeline_el
             .downcast::<gst::Pipeline>()
-            .expect("Failed to downcast to gst::Pipeline");
+            .expect("Failed to downcast to gst::Pipeline")
+    }
 
-        // Set the pipeline to the Playing state
+    fn help_run_gstreamer_tests(name: &str, pipeline: &str) -> gst::Pipeline {
+        setup_test();
+        let pipeline = create_pipeline(name, pipeline);
+        common::run_to_eos(&pipeline);
         pipeline
-            .set_state(gst::State::Playing)
-            .expect("Unable to set the pipeline to Playing");
-
-        // Grab the bus to listen for EOS
-        let bus = pipeline.bus().unwrap();
-
-        // Wait for EOS message
-        for msg in bus.iter_timed(gst::ClockTime::NONE) {
-            use gst::MessageView;
-            match msg.view() {
-                MessageView::Eos(..) => break,
-                MessageView::Error(err) => {
-                    println!(
-                        "Error from {:?}: {} ({:?})",
-                        err.src().map(|s| s.path_string()),
-                        err.error(),
-                        err.debug()
-                    );
-                    break;
-                }
-                _ => (),
-            }
-        }
-        // Stop the pipeline
-        pipeline.set_state(gst::State::Null).unwrap();
     }
 }
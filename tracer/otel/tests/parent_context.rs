@@ -0,0 +1,51 @@
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use gst::prelude::*;
+    use gstreamer as gst;
+    use gsttracercommon::test_util as common;
+
+    const TRACE_ID: &str = "0af7651916cd43dd8448eb211c80319c";
+    const PARENT_SPAN_ID: &str = "b7ad6b7169203331";
+
+    #[test]
+    fn given_application_parent_when_pipeline_runs_then_source_spans_join_its_trace() {
+        setup_test();
+        let tracer = common::tracer("otel-tracer");
+        assert!(!tracer.emit_by_name::<bool>("request-set-parent-context", &[&"not-a-traceparent"]));
+        assert!(tracer.emit_by_name::<bool>(
+            "request-set-parent-context",
+            &[&format!("00-{TRACE_ID}-{PARENT_SPAN_ID}-01")],
+        ));
+
+        let pipeline = gst::parse::launch(
+            "fakesrc name=app_src num-buffers=3 ! identity name=app_id ! fakesink",
+        )
+        .expect("Failed to create pipeline from launch string")
+        .downcast::<gst::Pipeline>()
+        .expect("Failed to downcast to gst::Pipeline");
+        pipeline.set_property("name", "application");
+        common::run_to_eos(&pipeline);
+        assert!(tracer.emit_by_name::<bool>("request-set-parent-context", &[&None::<String>]));
+
+        let spans = tracer
+            .emit_by_name::<gst::Array>("request-finished-spans", &[])
+            .iter()
+            .map(|v| v.get::<gst::Structure>().unwrap())
+            .filter(|s| s.get::<String>("pipeline.name").as_deref() == Ok("application"))
+            .collect::<Vec<_>>();
+        assert_eq!(spans.len(), 6);
+        // Every span joins the application's trace, only the source's pushes hang off its span
+        for span in &spans {
+            assert_eq!(span.get::<String>("trace-id").unwrap(), TRACE_ID);
+            let from_source = span.get::<String>("src_pad.element").unwrap() == "app_src";
+            assert_eq!(
+                span.get::<String>("parent-span-id").unwrap() == PARENT_SPAN_ID,
+                from_source
+            );
+        }
+    }
+
+    fn setup_test() {
+        common::setup_tracers("otel-tracer(exporter=memory)");
+    }
+}
@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use gstreamer as gst;
+    use gsttracercommon::test_util as common;
+    use std::{
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    const COLLECTOR_PORT: u16 = 19114;
+
+    #[test]
+    fn given_collector_never_answering_when_pipeline_runs_then_completes_within_export_timeout() {
+        // Accepts the probe and the exports but never answers, so exports wait for the timeout
+        let listener = TcpListener::bind(("127.0.0.1", COLLECTOR_PORT))
+            .expect("Failed to bind fake OTLP collector");
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        thread::spawn(move || {
+            let mut streams = Vec::new();
+            for stream in listener.incoming().flatten() {
+                accepted.fetch_add(1, Ordering::Relaxed);
+                streams.push(stream);
+            }
+        });
+        setup_test();
+
+        let started = Instant::now();
+        let pipeline = gst::parse::launch("fakesrc num-buffers=100 ! identity ! fakesink")
+            .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        // The EOS flush gives up after 5s; a 200ms export timeout ends it well before that
+        assert!(
+            started.elapsed() < Duration::from_secs(4),
+            "Expected the pipeline to finish quickly, took {:?}",
+            started.elapsed()
+        );
+        // The probe alone would be a single connection
+        assert!(
+            connections.load(Ordering::Relaxed) > 1,
+            "Expected the tracer to export to the collector"
+        );
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "otel-tracer(endpoint=\"http://127.0.0.1:{COLLECTOR_PORT}/v1/traces\",export-timeout-ms=200)"
+        ));
+    }
+}
@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod tests {
+    use gst::glib::translate::IntoGlib;
+    use gst::prelude::*;
+    use gstreamer as gst;
+    use gsttracercommon::test_util as common;
+
+    #[test]
+    fn given_span_every_n_when_pipeline_runs_then_pushes_counted_per_pad() {
+        setup_test();
+
+        let pipeline = gst::parse::launch("fakesrc name=counted num-buffers=7 ! fakesink")
+            .expect("Failed to create pipeline from launch string")
+            .downcast::<gst::Pipeline>()
+            .expect("Failed to downcast to gst::Pipeline");
+        common::run_to_eos(&pipeline);
+
+        // 7 pushes with n=3 traces pushes 0, 3 and 6, leaving the counter at 1
+        let src_pad = pipeline
+            .by_name("counted")
+            .and_then(|e| e.static_pad("src"))
+            .expect("Expected fakesrc with a src pad");
+        let count = unsafe {
+            gst::glib::gobject_ffi::g_object_get_qdata(
+                src_pad.as_ptr() as *mut _,
+                gst::glib::Quark::from_str("otel-push-count").into_glib(),
+            )
+        } as usize;
+        assert_eq!(count, 1);
+    }
+
+    fn setup_test() {
+        common::setup_tracers("otel-tracer(span-every-n=3)");
+    }
+}
@@ -0,0 +1,27 @@
+#[cfg(test)]
+mod tests {
+    use gst::prelude::*;
+    use gstreamer as gst;
+    use gsttracercommon::test_util as common;
+
+    #[test]
+    fn given_element_granularity_when_pipeline_runs_then_one_span_per_element() {
+        setup_test();
+        let span_count =
+            || common::tracer("otel-tracer").emit_by_name::<u64>("request-span-count", &[]);
+        let before = span_count();
+
+        let pipeline = gst::parse::launch("fakesrc num-buffers=20 ! identity ! fakesink")
+            .expect("Failed to create pipeline from launch string")
+            .downcast::<gst::Pipeline>()
+            .expect("Failed to downcast to gst::Pipeline");
+        common::run_to_eos(&pipeline);
+
+        // fakesrc, identity and fakesink, rather than a span per push
+        assert_eq!(span_count(), before + 3);
+    }
+
+    fn setup_test() {
+        common::setup_tracers("otel-tracer(span-granularity=element)");
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Longest time latencies stay buffered before being written out to the file.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Appends per pad pair latencies to a file as JSON lines, for offline analysis.
+#[derive(Debug)]
+pub(crate) struct FileSink {
+    writer: Mutex<FileWriter>,
+}
+
+#[derive(Debug)]
+struct FileWriter {
+    file: BufWriter<File>,
+    last_flush: Instant,
+}
+
+impl FileSink {
+    pub(crate) const DEFAULT_PATH: &'static str = "gst-prom-latency.jsonl";
+
+    /// Opens `path` for appending, creating it if needed.
+    pub(crate) fn create(path: &str) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: Mutex::new(FileWriter {
+                file: BufWriter::new(file),
+                last_flush: Instant::now(),
+            }),
+        })
+    }
+
+    /// Formats the JSON members of a pad pair once; they only change when its media type is
+    /// resolved.
+    pub(crate) fn fields(names: &[impl AsRef<str>], values: &[String]) -> String {
+        names
+            .iter()
+            .zip(values)
+            .map(|(name, value)| {
+                format!(
+                    "{}:{}",
+                    serde_json::Value::from(name.as_ref()),
+                    serde_json::Value::from(value.as_str())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub(crate) fn format_latency(ts: u64, latency_ns: u64, fields: &str) -> String {
+        format!("{{\"ts\":{ts},\"latency_ns\":{latency_ns},{fields}}}\n")
+    }
+
+    /// Buffers a latency measured at `ts`, flushing at most every `FLUSH_INTERVAL`. Like StatsD,
+    /// a failed write is dropped rather than reported on the streaming thread.
+    pub(crate) fn write_latency(&self, ts: u64, latency_ns: u64, fields: &str) {
+        let mut writer = self.writer.lock().unwrap();
+        let _ = writer
+            .file
+            .write_all(Self::format_latency(ts, latency_ns, fields).as_bytes());
+        if writer.last_flush.elapsed() >= FLUSH_INTERVAL {
+            writer.last_flush = Instant::now();
+            let _ = writer.file.flush();
+        }
+    }
+
+    /// Writes out all buffered latencies.
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.last_flush = Instant::now();
+        writer.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileSink;
+
+    #[test]
+    fn latencies_are_appended_as_json_lines() {
+        let path = std::env::temp_dir().join(format!("prom-latency-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let fields = FileSink::fields(
+            &["element", "src_pad"],
+            &["q\"1".to_string(), "src".to_string()],
+        );
+        assert_eq!(fields, r#""element":"q\"1","src_pad":"src""#);
+
+        let sink = FileSink::create(path.to_str().unwrap()).unwrap();
+        sink.write_latency(10, 1_500, &fields);
+        sink.write_latency(20, 2_500, &fields);
+        sink.flush().unwrap();
+
+        let lines = std::fs::read_to_string(&path).unwrap();
+        let lines = lines
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["ts"], 20);
+        assert_eq!(lines[1]["latency_ns"], 2_500);
+        assert_eq!(lines[1]["element"], "q\"1");
+        let _ = std::fs::remove_file(&path);
+    }
+}
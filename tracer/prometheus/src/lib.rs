@@ -16,8 +16,22 @@
  * Boston, MA 02110-1301, USA.
  */
 use gstreamer as gst;
+mod file;
+mod otlp;
 mod promlatency;
 mod promlatencyimp;
+mod statsd;
+mod summary;
+
+pub use promlatency::{PromLatencySettings, PromLatencyTracer, PromLatencyTracerBuilder};
+pub use promlatencyimp::{LatencyScope, LatencyUnit, PromLatencyTracerImp};
+pub use statsd::MetricsSink;
+// Re-exported as the type of `PromLatencySettings::filter` and `pad_filter`.
+pub use gsttracercommon::element_filter::Filter;
+// Re-exported as the type of `PromLatencySettings::filters`.
+pub use gsttracercommon::tracer_flags::TracedTypes;
+// Re-exported so embedders use the same version as `PromLatencyTracer::metric_families`.
+pub use prometheus;
 
 // ───────────────── plugin boilerplate ──────────────────
 pub fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
@@ -0,0 +1,229 @@
+use prometheus::proto::{Metric, MetricFamily, MetricType};
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Longest a push to the collector may take, so a hung collector cannot stall disposal.
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+/// `AGGREGATION_TEMPORALITY_CUMULATIVE`, as Prometheus counters never reset between scrapes.
+const CUMULATIVE: u8 = 2;
+
+/// Pushes gathered metric families to an OTLP/HTTP collector, encoded as OTLP JSON.
+#[derive(Debug)]
+pub(crate) struct OtlpExporter {
+    url: String,
+    client: reqwest::blocking::Client,
+    /// Start of every cumulative series, as the tracer's counters start with it.
+    start_time_unix_nano: u64,
+}
+
+impl OtlpExporter {
+    /// Reported as the `service.name` resource attribute.
+    pub(crate) const SERVICE_NAME: &'static str = "gst.prom-latency";
+
+    pub(crate) fn new(endpoint: &str) -> reqwest::Result<Self> {
+        Ok(Self {
+            url: Self::metrics_url(endpoint),
+            client: reqwest::blocking::Client::builder()
+                .timeout(EXPORT_TIMEOUT)
+                .build()?,
+            start_time_unix_nano: unix_nanos(),
+        })
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Like `OTEL_EXPORTER_OTLP_ENDPOINT`, a base endpoint gets the metrics path appended.
+    pub(crate) fn metrics_url(endpoint: &str) -> String {
+        if endpoint.ends_with("/v1/metrics") {
+            endpoint.to_string()
+        } else {
+            format!("{}/v1/metrics", endpoint.trim_end_matches('/'))
+        }
+    }
+
+    pub(crate) fn export(&self, metric_families: &[MetricFamily]) -> reqwest::Result<()> {
+        let body = Self::encode(metric_families, self.start_time_unix_nano, unix_nanos());
+        self.client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Encodes `metric_families` as an OTLP `ExportMetricsServiceRequest`.
+    ///
+    /// Counters become monotonic cumulative sums, gauges and untyped metrics gauges, and
+    /// summaries and histograms their OTLP counterparts. Labels become attributes.
+    pub(crate) fn encode(metric_families: &[MetricFamily], start: u64, now: u64) -> Value {
+        let metrics = metric_families
+            .iter()
+            .map(|mf| {
+                let points = |value: fn(&Metric) -> Value| {
+                    mf.get_metric()
+                        .iter()
+                        .map(|m| {
+                            let mut point = value(m);
+                            point["attributes"] = attributes(m);
+                            point["startTimeUnixNano"] = start.to_string().into();
+                            point["timeUnixNano"] = now.to_string().into();
+                            point
+                        })
+                        .collect::<Vec<_>>()
+                };
+                let mut metric = json!({ "name": mf.name(), "description": mf.help() });
+                match mf.get_field_type() {
+                    MetricType::COUNTER => {
+                        let data_points =
+                            points(|m| json!({ "asDouble": m.get_counter().value() }));
+                        metric["sum"] = json!({
+                            "dataPoints": data_points,
+                            "aggregationTemporality": CUMULATIVE,
+                            "isMonotonic": true,
+                        });
+                    }
+                    MetricType::GAUGE => {
+                        let data_points = points(|m| json!({ "asDouble": m.get_gauge().value() }));
+                        metric["gauge"] = json!({ "dataPoints": data_points });
+                    }
+                    MetricType::UNTYPED => {
+                        let data_points = points(|m| json!({ "asDouble": m.untyped.value() }));
+                        metric["gauge"] = json!({ "dataPoints": data_points });
+                    }
+                    MetricType::SUMMARY => {
+                        metric["summary"] = json!({ "dataPoints": points(summary_point) });
+                    }
+                    MetricType::HISTOGRAM => {
+                        metric["histogram"] = json!({
+                            "dataPoints": points(histogram_point),
+                            "aggregationTemporality": CUMULATIVE,
+                        });
+                    }
+                }
+                metric
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [{
+                        "key": "service.name",
+                        "value": { "stringValue": Self::SERVICE_NAME },
+                    }],
+                },
+                "scopeMetrics": [{
+                    "scope": { "name": "prom-latency", "version": env!("CARGO_PKG_VERSION") },
+                    "metrics": metrics,
+                }],
+            }],
+        })
+    }
+}
+
+fn summary_point(metric: &Metric) -> Value {
+    let summary = metric.get_summary();
+    let quantiles = summary
+        .get_quantile()
+        .iter()
+        .map(|q| json!({ "quantile": q.quantile(), "value": q.value() }))
+        .collect::<Vec<_>>();
+    json!({
+        "count": summary.sample_count().to_string(),
+        "sum": summary.sample_sum(),
+        "quantileValues": quantiles,
+    })
+}
+
+/// OTLP buckets are not cumulative and end with an implicit `+Inf` bucket.
+fn histogram_point(metric: &Metric) -> Value {
+    let histogram = metric.get_histogram();
+    let mut bounds = Vec::new();
+    let mut counts = Vec::new();
+    let mut below = 0;
+    for bucket in histogram.get_bucket() {
+        if bucket.upper_bound() == f64::INFINITY {
+            continue;
+        }
+        bounds.push(bucket.upper_bound());
+        counts.push(bucket.cumulative_count().saturating_sub(below).to_string());
+        below = bucket.cumulative_count();
+    }
+    counts.push(histogram.sample_count().saturating_sub(below).to_string());
+    json!({
+        "count": histogram.sample_count().to_string(),
+        "sum": histogram.sample_sum(),
+        "bucketCounts": counts,
+        "explicitBounds": bounds,
+    })
+}
+
+fn attributes(metric: &Metric) -> Value {
+    metric
+        .get_label()
+        .iter()
+        .map(|lp| json!({ "key": lp.name(), "value": { "stringValue": lp.value() } }))
+        .collect()
+}
+
+fn unix_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OtlpExporter;
+    use prometheus::{
+        register_histogram_vec_with_registry, register_int_counter_vec_with_registry, Registry,
+    };
+
+    #[test]
+    fn metric_families_are_encoded_as_otlp_json() {
+        let registry = Registry::new();
+        let counter = register_int_counter_vec_with_registry!(
+            "gst_element_latency_count_count",
+            "Count of latencies",
+            &["element"],
+            registry
+        )
+        .unwrap();
+        counter.with_label_values(&["identity0"]).inc_by(3);
+        let histogram = register_histogram_vec_with_registry!(
+            "gst_element_state_change_seconds",
+            "State change durations",
+            &["element"],
+            vec![0.1, 1.0],
+            registry
+        )
+        .unwrap();
+        for value in [0.05, 0.5, 5.0] {
+            histogram.with_label_values(&["identity0"]).observe(value);
+        }
+
+        let request = OtlpExporter::encode(&registry.gather(), 10, 20);
+        let metrics = &request["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        let count = &metrics[0];
+        assert_eq!(count["name"], "gst_element_latency_count_count");
+        assert_eq!(count["sum"]["isMonotonic"], true);
+        let point = &count["sum"]["dataPoints"][0];
+        assert_eq!(point["asDouble"], 3.0);
+        assert_eq!(point["startTimeUnixNano"], "10");
+        assert_eq!(point["attributes"][0]["key"], "element");
+        assert_eq!(point["attributes"][0]["value"]["stringValue"], "identity0");
+
+        let point = &metrics[1]["histogram"]["dataPoints"][0];
+        assert_eq!(point["count"], "3");
+        assert_eq!(point["explicitBounds"], serde_json::json!([0.1, 1.0]));
+        assert_eq!(point["bucketCounts"], serde_json::json!(["1", "1", "1"]));
+
+        assert_eq!(
+            OtlpExporter::metrics_url("http://collector:4318/"),
+            "http://collector:4318/v1/metrics"
+        );
+    }
+}
@@ -1,49 +1,364 @@
-use glib::prelude::*;
+use glib::{prelude::*, translate::FromGlibPtrBorrow};
+use gst::subclass::prelude::*;
 use gstreamer as gst;
+use gsttracercommon::element_filter::Filter;
+use gsttracercommon::tracer_flags::{LatencyFlags, TracedTypes};
+use std::str::FromStr;
 
-use crate::promlatencyimp::{PromLatencyTracerImp, CAT};
+use crate::{
+    file::FileSink,
+    promlatencyimp::{LatencyScope, LatencyUnit, PromLatencyTracerImp, CAT},
+    statsd::{MetricsSink, StatsdSink},
+    summary::LatencySummary,
+};
 
-mod imp {
-    use super::*;
-    use gst::subclass::prelude::*;
-    use std::{
-        str::FromStr,
-        sync::{OnceLock, RwLock},
-    };
+/// Environment variable setting the port of the metrics HTTP server when the `port` parameter
+/// is not given.
+const PORT_ENV: &str = "GST_PROMETHEUS_TRACER_PORT";
+
+/// Effective settings of a `PromLatencyTracer`, as parsed from its tracer parameters.
+///
+/// Each field corresponds to the tracer parameter of the same name; see
+/// [`PromLatencyTracerBuilder`] for what they do.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct PromLatencySettings {
+    /// Port of the metrics HTTP server.
+    pub server_port: u16,
+    /// Ports after `server_port` tried in turn when it is already in use.
+    pub port_retries: u16,
+    /// When false no HTTP server is started; metrics are only available via signal.
+    pub http_server: bool,
+    /// Threads answering scrapes.
+    pub server_threads: usize,
+    /// Pushgateway metrics are pushed to, if any.
+    pub pushgateway_url: Option<String>,
+    /// OTLP/HTTP collector metrics are also exported to, if any.
+    pub otlp_endpoint: Option<String>,
+    /// Interval between pushes to the Pushgateway or OTLP collector.
+    pub push_interval_ms: u64,
+    /// Unit latencies are reported in.
+    pub unit: LatencyUnit,
+    /// When true, times are reported as float seconds under `_seconds` names.
+    pub seconds: bool,
+    /// When true, element state change durations are recorded.
+    pub state_changes: bool,
+    /// When true, the thread CPU time spent in each element is recorded.
+    pub cpu_time: bool,
+    /// When true, latencies are also exposed as a summary with `summary_quantiles`.
+    pub summary: bool,
+    /// Quantiles of the latency summary, each in `[0, 1]`.
+    pub summary_quantiles: Vec<f64>,
+    /// Distinct label sets before new pad pairs record into the overflow series, 0 for no limit.
+    pub max_series: usize,
+    /// Where latencies are reported to.
+    pub sink: MetricsSink,
+    /// `host:port` of the StatsD daemon, only used with `sink=statsd`.
+    pub statsd_address: String,
+    /// File latencies are appended to, only used with `sink=file`.
+    pub file_path: String,
+    /// Source element property added as an extra label to every pad pair metric.
+    pub label_from_property: Option<String>,
+    /// Elements whose latency is measured, by name.
+    pub filter: Filter,
+    /// Pads whose latency is measured, by `element.pad` name of the pad buffers arrive on.
+    pub pad_filter: Filter,
+    /// Whether the latency of every element or only end-to-end latency is measured.
+    pub scope: LatencyScope,
+    /// Whether source-to-sink latency is recorded, which stamps buffers leaving sources. Always
+    /// on with `scope=pipeline`.
+    pub pipeline_latency: bool,
+    /// Objects whose pushes are hooked, from `filters`.
+    pub filters: TracedTypes,
+}
 
-    #[derive(Debug)]
-    struct Settings {
-        pub server_port: u16,
+impl Default for PromLatencySettings {
+    fn default() -> Self {
+        Self {
+            server_port: 8080u16,
+            port_retries: 0,
+            http_server: true,
+            server_threads: 2,
+            pushgateway_url: None,
+            otlp_endpoint: None,
+            push_interval_ms: 5000,
+            unit: LatencyUnit::default(),
+            seconds: false,
+            state_changes: false,
+            cpu_time: false,
+            summary: false,
+            summary_quantiles: LatencySummary::DEFAULT_QUANTILES.to_vec(),
+            max_series: PromLatencyTracerImp::DEFAULT_MAX_SERIES,
+            sink: MetricsSink::default(),
+            statsd_address: StatsdSink::DEFAULT_ADDRESS.to_string(),
+            file_path: FileSink::DEFAULT_PATH.to_string(),
+            label_from_property: None,
+            filter: Filter::default(),
+            pad_filter: Filter::default(),
+            scope: LatencyScope::default(),
+            pipeline_latency: false,
+            filters: TracedTypes::default(),
+        }
+    }
+}
+
+impl PromLatencySettings {
+    /// Reads the settings for `tracer` from the environment, then from its `params` property.
+    pub fn from_tracer(tracer: &gst::Tracer) -> Self {
+        let mut settings = Self::default();
+        settings.update_from_env(tracer);
+        if let Some(params) = tracer.property::<Option<String>>("params") {
+            settings.update_from_params(tracer, params);
+        }
+        settings
     }
 
-    impl Default for Settings {
-        fn default() -> Self {
-            Self {
-                server_port: 8080u16,
+    /// The port set through `PORT_ENV`, if it is set to a valid port.
+    fn env_port(tracer: &gst::Tracer) -> Option<u16> {
+        let value = std::env::var(PORT_ENV).ok()?;
+        match value.parse::<u16>() {
+            Ok(port) => Some(port),
+            Err(err) => {
+                gst::warning!(
+                    CAT,
+                    obj = tracer,
+                    "ignoring {}={}: {}",
+                    PORT_ENV,
+                    value,
+                    err
+                );
+                None
             }
         }
     }
 
-    impl Settings {
-        fn update_from_params(&mut self, imp: &PromLatencyTracer, params: String) {
-            let s = match gst::Structure::from_str(&format!("prom-latency,{params}")) {
-                Ok(s) => s,
-                Err(err) => {
-                    gst::warning!(CAT, imp = imp, "failed to parse tracer parameters: {}", err);
-                    return;
+    /// Takes the port from `PORT_ENV`, which the `port` parameter overrides.
+    fn update_from_env(&mut self, tracer: &gst::Tracer) {
+        if let Some(port) = Self::env_port(tracer) {
+            gst::info!(
+                CAT,
+                obj = tracer,
+                "setting port to {} from {}",
+                port,
+                PORT_ENV
+            );
+            self.server_port = port;
+        }
+    }
+
+    fn update_from_params(&mut self, tracer: &gst::Tracer, params: String) {
+        let s = match gst::Structure::from_str(&format!("prom-latency,{params}")) {
+            Ok(s) => s,
+            Err(err) => {
+                gst::warning!(
+                    CAT,
+                    obj = tracer,
+                    "failed to parse tracer parameters: {}",
+                    err
+                );
+                return;
+            }
+        };
+        if let Ok(v) = s.get::<i32>("port") {
+            if let Some(env_port) = Self::env_port(tracer).filter(|port| *port != v as u16) {
+                gst::warning!(
+                    CAT,
+                    obj = tracer,
+                    "port={} overrides {}={}",
+                    v,
+                    PORT_ENV,
+                    env_port
+                );
+            }
+            gst::info!(CAT, obj = tracer, "setting port to {}", v);
+            self.server_port = v as u16;
+        }
+        if let Ok(v) = s.get::<i32>("port-retries") {
+            gst::log!(CAT, obj = tracer, "setting port-retries to {}", v);
+            self.port_retries = v.clamp(0, u16::MAX as i32) as u16;
+        }
+        if let Ok(v) = s.get::<bool>("http-server") {
+            gst::log!(CAT, obj = tracer, "setting http-server to {}", v);
+            self.http_server = v;
+        }
+        if let Ok(v) = s.get::<i32>("server-threads") {
+            gst::log!(CAT, obj = tracer, "setting server-threads to {}", v);
+            self.server_threads = v.max(1) as usize;
+        }
+        if let Ok(v) = s.get::<String>("pushgateway-url") {
+            gst::log!(CAT, obj = tracer, "setting pushgateway-url to {}", v);
+            self.pushgateway_url = Some(v);
+        }
+        if let Ok(v) = s.get::<String>("otlp-endpoint") {
+            gst::log!(CAT, obj = tracer, "setting otlp-endpoint to {}", v);
+            self.otlp_endpoint = Some(v);
+        }
+        if let Ok(v) = s.get::<i32>("push-interval-ms") {
+            gst::log!(CAT, obj = tracer, "setting push-interval-ms to {}", v);
+            self.push_interval_ms = v.max(1) as u64;
+        }
+        if let Ok(v) = s.get::<String>("unit") {
+            match LatencyUnit::from_param(&v) {
+                Some(unit) => {
+                    gst::log!(CAT, obj = tracer, "setting unit to {:?}", unit);
+                    self.unit = unit;
+                }
+                None => {
+                    gst::warning!(
+                        CAT,
+                        obj = tracer,
+                        "unknown unit {}, expected ns, us or ms",
+                        v
+                    )
+                }
+            }
+        }
+        if let Ok(v) = s.get::<bool>("seconds") {
+            gst::log!(CAT, obj = tracer, "setting seconds to {}", v);
+            self.seconds = v;
+        }
+        if let Ok(v) = s.get::<bool>("state-changes") {
+            gst::log!(CAT, obj = tracer, "setting state-changes to {}", v);
+            self.state_changes = v;
+        }
+        if let Ok(v) = s.get::<bool>("cpu-time") {
+            gst::log!(CAT, obj = tracer, "setting cpu-time to {}", v);
+            self.cpu_time = v;
+        }
+        if let Ok(v) = s.get::<bool>("summary") {
+            gst::log!(CAT, obj = tracer, "setting summary to {}", v);
+            self.summary = v;
+        }
+        if let Ok(v) = s.get::<String>("summary-quantiles") {
+            let quantiles = LatencySummary::parse_quantiles(&v);
+            if quantiles.is_empty() {
+                gst::warning!(
+                    CAT,
+                    obj = tracer,
+                    "no valid quantiles in {}, expected values in [0, 1] like 0.5,0.99",
+                    v
+                );
+            } else {
+                gst::log!(
+                    CAT,
+                    obj = tracer,
+                    "setting summary-quantiles to {:?}",
+                    quantiles
+                );
+                self.summary_quantiles = quantiles;
+            }
+        }
+        if let Ok(v) = s.get::<i32>("max-series") {
+            gst::log!(CAT, obj = tracer, "setting max-series to {}", v);
+            self.max_series = v.max(0) as usize;
+        }
+        if let Ok(v) = s.get::<String>("sink") {
+            match MetricsSink::from_param(&v) {
+                Some(sink) => {
+                    gst::log!(CAT, obj = tracer, "setting sink to {:?}", sink);
+                    self.sink = sink;
+                }
+                None => gst::warning!(
+                    CAT,
+                    obj = tracer,
+                    "unknown sink {}, expected prometheus, statsd or file",
+                    v
+                ),
+            }
+        }
+        if let Ok(v) = s.get::<String>("statsd-address") {
+            gst::log!(CAT, obj = tracer, "setting statsd-address to {}", v);
+            self.statsd_address = v;
+        }
+        if let Ok(v) = s.get::<String>("file-path") {
+            gst::log!(CAT, obj = tracer, "setting file-path to {}", v);
+            self.file_path = v;
+        }
+        if let Ok(v) = s.get::<String>("label-from-property") {
+            gst::log!(CAT, obj = tracer, "setting label-from-property to {}", v);
+            self.label_from_property = Some(v);
+        }
+        if let Ok(v) = s.get::<String>("filter") {
+            gst::log!(CAT, obj = tracer, "setting filter to {}", v);
+            self.filter = Filter::new(&v);
+        }
+        if let Ok(v) = s.get::<String>("pad-filter") {
+            gst::log!(CAT, obj = tracer, "setting pad-filter to {}", v);
+            self.pad_filter = Filter::new(&v);
+        }
+        // The `latency` tracer's flags, of which `scope` is the explicit form
+        if let Ok(v) = s.get::<String>("flags") {
+            match LatencyFlags::parse(&v) {
+                Ok(flags) => {
+                    if flags.reported {
+                        gst::warning!(CAT, obj = tracer, "reported latency is not measured");
+                    }
+                    if flags.element {
+                        self.scope = LatencyScope::Element;
+                    } else if flags.pipeline {
+                        self.scope = LatencyScope::Pipeline;
+                    }
+                    self.pipeline_latency = flags.pipeline;
+                    gst::log!(
+                        CAT,
+                        obj = tracer,
+                        "setting scope to {:?} and pipeline-latency to {} from flags",
+                        self.scope,
+                        self.pipeline_latency
+                    );
+                }
+                Err(flag) => gst::warning!(
+                    CAT,
+                    obj = tracer,
+                    "unknown flag {}, expected pipeline, element or reported",
+                    flag
+                ),
+            }
+        }
+        if let Ok(v) = s.get::<String>("filters") {
+            match TracedTypes::parse(&v) {
+                Ok(filters) => {
+                    gst::log!(CAT, obj = tracer, "setting filters to {:?}", filters);
+                    self.filters = filters;
                 }
-            };
-            if let Ok(v) = s.get::<i32>("port") {
-                gst::log!(CAT, imp = imp, "setting port to {}", v);
-                self.server_port = v as u16;
+                Err(name) => gst::warning!(
+                    CAT,
+                    obj = tracer,
+                    "unknown filter {}, expected GstBuffer, GstBufferList or GstEvent",
+                    name
+                ),
             }
         }
+        if let Ok(v) = s.get::<String>("scope") {
+            match LatencyScope::from_param(&v) {
+                Some(scope) => {
+                    gst::log!(CAT, obj = tracer, "setting scope to {:?}", scope);
+                    self.scope = scope;
+                }
+                None => gst::warning!(
+                    CAT,
+                    obj = tracer,
+                    "unknown scope {}, expected element or pipeline",
+                    v
+                ),
+            }
+        }
+        if let Ok(v) = s.get::<bool>("pipeline-latency") {
+            gst::log!(CAT, obj = tracer, "setting pipeline-latency to {}", v);
+            self.pipeline_latency = v;
+        }
     }
+}
+
+mod imp {
+    use super::*;
+    use gst::subclass::prelude::*;
+    use std::sync::{OnceLock, RwLock};
 
     #[derive(Default)]
     pub struct PromLatencyTracer {
-        core: PromLatencyTracerImp,
-        settings: RwLock<Settings>,
+        pub(super) core: PromLatencyTracerImp,
+        pub(super) settings: RwLock<PromLatencySettings>,
     }
 
     #[glib::object_subclass]
@@ -59,50 +374,131 @@ mod imp {
             let obj = self.obj();
             let tracer_obj: &gst::Tracer = obj.upcast_ref();
 
-            // Update settings from parameters if provided
-            if let Some(params) = self.obj().property::<Option<String>>("params") {
-                let mut settings = self.settings.write().unwrap();
-                settings.update_from_params(self, params);
-                gst::debug!(CAT, imp = self, "using settings: {:?}", *settings);
-            }
-
-            // Register all tracer hooks via the core implementation
-            self.core.constructed(tracer_obj);
+            let settings = PromLatencySettings::from_tracer(tracer_obj);
+            gst::debug!(CAT, imp = self, "using settings: {:?}", settings);
+            self.core.configure(&settings);
+            PromLatencyTracerImp::register_hooks(tracer_obj, settings.filters);
+            *self.settings.write().unwrap() = settings;
 
             // Register callback to start metrics server if needed.
             self.register_hook(TracerHook::ElementNew);
+
+            if self.settings.read().unwrap().state_changes {
+                self.register_hook(TracerHook::ElementChangeStatePre);
+                self.register_hook(TracerHook::ElementChangeStatePost);
+            }
         }
 
         fn signals() -> &'static [glib::subclass::Signal] {
             static SIGNALS: OnceLock<Vec<glib::subclass::Signal>> = OnceLock::new();
             SIGNALS.get_or_init(|| {
-                vec![glib::subclass::Signal::builder("metrics")
-                    .flags(glib::SignalFlags::ACTION)
-                    .return_type::<Option<String>>()
-                    .class_handler(|_, _args| {
-                        let ret = PromLatencyTracerImp::request_metrics();
-                        gst::info!(
-                            CAT,
-                            "Prometheus metrics requested via signal, returning {} bytes",
-                            ret.len()
-                        );
-                        Some(ret.to_value())
-                    })
-                    .accumulator(|_hint, ret, value| {
-                        *ret = value.clone();
-                        true
-                    })
-                    .build()]
+                vec![
+                    glib::subclass::Signal::builder("metrics")
+                        .flags(glib::SignalFlags::ACTION)
+                        .return_type::<Option<String>>()
+                        .class_handler(|_, args| {
+                            let tracer = args[0]
+                                .get::<super::PromLatencyTracer>()
+                                .expect("signal arg");
+                            let ret = tracer.imp().core.request_metrics();
+                            gst::info!(
+                                CAT,
+                                "Prometheus metrics requested via signal, returning {} bytes",
+                                ret.len()
+                            );
+                            Some(ret.to_value())
+                        })
+                        .accumulator(|_hint, ret, value| {
+                            *ret = value.clone();
+                            true
+                        })
+                        .build(),
+                    glib::subclass::Signal::builder("request-metrics-json")
+                        .flags(glib::SignalFlags::ACTION)
+                        .return_type::<Option<String>>()
+                        .class_handler(|_, args| {
+                            let tracer = args[0]
+                                .get::<super::PromLatencyTracer>()
+                                .expect("signal arg");
+                            let ret = tracer.imp().core.request_metrics_json();
+                            gst::info!(
+                                CAT,
+                                "Prometheus metrics requested as JSON via signal, returning {} bytes",
+                                ret.len()
+                            );
+                            Some(ret.to_value())
+                        })
+                        .accumulator(|_hint, ret, value| {
+                            *ret = value.clone();
+                            true
+                        })
+                        .build(),
+                    glib::subclass::Signal::builder("request-percentiles")
+                        .flags(glib::SignalFlags::ACTION)
+                        .return_type::<Option<String>>()
+                        .class_handler(|_, args| {
+                            let tracer = args[0]
+                                .get::<super::PromLatencyTracer>()
+                                .expect("signal arg");
+                            let ret = tracer.imp().core.request_percentiles();
+                            gst::info!(
+                                CAT,
+                                "Latency percentiles requested via signal, returning {} bytes",
+                                ret.len()
+                            );
+                            Some(ret.to_value())
+                        })
+                        .accumulator(|_hint, ret, value| {
+                            *ret = value.clone();
+                            true
+                        })
+                        .build(),
+                    // Starts a new min/max latency window without scraping
+                    glib::subclass::Signal::builder("reset-metrics")
+                        .flags(glib::SignalFlags::ACTION)
+                        .class_handler(|_, args| {
+                            let tracer = args[0]
+                                .get::<super::PromLatencyTracer>()
+                                .expect("signal arg");
+                            tracer.imp().core.metrics().reset_min_max();
+                            gst::info!(CAT, "Prometheus min/max latencies reset via signal");
+                            None
+                        })
+                        .build(),
+                ]
             })
         }
+
+        fn dispose(&self) {
+            self.core.dispose();
+        }
     }
 
     impl GstObjectImpl for PromLatencyTracer {}
 
     impl TracerImpl for PromLatencyTracer {
         fn element_new(&self, ts: u64, element: &gst::Element) {
-            let port = self.settings.read().unwrap().server_port;
-            self.core.element_new(ts, element, port);
+            self.core
+                .element_new(ts, element, &self.settings.read().unwrap());
+        }
+
+        fn element_change_state_pre(
+            &self,
+            ts: u64,
+            element: &gst::Element,
+            _change: gst::StateChange,
+        ) {
+            self.core.element_change_state_pre(ts, element);
+        }
+
+        fn element_change_state_post(
+            &self,
+            ts: u64,
+            element: &gst::Element,
+            change: gst::StateChange,
+            _result: Result<gst::StateChangeSuccess, gst::StateChangeError>,
+        ) {
+            self.core.element_change_state_post(ts, element, change);
         }
     }
 }
@@ -112,6 +508,324 @@ glib::wrapper! {
         @extends gst::Tracer, gst::Object;
 }
 
+impl PromLatencyTracer {
+    /// Returns the core of the tracer a hook was registered by.
+    ///
+    /// # Safety
+    ///
+    /// `tracer` must point to a live `PromLatencyTracer`, which holds for the duration of any hook
+    /// registered in `PromLatencyTracerImp::register_hooks`.
+    pub(crate) unsafe fn core_from_ptr<'a>(
+        tracer: *mut gst::ffi::GstTracer,
+    ) -> &'a PromLatencyTracerImp {
+        let tracer = gst::Tracer::from_glib_borrow(tracer);
+        let core: *const PromLatencyTracerImp =
+            &tracer.unsafe_cast_ref::<PromLatencyTracer>().imp().core;
+        &*core
+    }
+}
+
+/// Builds a `PromLatencyTracer` from Rust without going through `GST_TRACERS`.
+///
+/// Each setter maps onto the tracer parameter of the same name; anything left unset keeps the
+/// tracer's default. GStreamer must be initialized before calling [`build`](Self::build), and the
+/// returned tracer only observes pipelines for as long as it is kept alive.
+#[derive(Debug, Default, Clone)]
+#[must_use = "the builder does nothing until `build` is called"]
+pub struct PromLatencyTracerBuilder {
+    port: Option<u16>,
+    port_retries: Option<u16>,
+    http_server: Option<bool>,
+    server_threads: Option<usize>,
+    pushgateway_url: Option<String>,
+    otlp_endpoint: Option<String>,
+    push_interval_ms: Option<u64>,
+    unit: Option<LatencyUnit>,
+    seconds: Option<bool>,
+    state_changes: Option<bool>,
+    cpu_time: Option<bool>,
+    summary: Option<bool>,
+    summary_quantiles: Option<Vec<f64>>,
+    max_series: Option<usize>,
+    sink: Option<MetricsSink>,
+    statsd_address: Option<String>,
+    file_path: Option<String>,
+    label_from_property: Option<String>,
+    filter: Option<String>,
+    pad_filter: Option<String>,
+    scope: Option<LatencyScope>,
+    pipeline_latency: Option<bool>,
+    filters: Option<String>,
+}
+
+impl PromLatencyTracerBuilder {
+    /// Port for the metrics HTTP server (`port`).
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Ports after `port` to try in turn when it is already in use (`port-retries`).
+    pub fn port_retries(mut self, retries: u16) -> Self {
+        self.port_retries = Some(retries);
+        self
+    }
+
+    /// Whether to start the metrics HTTP server at all (`http-server`).
+    pub fn http_server(mut self, enabled: bool) -> Self {
+        self.http_server = Some(enabled);
+        self
+    }
+
+    /// Threads answering scrapes (`server-threads`).
+    pub fn server_threads(mut self, threads: usize) -> Self {
+        self.server_threads = Some(threads);
+        self
+    }
+
+    /// Pushgateway to push metrics to (`pushgateway-url`).
+    pub fn pushgateway_url(mut self, url: impl Into<String>) -> Self {
+        self.pushgateway_url = Some(url.into());
+        self
+    }
+
+    /// OTLP/HTTP collector to also export metrics to, e.g. `http://localhost:4318`
+    /// (`otlp-endpoint`).
+    pub fn otlp_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.otlp_endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Interval between Pushgateway or OTLP pushes in milliseconds (`push-interval-ms`).
+    pub fn push_interval_ms(mut self, interval_ms: u64) -> Self {
+        self.push_interval_ms = Some(interval_ms);
+        self
+    }
+
+    /// Unit latencies are reported in (`unit`).
+    pub fn unit(mut self, unit: LatencyUnit) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    /// Whether to report times as float seconds under `_seconds` names (`seconds`).
+    pub fn seconds(mut self, enabled: bool) -> Self {
+        self.seconds = Some(enabled);
+        self
+    }
+
+    /// Whether to record element state change durations (`state-changes`).
+    pub fn state_changes(mut self, enabled: bool) -> Self {
+        self.state_changes = Some(enabled);
+        self
+    }
+
+    /// Whether to record the thread CPU time spent in each element (`cpu-time`).
+    pub fn cpu_time(mut self, enabled: bool) -> Self {
+        self.cpu_time = Some(enabled);
+        self
+    }
+
+    /// Whether to also expose latencies as a summary (`summary`).
+    pub fn summary(mut self, enabled: bool) -> Self {
+        self.summary = Some(enabled);
+        self
+    }
+
+    /// Quantiles of the latency summary, each in `[0, 1]` (`summary-quantiles`).
+    pub fn summary_quantiles(mut self, quantiles: impl IntoIterator<Item = f64>) -> Self {
+        self.summary_quantiles = Some(quantiles.into_iter().collect());
+        self
+    }
+
+    /// Distinct label sets before new pad pairs record into the overflow series, 0 for no
+    /// limit (`max-series`).
+    pub fn max_series(mut self, max_series: usize) -> Self {
+        self.max_series = Some(max_series);
+        self
+    }
+
+    /// Where latencies are reported to (`sink`).
+    pub fn sink(mut self, sink: MetricsSink) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// `host:port` of the StatsD daemon used with [`MetricsSink::Statsd`] (`statsd-address`).
+    pub fn statsd_address(mut self, address: impl Into<String>) -> Self {
+        self.statsd_address = Some(address.into());
+        self
+    }
+
+    /// File latencies are appended to with [`MetricsSink::File`] (`file-path`).
+    pub fn file_path(mut self, path: impl Into<String>) -> Self {
+        self.file_path = Some(path.into());
+        self
+    }
+
+    /// Source element property to add as an extra label (`label-from-property`).
+    pub fn label_from_property(mut self, property: impl Into<String>) -> Self {
+        self.label_from_property = Some(property.into());
+        self
+    }
+
+    /// Elements to measure, by name, e.g. `video*,!*sink*` (`filter`).
+    pub fn filter(mut self, spec: impl Into<String>) -> Self {
+        self.filter = Some(spec.into());
+        self
+    }
+
+    /// Pads to measure, by `element.pad` name, e.g. `mux.video_*` (`pad-filter`).
+    pub fn pad_filter(mut self, spec: impl Into<String>) -> Self {
+        self.pad_filter = Some(spec.into());
+        self
+    }
+
+    /// Whether to measure every element or only end-to-end latency (`scope`).
+    pub fn scope(mut self, scope: LatencyScope) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    /// Whether to record source-to-sink latency with `scope=element` (`pipeline-latency`).
+    pub fn pipeline_latency(mut self, enabled: bool) -> Self {
+        self.pipeline_latency = Some(enabled);
+        self
+    }
+
+    /// Objects whose pushes are hooked, e.g. `GstBuffer,GstEvent` (`filters`).
+    pub fn filters(mut self, spec: impl Into<String>) -> Self {
+        self.filters = Some(spec.into());
+        self
+    }
+
+    /// The tracer parameter string these settings translate to.
+    ///
+    /// Serialized from a structure with typed fields, so strings are quoted and escaped the way
+    /// the parameters are parsed back.
+    pub fn params(&self) -> String {
+        let mut s = gst::Structure::new_empty("prom-latency");
+        if let Some(port) = self.port {
+            s.set("port", i32::from(port));
+        }
+        if let Some(retries) = self.port_retries {
+            s.set("port-retries", i32::from(retries));
+        }
+        if let Some(enabled) = self.http_server {
+            s.set("http-server", enabled);
+        }
+        if let Some(threads) = self.server_threads {
+            s.set("server-threads", threads.min(i32::MAX as usize) as i32);
+        }
+        if let Some(url) = &self.pushgateway_url {
+            s.set("pushgateway-url", url.as_str());
+        }
+        if let Some(endpoint) = &self.otlp_endpoint {
+            s.set("otlp-endpoint", endpoint.as_str());
+        }
+        if let Some(interval_ms) = self.push_interval_ms {
+            s.set("push-interval-ms", interval_ms.min(i32::MAX as u64) as i32);
+        }
+        if let Some(unit) = self.unit {
+            s.set("unit", unit.param());
+        }
+        if let Some(enabled) = self.seconds {
+            s.set("seconds", enabled);
+        }
+        if let Some(enabled) = self.state_changes {
+            s.set("state-changes", enabled);
+        }
+        if let Some(enabled) = self.cpu_time {
+            s.set("cpu-time", enabled);
+        }
+        if let Some(enabled) = self.summary {
+            s.set("summary", enabled);
+        }
+        if let Some(quantiles) = &self.summary_quantiles {
+            let quantiles = quantiles
+                .iter()
+                .map(f64::to_string)
+                .collect::<Vec<_>>()
+                .join(",");
+            s.set("summary-quantiles", quantiles);
+        }
+        if let Some(max_series) = self.max_series {
+            s.set("max-series", max_series.min(i32::MAX as usize) as i32);
+        }
+        if let Some(sink) = self.sink {
+            s.set("sink", sink.param());
+        }
+        if let Some(address) = &self.statsd_address {
+            s.set("statsd-address", address.as_str());
+        }
+        if let Some(path) = &self.file_path {
+            s.set("file-path", path.as_str());
+        }
+        if let Some(property) = &self.label_from_property {
+            s.set("label-from-property", property.as_str());
+        }
+        if let Some(spec) = &self.filter {
+            s.set("filter", spec.as_str());
+        }
+        if let Some(spec) = &self.pad_filter {
+            s.set("pad-filter", spec.as_str());
+        }
+        if let Some(scope) = self.scope {
+            s.set("scope", scope.param());
+        }
+        if let Some(enabled) = self.pipeline_latency {
+            s.set("pipeline-latency", enabled);
+        }
+        if let Some(spec) = &self.filters {
+            s.set("filters", spec.as_str());
+        }
+        // Only the fields, as they follow the tracer name in `GST_TRACERS`
+        let serialized = s.to_string();
+        serialized
+            .strip_prefix("prom-latency")
+            .unwrap_or(&serialized)
+            .trim_end_matches(';')
+            .trim_start_matches([',', ' '])
+            .to_string()
+    }
+
+    /// Creates the tracer, which registers its hooks straight away.
+    pub fn build(self) -> PromLatencyTracer {
+        glib::Object::builder::<PromLatencyTracer>()
+            .property("params", self.params())
+            .build()
+    }
+}
+
+impl PromLatencyTracer {
+    /// Starts building a tracer configured from Rust instead of `GST_TRACERS`.
+    pub fn builder() -> PromLatencyTracerBuilder {
+        PromLatencyTracerBuilder::default()
+    }
+
+    /// The settings this tracer was created with, as parsed from its tracer parameters.
+    pub fn settings(&self) -> PromLatencySettings {
+        self.imp().settings.read().unwrap().clone()
+    }
+
+    /// The port metrics are served on, once the first pipeline started the HTTP server.
+    ///
+    /// Differs from the `port` setting when it was in use and `port-retries` picked another one.
+    /// `None` before that, without a server, or when no port could be bound.
+    pub fn served_port(&self) -> Option<u16> {
+        self.imp().core.served_port()
+    }
+
+    /// Gathers the metric families recorded by this tracer.
+    ///
+    /// Lets applications merge GStreamer latencies into their own Prometheus registry or endpoint
+    /// instead of running the tracer's HTTP server. Unlike a scrape, this leaves the min/max window
+    /// open.
+    pub fn metric_families(&self) -> Vec<prometheus::proto::MetricFamily> {
+        self.imp().core.metric_families()
+    }
+}
+
 // Register the plugin with GStreamer
 pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
     gst::Tracer::register(
@@ -1,8 +1,14 @@
 use std::{
-    cell::Cell,
+    cell::RefCell,
+    collections::HashMap,
     os::raw::c_void,
-    sync::{LazyLock, OnceLock},
+    sync::{
+        atomic::{AtomicBool, AtomicU16, AtomicU64, AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, LazyLock, Mutex, OnceLock, RwLock, Weak,
+    },
     thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use glib::{
@@ -12,48 +18,879 @@ use glib::{
 };
 use gst::{ffi, prelude::*};
 use gstreamer as gst;
+use gsttracercommon::{element_filter::Filter, tracer_flags::TracedTypes};
 use prometheus::{
-    gather, register_int_counter_vec, register_int_gauge_vec, Encoder, IntCounter, IntCounterVec,
-    IntGauge, IntGaugeVec, TextEncoder,
+    core::Collector,
+    proto::{Metric, MetricFamily, MetricType},
+    register_counter_vec_with_registry, register_histogram_vec_with_registry,
+    register_int_counter_vec_with_registry, register_int_gauge_vec_with_registry,
+    register_int_gauge_with_registry, Counter, CounterVec, Encoder, Gauge, HistogramVec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Registry, TextEncoder,
 };
 use tiny_http::{Header, Response, Server};
 
-// Define Prometheus metrics, all in nanoseconds
-static LATENCY_LAST: LazyLock<IntGaugeVec> = LazyLock::new(|| {
-    register_int_gauge_vec!(
+use crate::{
+    file::FileSink,
+    otlp::OtlpExporter,
+    promlatency::{PromLatencySettings, PromLatencyTracer},
+    statsd::{MetricsSink, StatsdSink},
+    summary::{LatencySummary, SummarySeries},
+};
+
+/// Labels shared by all per pad pair metrics.
+const PAD_PAIR_LABELS: &[&str] = &[
+    "element",
+    "element_factory",
+    "src_pad",
+    "sink_pad",
+    "path",
+    "media_type",
+];
+/// Index of the `media_type` label, which may only be known once the first buffer flows.
+const MEDIA_TYPE_LABEL_IDX: usize = 5;
+/// `element` label of the series pad pairs record into once `max-series` is exceeded.
+const OVERFLOW_ELEMENT: &str = "__overflow__";
+/// Families reporting a time in the latency unit, and their names once rescaled to seconds.
+const SECONDS_FAMILIES: &[(&str, &str)] = &[
+    (
+        "gst_element_render_latency_last_gauge",
+        "gst_element_render_latency_last_seconds",
+    ),
+    (
         "gst_element_latency_last_gauge",
-        "Last latency in nanoseconds per element",
-        &["element", "src_pad", "sink_pad", "path"]
-    )
-    .unwrap()
-});
-static LATENCY_SUM: LazyLock<IntCounterVec> = LazyLock::new(|| {
-    register_int_counter_vec!(
+        "gst_element_latency_last_seconds",
+    ),
+    (
+        "gst_element_latency_min_gauge",
+        "gst_element_latency_min_seconds",
+    ),
+    (
+        "gst_element_latency_max_gauge",
+        "gst_element_latency_max_seconds",
+    ),
+    (
         "gst_element_latency_sum_count",
-        "Sum of latencies in nanoseconds per element",
-        &["element", "src_pad", "sink_pad", "path"]
-    )
-    .unwrap()
-});
-static LATENCY_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
-    register_int_counter_vec!(
-        "gst_element_latency_count_count",
-        "Count of latency measurements per element",
-        &["element", "src_pad", "sink_pad", "path"]
-    )
-    .unwrap()
-});
+        "gst_element_latency_seconds_total",
+    ),
+    (
+        "gst_element_interbuffer_interval_last_gauge",
+        "gst_element_interbuffer_interval_last_seconds",
+    ),
+    (
+        "gst_element_interbuffer_interval_min_gauge",
+        "gst_element_interbuffer_interval_min_seconds",
+    ),
+    (
+        "gst_element_interbuffer_interval_max_gauge",
+        "gst_element_interbuffer_interval_max_seconds",
+    ),
+    (
+        "gst_pipeline_latency_last_gauge",
+        "gst_pipeline_latency_last_seconds",
+    ),
+    (
+        "gst_pipeline_latency_sum_count",
+        "gst_pipeline_latency_seconds_total",
+    ),
+    ("gst_element_latency_summary", "gst_element_latency_seconds"),
+    ("gst_queue_level_time", "gst_queue_level_seconds"),
+];
+/// Property whose presence marks an element, e.g. `queue` or `queue2`, whose level is reported.
+const QUEUE_LEVEL_PROPERTY: &str = "current-level-buffers";
+/// Quantiles returned by the `request-percentiles` signal.
+const PERCENTILES: [f64; 3] = [0.5, 0.95, 0.99];
+/// Labels of the state change duration histogram.
+const STATE_CHANGE_LABELS: &[&str] = &["element", "transition"];
+
+/// Content type of the Prometheus text exposition format.
+const PROMETHEUS_TEXT_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+/// Content type of the OpenMetrics text exposition format.
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Unit latencies are reported in. Latency is always measured in nanoseconds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LatencyUnit {
+    #[default]
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+}
+
+impl LatencyUnit {
+    /// Parses the `unit` tracer parameter: `ns`, `us` or `ms`.
+    pub fn from_param(unit: &str) -> Option<Self> {
+        match unit {
+            "ns" => Some(Self::Nanoseconds),
+            "us" => Some(Self::Microseconds),
+            "ms" => Some(Self::Milliseconds),
+            _ => None,
+        }
+    }
+
+    /// The `unit` tracer parameter value for this unit.
+    pub fn param(self) -> &'static str {
+        match self {
+            Self::Nanoseconds => "ns",
+            Self::Microseconds => "us",
+            Self::Milliseconds => "ms",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Nanoseconds => "nanoseconds",
+            Self::Microseconds => "microseconds",
+            Self::Milliseconds => "milliseconds",
+        }
+    }
+
+    /// How many of this unit make up a second.
+    fn per_second(self) -> f64 {
+        match self {
+            Self::Nanoseconds => 1e9,
+            Self::Microseconds => 1e6,
+            Self::Milliseconds => 1e3,
+        }
+    }
+
+    /// Converts a nanosecond latency into this unit, rounding to the nearest value.
+    pub(crate) fn convert(self, ns: u64) -> u64 {
+        let divisor = match self {
+            Self::Nanoseconds => return ns,
+            Self::Microseconds => 1_000,
+            Self::Milliseconds => 1_000_000,
+        };
+        ns.saturating_add(divisor / 2) / divisor
+    }
+}
+
+/// Which latencies a tracer measures.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LatencyScope {
+    /// The latency of every element, along with the end-to-end latency into sinks.
+    #[default]
+    Element,
+    /// Only the end-to-end latency from sources into sinks.
+    Pipeline,
+}
+
+impl LatencyScope {
+    /// Parses the `scope` tracer parameter: `element` or `pipeline`.
+    pub fn from_param(scope: &str) -> Option<Self> {
+        match scope {
+            "element" => Some(Self::Element),
+            "pipeline" => Some(Self::Pipeline),
+            _ => None,
+        }
+    }
+
+    /// The `scope` tracer parameter value for this scope.
+    pub fn param(self) -> &'static str {
+        match self {
+            Self::Element => "element",
+            Self::Pipeline => "pipeline",
+        }
+    }
+}
+
+/// Metric vecs of a single tracer, registered against its own registry rather than the
+/// process-wide default one.
+pub(crate) struct Metrics {
+    registry: Registry,
+    unit: LatencyUnit,
+    /// Element property whose value is added as an extra pad pair label, if any.
+    label_property: Option<String>,
+    /// `PAD_PAIR_LABELS`, followed by the label of `label_property`.
+    pad_pair_labels: Vec<String>,
+    /// Pad pairs sharing each label set, whose series are removed once the last one unlinks.
+    series_refs: Mutex<HashMap<Vec<String>, usize>>,
+    /// Distinct label sets allowed before new pad pairs record into the overflow series, 0 for
+    /// no limit.
+    max_series: AtomicUsize,
+    /// Set once `max_series` was first exceeded, so the warning is only logged once.
+    overflowed: AtomicBool,
+    /// With `seconds=true`, times are reported as seconds in `SECONDS_FAMILIES` when scraped.
+    seconds: AtomicBool,
+    /// Bumped on every HTTP scrape or reset, starting a new min/max window for every pad pair.
+    min_max_window: AtomicU64,
+    latency_last: IntGaugeVec,
+    latency_min: IntGaugeVec,
+    latency_max: IntGaugeVec,
+    latency_sum: IntCounterVec,
+    latency_count: IntCounterVec,
+    buffers_in: IntCounterVec,
+    buffers_out: IntCounterVec,
+    qos_dropped: IntCounterVec,
+    bytes_total: IntCounterVec,
+    buffer_discont: IntCounterVec,
+    buffer_gap: IntCounterVec,
+    buffer_corrupted: IntCounterVec,
+    interval_last: IntGaugeVec,
+    interval_min: IntGaugeVec,
+    interval_max: IntGaugeVec,
+    /// Only populated when the tracer is created with `cpu-time=true`.
+    cpu_seconds: CounterVec,
+    /// Only populated for pad pairs into sink elements, which have no src pad to push on.
+    render_latency_last: IntGaugeVec,
+    /// Only populated for pad pairs into sink elements.
+    pipeline_latency_last: IntGaugeVec,
+    pipeline_latency_sum: IntCounterVec,
+    pipeline_latency_count: IntCounterVec,
+    /// Only created with `summary=true`.
+    latency_summary: Option<LatencySummary>,
+    /// Only populated when the tracer is created with `state-changes=true`.
+    state_change_duration: HistogramVec,
+    /// Constant 1, labelled with the version of the plugin.
+    build_info: IntGaugeVec,
+    /// Pad pairs currently holding a `PadCacheData`, which only grows if unlinks are missed.
+    active_pad_pairs: IntGauge,
+    /// Queues whose fill level is read into the queue level gauges on every scrape.
+    queues: Mutex<Vec<glib::WeakRef<gst::Element>>>,
+    queue_level_buffers: IntGaugeVec,
+    queue_level_bytes: IntGaugeVec,
+    queue_level_time: IntGaugeVec,
+}
+
+impl Metrics {
+    fn new(
+        unit: LatencyUnit,
+        label_property: Option<&str>,
+        summary_quantiles: Option<Vec<f64>>,
+    ) -> Self {
+        let registry = Registry::new();
+        let pad_pair_labels = PAD_PAIR_LABELS
+            .iter()
+            .map(|label| label.to_string())
+            .chain(label_property.map(Self::property_label_name))
+            .collect::<Vec<_>>();
+        let label_names = pad_pair_labels
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let latency_summary = summary_quantiles.map(|quantiles| {
+            let summary = LatencySummary::new(
+                "gst_element_latency_summary",
+                format!(
+                    "Latency quantiles in {} per element over its last latencies",
+                    unit.name()
+                ),
+                &label_names,
+                quantiles,
+            )
+            .unwrap();
+            registry.register(Box::new(summary.clone())).unwrap();
+            summary
+        });
+        let metrics = Self {
+            latency_last: register_int_gauge_vec_with_registry!(
+                "gst_element_latency_last_gauge",
+                format!("Last latency in {} per element", unit.name()),
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            latency_min: register_int_gauge_vec_with_registry!(
+                "gst_element_latency_min_gauge",
+                format!(
+                    "Minimum latency in {} per element since the last scrape",
+                    unit.name()
+                ),
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            latency_max: register_int_gauge_vec_with_registry!(
+                "gst_element_latency_max_gauge",
+                format!(
+                    "Maximum latency in {} per element since the last scrape",
+                    unit.name()
+                ),
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            latency_sum: register_int_counter_vec_with_registry!(
+                "gst_element_latency_sum_count",
+                format!("Sum of latencies in {} per element", unit.name()),
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            latency_count: register_int_counter_vec_with_registry!(
+                "gst_element_latency_count_count",
+                "Count of latency measurements per element",
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            buffers_in: register_int_counter_vec_with_registry!(
+                "gst_element_buffers_in_total",
+                "Buffers pushed into each element",
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            buffers_out: register_int_counter_vec_with_registry!(
+                "gst_element_buffers_out_total",
+                "Buffers each element finished processing",
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            qos_dropped: register_int_counter_vec_with_registry!(
+                "gst_element_qos_dropped_total",
+                "QoS events reporting a late, and likely dropped, buffer per element",
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            bytes_total: register_int_counter_vec_with_registry!(
+                "gst_element_bytes_total",
+                "Total bytes pushed into each element",
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            buffer_discont: register_int_counter_vec_with_registry!(
+                "gst_element_buffer_discont_total",
+                "Buffers flagged as a discontinuity pushed into each element",
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            buffer_gap: register_int_counter_vec_with_registry!(
+                "gst_element_buffer_gap_total",
+                "Buffers flagged as a gap in the stream pushed into each element",
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            buffer_corrupted: register_int_counter_vec_with_registry!(
+                "gst_element_buffer_corrupted_total",
+                "Buffers flagged as corrupted pushed into each element",
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            interval_last: register_int_gauge_vec_with_registry!(
+                "gst_element_interbuffer_interval_last_gauge",
+                format!(
+                    "Last interval in {} between consecutive pushes into each element",
+                    unit.name()
+                ),
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            interval_min: register_int_gauge_vec_with_registry!(
+                "gst_element_interbuffer_interval_min_gauge",
+                format!(
+                    "Minimum interval in {} between consecutive pushes into each element since the last scrape",
+                    unit.name()
+                ),
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            interval_max: register_int_gauge_vec_with_registry!(
+                "gst_element_interbuffer_interval_max_gauge",
+                format!(
+                    "Maximum interval in {} between consecutive pushes into each element since the last scrape",
+                    unit.name()
+                ),
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            cpu_seconds: register_counter_vec_with_registry!(
+                "gst_element_cpu_seconds_total",
+                "Thread CPU time each element spent processing pushed buffers, excluding downstream elements on the same thread",
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            render_latency_last: register_int_gauge_vec_with_registry!(
+                "gst_element_render_latency_last_gauge",
+                format!(
+                    "Last time in {} each sink element spent consuming a buffer",
+                    unit.name()
+                ),
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            pipeline_latency_last: register_int_gauge_vec_with_registry!(
+                "gst_pipeline_latency_last_gauge",
+                format!(
+                    "Last time in {} a buffer took from its source to each sink",
+                    unit.name()
+                ),
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            pipeline_latency_sum: register_int_counter_vec_with_registry!(
+                "gst_pipeline_latency_sum_count",
+                format!(
+                    "Sum of times in {} buffers took from their source to each sink",
+                    unit.name()
+                ),
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            pipeline_latency_count: register_int_counter_vec_with_registry!(
+                "gst_pipeline_latency_count_count",
+                "Count of buffers reaching each sink with the timestamp of their source",
+                &label_names,
+                registry
+            )
+            .unwrap(),
+            state_change_duration: register_histogram_vec_with_registry!(
+                "gst_element_state_change_duration_seconds",
+                "Time each element spent in its change_state function per transition",
+                STATE_CHANGE_LABELS,
+                registry
+            )
+            .unwrap(),
+            build_info: register_int_gauge_vec_with_registry!(
+                "gst_tracer_build_info",
+                "Constant 1, labelled with the name and version of the tracer plugin",
+                &["name", "version"],
+                registry
+            )
+            .unwrap(),
+            active_pad_pairs: register_int_gauge_with_registry!(
+                "gst_tracer_active_pad_pairs",
+                "Linked pad pairs the tracer currently measures",
+                registry
+            )
+            .unwrap(),
+            queues: Mutex::default(),
+            queue_level_buffers: register_int_gauge_vec_with_registry!(
+                "gst_queue_level_buffers",
+                "Buffers currently held by each queue",
+                &["element"],
+                registry
+            )
+            .unwrap(),
+            queue_level_bytes: register_int_gauge_vec_with_registry!(
+                "gst_queue_level_bytes",
+                "Bytes currently held by each queue",
+                &["element"],
+                registry
+            )
+            .unwrap(),
+            queue_level_time: register_int_gauge_vec_with_registry!(
+                "gst_queue_level_time",
+                format!("Data currently held by each queue in {}", unit.name()),
+                &["element"],
+                registry
+            )
+            .unwrap(),
+            latency_summary,
+            registry,
+            unit,
+            label_property: label_property.map(str::to_string),
+            pad_pair_labels,
+            series_refs: Mutex::new(HashMap::new()),
+            max_series: AtomicUsize::new(PromLatencyTracerImp::DEFAULT_MAX_SERIES),
+            overflowed: AtomicBool::new(false),
+            seconds: AtomicBool::new(false),
+            min_max_window: AtomicU64::new(0),
+        };
+        metrics
+            .build_info
+            .with_label_values(&[env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")])
+            .set(1);
+        metrics
+    }
+
+    /// Label names may not contain `-`, so `stream-id` becomes `stream_id`.
+    fn property_label_name(property: &str) -> String {
+        property
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+
+    /// Gathers all metrics, leaving the min/max window open.
+    fn gather(&self) -> Vec<MetricFamily> {
+        self.update_queue_levels();
+        let mut metric_families = self.registry.gather();
+        if self.seconds.load(Ordering::Relaxed) {
+            metric_families
+                .iter_mut()
+                .for_each(|family| self.rescale_to_seconds(family));
+        }
+        metric_families
+    }
+
+    /// Reads the current level of every queue still alive. Series are recreated on every scrape,
+    /// so renamed and freed queues leave none behind.
+    fn update_queue_levels(&self) {
+        let mut queues = self.queues.lock().unwrap();
+        queues.retain(|queue| queue.upgrade().is_some());
+        self.queue_level_buffers.reset();
+        self.queue_level_bytes.reset();
+        self.queue_level_time.reset();
+        for queue in queues.iter().filter_map(glib::WeakRef::upgrade) {
+            let name = queue.name();
+            let labels = [name.as_str()];
+            self.queue_level_buffers
+                .with_label_values(&labels)
+                .set(Self::queue_level(&queue, "current-level-buffers"));
+            self.queue_level_bytes
+                .with_label_values(&labels)
+                .set(Self::queue_level(&queue, "current-level-bytes"));
+            let time_ns = Self::queue_level(&queue, "current-level-time") as u64;
+            self.queue_level_time
+                .with_label_values(&labels)
+                .set(self.unit.convert(time_ns).try_into().unwrap_or(i64::MAX));
+        }
+    }
+
+    /// A `current-level-*` property of `queue`, whose integer type differs between queues.
+    fn queue_level(queue: &gst::Element, property: &str) -> i64 {
+        if queue.find_property(property).is_none() {
+            return 0;
+        }
+        queue
+            .property_value(property)
+            .transform::<u64>()
+            .ok()
+            .and_then(|value| value.get::<u64>().ok())
+            .map_or(0, |level| level.try_into().unwrap_or(i64::MAX))
+    }
+
+    /// Reports a family of `SECONDS_FAMILIES` in seconds under its `_seconds` name, following
+    /// the Prometheus convention of base units. Other families are left as they are.
+    fn rescale_to_seconds(&self, family: &mut MetricFamily) {
+        let Some((_, seconds_name)) = SECONDS_FAMILIES
+            .iter()
+            .find(|(name, _)| *name == family.name())
+        else {
+            return;
+        };
+        let per_second = self.unit.per_second();
+        family.set_name(seconds_name.to_string());
+        family.set_help(family.help().replace(self.unit.name(), "seconds"));
+        let field_type = family.get_field_type();
+        for metric in family.mut_metric() {
+            match field_type {
+                MetricType::GAUGE => {
+                    let gauge = metric.gauge.mut_or_insert_default();
+                    gauge.set_value(gauge.value() / per_second);
+                }
+                MetricType::COUNTER => {
+                    let counter = metric.counter.mut_or_insert_default();
+                    counter.set_value(counter.value() / per_second);
+                }
+                MetricType::SUMMARY => {
+                    let summary = metric.summary.mut_or_insert_default();
+                    summary.set_sample_sum(summary.sample_sum() / per_second);
+                    for quantile in &mut summary.quantile {
+                        quantile.set_value(quantile.value() / per_second);
+                    }
+                }
+                MetricType::HISTOGRAM | MetricType::UNTYPED => {}
+            }
+        }
+    }
+
+    /// p50, p95 and p99 latencies of each pad pair from the `summary=true` windows, one
+    /// `element.src_pad p50=… p95=… p99=…` line each. Empty without `summary=true`.
+    fn percentiles(&self) -> String {
+        let Some(summary) = &self.latency_summary else {
+            return String::new();
+        };
+        summary
+            .quantiles_by_series(&PERCENTILES)
+            .into_iter()
+            // Pad pairs without a latency yet have nothing to report
+            .filter(|(_, values)| values.iter().all(|v| !v.is_nan()))
+            .map(|(labels, values)| {
+                format!(
+                    "{}.{} p50={} p95={} p99={}",
+                    labels[0], labels[2], values[0], values[1], values[2]
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Min/max gauges are reset to the next latency recorded for each pad pair.
+    pub(crate) fn reset_min_max(&self) {
+        self.min_max_window.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the metric handles of a single pad pair.
+    fn with_label_values(&self, labels: &[String]) -> PadMetrics {
+        PadMetrics {
+            last_gauge: self.latency_last.with_label_values(labels),
+            min_gauge: self.latency_min.with_label_values(labels),
+            max_gauge: self.latency_max.with_label_values(labels),
+            sum_counter: self.latency_sum.with_label_values(labels),
+            count_counter: self.latency_count.with_label_values(labels),
+            buffers_in_counter: self.buffers_in.with_label_values(labels),
+            buffers_out_counter: self.buffers_out.with_label_values(labels),
+            bytes_counter: self.bytes_total.with_label_values(labels),
+            qos_dropped_counter: self.qos_dropped.with_label_values(labels),
+            discont_counter: self.buffer_discont.with_label_values(labels),
+            gap_counter: self.buffer_gap.with_label_values(labels),
+            corrupted_counter: self.buffer_corrupted.with_label_values(labels),
+            interval_last_gauge: self.interval_last.with_label_values(labels),
+            interval_min_gauge: self.interval_min.with_label_values(labels),
+            interval_max_gauge: self.interval_max.with_label_values(labels),
+            cpu_seconds_counter: self.cpu_seconds.with_label_values(labels),
+            summary: self
+                .latency_summary
+                .as_ref()
+                .map(|summary| summary.with_label_values(labels)),
+        }
+    }
+
+    /// Returns the end-to-end latency handles of a pad pair into a sink element.
+    fn pipeline_latency_with_label_values(&self, labels: &[String]) -> PipelineLatencyMetrics {
+        PipelineLatencyMetrics {
+            last_gauge: self.pipeline_latency_last.with_label_values(labels),
+            sum_counter: self.pipeline_latency_sum.with_label_values(labels),
+            count_counter: self.pipeline_latency_count.with_label_values(labels),
+        }
+    }
+
+    /// Returns the metric handles of a newly linked pad pair, keeping its series alive until
+    /// every pad pair with the same labels released it.
+    ///
+    /// Once `max_series` label sets exist, the labels of a pad pair with new labels are replaced
+    /// by those of the overflow series, which it then records into and later releases.
+    fn acquire_series(&self, labels: &mut [String]) -> PadMetrics {
+        self.acquire_labels(labels);
+        self.with_label_values(labels)
+    }
+
+    /// Like `acquire_series`, without creating the per element series, for pad pairs that only
+    /// record end-to-end latency.
+    fn acquire_labels(&self, labels: &mut [String]) {
+        let mut series_refs = self.series_refs.lock().unwrap();
+        let max_series = self.max_series.load(Ordering::Relaxed);
+        if max_series > 0 && series_refs.len() >= max_series && !series_refs.contains_key(labels) {
+            if !self.overflowed.swap(true, Ordering::Relaxed) {
+                gst::warning!(
+                    CAT,
+                    "More than {} series, recording new pad pairs into element=\"{}\"",
+                    max_series,
+                    OVERFLOW_ELEMENT
+                );
+            }
+            for (i, label) in labels.iter_mut().enumerate() {
+                *label = if i == 0 { OVERFLOW_ELEMENT } else { "" }.to_string();
+            }
+        }
+        *series_refs.entry(labels.to_vec()).or_default() += 1;
+    }
+
+    fn is_overflow(labels: &[String]) -> bool {
+        labels[0] == OVERFLOW_ELEMENT
+    }
+
+    /// Releases the series of a pad pair, removing them once no other pad pair uses them.
+    ///
+    /// Releasing labels that were never acquired, or already released, does nothing.
+    fn release_series(&self, labels: &[String]) {
+        let mut series_refs = self.series_refs.lock().unwrap();
+        let Some(refs) = series_refs.get_mut(labels) else {
+            return;
+        };
+        *refs -= 1;
+        if *refs == 0 {
+            series_refs.remove(labels);
+            self.remove_label_values(labels);
+        }
+    }
+
+    /// Removes the series of a pad pair from every metric.
+    fn remove_label_values(&self, labels: &[String]) {
+        let _ = self.latency_last.remove_label_values(labels);
+        let _ = self.latency_min.remove_label_values(labels);
+        let _ = self.latency_max.remove_label_values(labels);
+        let _ = self.latency_sum.remove_label_values(labels);
+        let _ = self.latency_count.remove_label_values(labels);
+        let _ = self.buffers_in.remove_label_values(labels);
+        let _ = self.buffers_out.remove_label_values(labels);
+        let _ = self.bytes_total.remove_label_values(labels);
+        let _ = self.qos_dropped.remove_label_values(labels);
+        let _ = self.buffer_discont.remove_label_values(labels);
+        let _ = self.buffer_gap.remove_label_values(labels);
+        let _ = self.buffer_corrupted.remove_label_values(labels);
+        let _ = self.interval_last.remove_label_values(labels);
+        let _ = self.interval_min.remove_label_values(labels);
+        let _ = self.interval_max.remove_label_values(labels);
+        let _ = self.cpu_seconds.remove_label_values(labels);
+        let _ = self.render_latency_last.remove_label_values(labels);
+        let _ = self.pipeline_latency_last.remove_label_values(labels);
+        let _ = self.pipeline_latency_sum.remove_label_values(labels);
+        let _ = self.pipeline_latency_count.remove_label_values(labels);
+        if let Some(summary) = &self.latency_summary {
+            summary.remove_label_values(labels);
+        }
+    }
+}
+
+/// A push in progress on the current thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PushFrame {
+    /// The tracer core that entered the push, as each tracer attributes latency on its own.
+    owner: usize,
+    start: u64,
+    /// Time spent in pushes nested inside this one, i.e. downstream of the receiving element.
+    downstream: u64,
+}
+
+/// Duration of a completed push, and how much of it was spent further downstream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PushSpan {
+    total: u64,
+    downstream: u64,
+}
+
+/// Pushes in progress on a thread, innermost last.
+///
+/// Downstream elements on the same thread run nested inside the push into an element, so an
+/// element's own latency is the push duration minus the nested pushes it made. Every nested push
+/// is added up, however many buffers the element pushes per input. Pushes crossing a thread
+/// boundary, e.g. out of a `queue`, start a new stack on the streaming thread, so nothing is
+/// subtracted twice or carried over between unrelated pushes.
+#[derive(Debug, Default)]
+struct PushFrames(Vec<PushFrame>);
+
+impl PushFrames {
+    fn enter(&mut self, owner: usize, ts: u64) {
+        self.0.push(PushFrame {
+            owner,
+            start: ts,
+            downstream: 0,
+        });
+    }
+
+    /// Ends the innermost push of `owner`, crediting its duration to the push enclosing it.
+    fn exit(&mut self, owner: usize, ts: u64) -> Option<PushSpan> {
+        let idx = self.0.iter().rposition(|f| f.owner == owner)?;
+        let frame = self.0.remove(idx);
+        let total = ts.saturating_sub(frame.start);
+        if let Some(parent) = self.0[..idx].iter_mut().rev().find(|f| f.owner == owner) {
+            parent.downstream += total;
+        }
+        Some(PushSpan {
+            total,
+            downstream: frame.downstream,
+        })
+    }
+}
 
 thread_local! {
-    /// Experimental approach to seeing if we set the span latency if
-    /// we can use it to measure cross element latency.
-    pub static SPAN_LATENCY: Cell<u64> = const { Cell::new(0) };
+    static PUSH_FRAMES: RefCell<PushFrames> = const { RefCell::new(PushFrames(Vec::new())) };
+    /// The same pushes timed by thread CPU time, only entered with `cpu-time=true`.
+    static CPU_FRAMES: RefCell<PushFrames> = const { RefCell::new(PushFrames(Vec::new())) };
 }
 
-static PAD_CACHE_QUARK: LazyLock<glib::ffi::GQuark> =
-    LazyLock::new(|| Quark::from_str("promlatency.pad_cache").into_glib());
+/// CPU time consumed by the calling thread so far, in nanoseconds.
+fn thread_cpu_time_ns() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid out pointer and the clock is supported on every target we build for.
+    unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+/// Numbers every tracer's pad cache quark, so tracers never find each other's caches.
+static NEXT_PAD_CACHE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Metrics servers by configured port, shared by every tracer configured with the same port.
+static METRICS_SERVERS: LazyLock<Mutex<HashMap<u16, Arc<MetricsServer>>>> =
+    LazyLock::new(Mutex::default);
+
+/// Serves the metrics of every tracer sharing a port.
+#[derive(Default)]
+struct MetricsServer {
+    sources: Mutex<Vec<Weak<Metrics>>>,
+    /// Port the server listens on, past the configured one if that was taken.
+    port: u16,
+    /// Unix time in seconds of the previous scrape, as `f64` bits, or `0` before the first one.
+    last_scrape: AtomicU64,
+}
+
+impl MetricsServer {
+    /// Scrapes every tracer still alive, merging families of the same name, then starts a new
+    /// min/max window on each.
+    ///
+    /// Tracers sharing a port usually trace the same pipelines, so a series another tracer
+    /// already reported (e.g. `gst_tracer_build_info`) is only reported once.
+    fn scrape(&self) -> Vec<MetricFamily> {
+        let mut sources = self.sources.lock().unwrap();
+        sources.retain(|metrics| metrics.strong_count() > 0);
+        let mut families: Vec<MetricFamily> = Vec::new();
+        for metrics in sources.iter().filter_map(Weak::upgrade) {
+            let gathered = metrics.gather();
+            metrics.reset_min_max();
+            for mut family in gathered {
+                let Some(existing) = families.iter_mut().find(|f| f.name() == family.name()) else {
+                    families.push(family);
+                    continue;
+                };
+                for metric in family.take_metric() {
+                    if !existing
+                        .get_metric()
+                        .iter()
+                        .any(|m| m.get_label() == metric.get_label())
+                    {
+                        existing.mut_metric().push(metric);
+                    }
+                }
+            }
+        }
+        families.extend(self.meta_families(&families));
+        families
+    }
+
+    /// Families describing the server itself: the number of series in `families`, to spot
+    /// cardinality growth, and the time of the previous scrape, to confirm scrapes land.
+    fn meta_families(&self, families: &[MetricFamily]) -> Vec<MetricFamily> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+        let last_scrape = f64::from_bits(self.last_scrape.swap(now.to_bits(), Ordering::Relaxed));
+
+        let series = IntGauge::new(
+            "gst_tracer_series_total",
+            "Number of metric series served by the tracer",
+        )
+        .unwrap();
+        series.set(families.iter().map(|f| f.get_metric().len() as i64).sum());
+        let mut meta = series.collect();
+        if last_scrape > 0.0 {
+            let gauge = Gauge::new(
+                "gst_tracer_last_scrape_timestamp_seconds",
+                "Unix time of the previous scrape of the metrics server",
+            )
+            .unwrap();
+            gauge.set(last_scrape);
+            meta.extend(gauge.collect());
+        }
+        meta
+    }
+}
+
+/// Reference of the timestamp meta carrying the time a buffer left its source element.
+static SOURCE_TS_CAPS: LazyLock<gst::Caps> =
+    LazyLock::new(|| gst::Caps::new_empty_simple("timestamp/x-gst-prom-latency-source"));
+
+/// Holds the timestamp an element's current state change started at.
+static STATE_CHANGE_START_QUARK: LazyLock<Quark> =
+    LazyLock::new(|| Quark::from_str("promlatency.state_change_start"));
 
-static METRICS_SERVER_ONCE: OnceLock<()> = OnceLock::new();
 pub(crate) static CAT: LazyLock<gst::DebugCategory> = LazyLock::new(|| {
     gst::DebugCategory::new(
         "prom-latency",
@@ -73,59 +910,385 @@ struct PadCacheData {
     /// The verdict tag indicating whether to skip or measure latency.
     ts: u64, // timestamp of the last push/pull
 
+    /// Timestamp of the previous push, kept across records to measure inter-buffer intervals.
+    /// Starts at zero for every new link, so intervals never span an unlink.
+    last_push_ts: u64,
+
     /// Pointer to the peer pad, used during unlink to verify the pad pair.
     peer: *mut c_void,
 
+    /// Label values of this pad pair, in `Metrics::pad_pair_labels` order.
+    labels: Vec<String>,
+
+    /// Caps are often not negotiated at link time, in which case the media type is resolved
+    /// again on every push until the src pad has caps.
+    media_type_pending: bool,
+
+    /// Lowest and highest latency recorded in the `min_max_window` window.
+    min: u64,
+    max: u64,
+    /// Lowest and highest inter-buffer interval recorded in the `min_max_window` window.
+    interval_min: u64,
+    interval_max: u64,
+    min_max_window: u64,
+
+    /// `None` with `scope=pipeline`, where only end-to-end latency is recorded.
+    metrics: Option<PadMetrics>,
+    /// DogStatsD tags of this pad pair, empty unless latencies are sent to StatsD.
+    statsd_tags: String,
+    /// JSON members of this pad pair, empty unless latencies are written to a file.
+    file_fields: String,
+
+    /// Whether buffers pushed on this pad leave a source element and, while a sink of
+    /// `pipeline` records pipeline latency, get stamped with the time.
+    stamps_source_ts: bool,
+    /// Name of the top-level pipeline, only set when pipeline latency is recorded.
+    pipeline: String,
+    /// Only set for pad pairs into sink elements, which record the time since the source stamp.
+    pipeline_latency: Option<PipelineLatencyMetrics>,
+    /// Only set for pad pairs into sink elements without src pads, which render the buffers.
+    render_latency: Option<RenderLatency>,
+}
+
+/// Render latency of a pad pair into a sink element, timed from a probe on its sink pad.
+struct RenderLatency {
+    last_gauge: IntGauge,
+    /// When the last buffer reached the sink pad, 0 once recorded.
+    arrived_ts: Arc<AtomicU64>,
+    sink_pad: glib::WeakRef<gst::Pad>,
+    probe: Option<gst::PadProbeId>,
+}
+
+impl RenderLatency {
+    fn new(last_gauge: IntGauge, sink_pad: &gst::Pad) -> Self {
+        let arrived_ts = Arc::new(AtomicU64::new(0));
+        let arrived = arrived_ts.clone();
+        let probe = sink_pad.add_probe(
+            gst::PadProbeType::BUFFER | gst::PadProbeType::BUFFER_LIST,
+            move |_, _| {
+                arrived.store(gst::util_get_timestamp().nseconds(), Ordering::Relaxed);
+                gst::PadProbeReturn::Ok
+            },
+        );
+        Self {
+            last_gauge,
+            arrived_ts,
+            sink_pad: sink_pad.downgrade(),
+            probe,
+        }
+    }
+
+    /// Sets the time from the buffer reaching the sink pad until the push into it returned at
+    /// `ts`.
+    fn record(&self, ts: u64, unit: LatencyUnit) {
+        let arrived = self.arrived_ts.swap(0, Ordering::Relaxed);
+        if arrived != 0 && arrived <= ts {
+            self.last_gauge
+                .set(unit.convert(ts - arrived).try_into().unwrap_or(i64::MAX));
+        }
+    }
+}
+
+impl Drop for RenderLatency {
+    fn drop(&mut self) {
+        if let (Some(sink_pad), Some(probe)) = (self.sink_pad.upgrade(), self.probe.take()) {
+            sink_pad.remove_probe(probe);
+        }
+    }
+}
+
+/// End-to-end latency handles of a pad pair into a sink element.
+struct PipelineLatencyMetrics {
+    last_gauge: IntGauge,
+    sum_counter: IntCounter,
+    count_counter: IntCounter,
+}
+
+/// Metric handles for a single pad pair.
+struct PadMetrics {
     last_gauge: IntGauge,
+    min_gauge: IntGauge,
+    max_gauge: IntGauge,
     sum_counter: IntCounter,
-    // TODO - at the moment we don't differentiate between buffers into the element vs buffers out, will require
-    //          a change to what we are doing here to make that work.
+    /// Only counts buffers for which a latency could be measured.
     count_counter: IntCounter,
+    /// Incremented on every push into the element, regardless of whether latency is measured.
+    buffers_in_counter: IntCounter,
+    /// Incremented on every completed push, comparing against `buffers_in_counter` shows drops.
+    buffers_out_counter: IntCounter,
+    bytes_counter: IntCounter,
+    qos_dropped_counter: IntCounter,
+    discont_counter: IntCounter,
+    gap_counter: IntCounter,
+    corrupted_counter: IntCounter,
+    interval_last_gauge: IntGauge,
+    interval_min_gauge: IntGauge,
+    interval_max_gauge: IntGauge,
+    cpu_seconds_counter: Counter,
+    summary: Option<Arc<Mutex<SummarySeries>>>,
+}
+
+impl PadCacheData {
+    /// Starts a new min/max window if metrics were scraped or reset since the last record.
+    fn sync_min_max_window(&mut self, metrics: &Metrics) {
+        let window = metrics.min_max_window.load(Ordering::Relaxed);
+        if self.min_max_window != window {
+            self.min_max_window = window;
+            self.min = u64::MAX;
+            self.max = 0;
+            self.interval_min = u64::MAX;
+            self.interval_max = 0;
+        }
+    }
+
+    /// Re-resolves the media type from the src pad's caps; if it is now known, the metric
+    /// handles are moved over to the series carrying it and it is no longer pending.
+    fn resolve_pending_media_type(&mut self, metrics: &Metrics, src_pad: *mut gst::ffi::GstPad) {
+        // The overflow series has no media type to resolve.
+        if Metrics::is_overflow(&self.labels) {
+            self.media_type_pending = false;
+            return;
+        }
+        let Some(media_type) = PromLatencyTracerImp::pad_media_type(src_pad) else {
+            return;
+        };
+        self.media_type_pending = false;
+
+        // The `unknown` series only holds what was pushed before caps, so drop it.
+        metrics.release_series(&self.labels);
+        self.labels[MEDIA_TYPE_LABEL_IDX] = media_type;
+        if self.metrics.is_some() {
+            self.metrics = Some(metrics.acquire_series(&mut self.labels));
+        } else {
+            metrics.acquire_labels(&mut self.labels);
+        }
+        if self.pipeline_latency.is_some() {
+            self.pipeline_latency = Some(metrics.pipeline_latency_with_label_values(&self.labels));
+        }
+        if let Some(render_latency) = &mut self.render_latency {
+            render_latency.last_gauge = metrics.render_latency_last.with_label_values(&self.labels);
+        }
+        if !self.statsd_tags.is_empty() {
+            self.statsd_tags = StatsdSink::tags(&metrics.pad_pair_labels, &self.labels);
+        }
+        if !self.file_fields.is_empty() {
+            self.file_fields = FileSink::fields(&metrics.pad_pair_labels, &self.labels);
+        }
+    }
 }
 
 #[derive(Default)]
-pub struct PromLatencyTracerImp;
+pub struct PromLatencyTracerImp {
+    /// Created on construction, once the latency unit is known.
+    metrics: OnceLock<Arc<Metrics>>,
+    /// Ports after the configured one tried in turn when it is already in use.
+    port_retries: AtomicU16,
+    /// Set once this tracer's metrics are served, by a server it started or shares, to the port
+    /// they are served on; `None` if no server could be started.
+    server_started: OnceLock<Option<u16>>,
+    /// Set once metrics are being pushed to a Pushgateway.
+    pusher: Mutex<Option<MetricsPusher>>,
+    /// Only set with an `otlp-endpoint`, where metrics are pushed in addition to being served.
+    otlp_endpoint: OnceLock<String>,
+    /// Set once metrics are being pushed to the OTLP collector.
+    otlp_pusher: Mutex<Option<MetricsPusher>>,
+    /// Key of this tracer's `PadCacheData` on src pads. Every tracer measures into its own
+    /// metrics, so tracers sharing a process must not find each other's caches.
+    pad_cache_quark: OnceLock<glib::ffi::GQuark>,
+    /// Only set with `sink=statsd`.
+    statsd: OnceLock<StatsdSink>,
+    /// Only set with `sink=file`.
+    file: OnceLock<FileSink>,
+    /// Set with `cpu-time=true`; reading the thread CPU clock costs a syscall per push.
+    cpu_time: AtomicBool,
+    /// Set with `scope=pipeline`, which only caches pad pairs leaving sources or entering sinks
+    /// and skips the per element bookkeeping of every push.
+    pipeline_scope: AtomicBool,
+    /// Set with `pipeline-latency=true` or `scope=pipeline`; source-to-sink latency stamps
+    /// buffers, so it is off by default.
+    pipeline_latency: AtomicBool,
+    /// Pad pairs into sinks recording pipeline latency, by top-level pipeline name. Buffers are
+    /// only stamped when leaving the sources of these pipelines.
+    pipeline_latency_sinks: RwLock<HashMap<String, usize>>,
+    /// Only pad pairs into elements selected by the `filter` param are measured.
+    filter: OnceLock<Filter>,
+    /// Only pad pairs into pads selected by the `pad-filter` param are measured.
+    pad_filter: OnceLock<Filter>,
+}
+
+/// Periodic push to a Pushgateway or OTLP collector, stopped by dropping `stop_tx`.
+struct MetricsPusher {
+    target: PushTarget,
+    stop_tx: Sender<()>,
+}
+
+impl MetricsPusher {
+    /// Pushes to `target` every `interval` from a new thread.
+    fn start(target: PushTarget, interval: Duration) -> Self {
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let thread_target = target.clone();
+        thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(interval) {
+                thread_target.push();
+            }
+        });
+        Self { target, stop_tx }
+    }
+}
+
+#[derive(Clone)]
+struct PushTarget {
+    destination: PushDestination,
+    metrics: Arc<Metrics>,
+}
+
+#[derive(Clone)]
+enum PushDestination {
+    /// A Pushgateway at `url`, grouping metrics under `job`.
+    Pushgateway { url: String, job: String },
+    /// An OTLP/HTTP collector.
+    Otlp(Arc<OtlpExporter>),
+}
+
+impl PushTarget {
+    /// Push all gathered metrics, logging rather than failing if the destination is unreachable.
+    fn push(&self) {
+        match &self.destination {
+            PushDestination::Pushgateway { url, job } => {
+                if let Err(err) = prometheus::push_add_metrics(
+                    job,
+                    HashMap::new(),
+                    url,
+                    self.metrics.gather(),
+                    None,
+                ) {
+                    gst::warning!(
+                        CAT,
+                        "Failed to push metrics to Pushgateway at {}: {}",
+                        url,
+                        err
+                    );
+                }
+            }
+            PushDestination::Otlp(exporter) => {
+                if let Err(err) = exporter.export(&self.metrics.gather()) {
+                    gst::warning!(
+                        CAT,
+                        "Failed to export metrics to OTLP collector at {}: {}",
+                        exporter.url(),
+                        err
+                    );
+                }
+            }
+        }
+    }
+}
 
 impl PromLatencyTracerImp {
-    /// Register all tracing hooks on construction
-    pub fn constructed(&self, tracer_obj: &gst::Tracer) {
-        // Hook callbacks
+    pub(crate) const DEFAULT_MAX_SERIES: usize = 10_000;
+
+    /// Create the tracer's metrics and apply `settings` on construction
+    ///
+    /// With `sink=statsd`, every latency recorded is also sent to the StatsD daemon, and with
+    /// `sink=file` appended to the file. With a label property, every pad pair is also labelled
+    /// with the value of that property on its source element. With `cpu_time`, the thread CPU
+    /// time spent in each element is recorded as well. With `summary`, latencies are also exposed
+    /// as a summary with the configured quantiles.
+    pub fn configure(&self, settings: &PromLatencySettings) {
+        self.cpu_time.store(settings.cpu_time, Ordering::Relaxed);
+        self.pipeline_latency.store(
+            settings.pipeline_latency || settings.scope == LatencyScope::Pipeline,
+            Ordering::Relaxed,
+        );
+        self.metrics.get_or_init(|| {
+            Arc::new(Metrics::new(
+                settings.unit,
+                settings.label_from_property.as_deref(),
+                settings.summary.then(|| settings.summary_quantiles.clone()),
+            ))
+        });
+        let statsd_address =
+            (settings.sink == MetricsSink::Statsd).then_some(settings.statsd_address.as_str());
+        let file_path = (settings.sink == MetricsSink::File).then_some(settings.file_path.as_str());
+        if let Some(address) = statsd_address {
+            match StatsdSink::connect(address) {
+                Ok(sink) => {
+                    gst::info!(CAT, "Sending latencies to StatsD at {}", address);
+                    let _ = self.statsd.set(sink);
+                }
+                Err(err) => {
+                    gst::warning!(CAT, "Failed to set up StatsD sink for {}: {}", address, err)
+                }
+            }
+        }
+        if let Some(path) = file_path {
+            match FileSink::create(path) {
+                Ok(sink) => {
+                    gst::info!(CAT, "Writing latencies to {}", path);
+                    let _ = self.file.set(sink);
+                }
+                Err(err) => gst::warning!(CAT, "Failed to open {} for latencies: {}", path, err),
+            }
+        }
+
+        self.set_max_series(settings.max_series);
+        self.set_port_retries(settings.port_retries);
+        if let Some(endpoint) = &settings.otlp_endpoint {
+            self.set_otlp_endpoint(endpoint);
+        }
+        self.set_seconds(settings.seconds);
+        self.set_filter(settings.filter.clone());
+        self.set_pad_filter(settings.pad_filter.clone());
+        self.set_scope(settings.scope);
+    }
+
+    /// Register the pad hooks of `tracer_obj`, a `PromLatencyTracer`, for the objects in `types`
+    pub(crate) fn register_hooks(tracer_obj: &gst::Tracer, types: TracedTypes) {
         unsafe extern "C" fn do_push_buffer_pre(
-            _tracer: *mut gst::Tracer,
+            tracer: *mut gst::ffi::GstTracer,
             ts: u64,
             pad: *mut gst::ffi::GstPad,
-            _buf_ptr: *mut gst::ffi::GstBuffer,
+            buf_ptr: *mut gst::ffi::GstBuffer,
         ) {
-            PromLatencyTracerImp::do_send_latency_ts(ts, pad);
+            PromLatencyTracer::core_from_ptr(tracer).pad_push_pre(ts, pad, buf_ptr);
         }
 
         unsafe extern "C" fn do_push_buffer_post(
-            _tracer: *mut gst::Tracer,
+            tracer: *mut gst::ffi::GstTracer,
             ts: u64,
             pad: *mut gst::ffi::GstPad,
         ) {
-            PromLatencyTracerImp::do_receive_and_record_latency_ts(ts, pad);
+            PromLatencyTracer::core_from_ptr(tracer).pad_push_post(ts, pad);
         }
 
         unsafe extern "C" fn do_push_list_pre(
-            _tracer: *mut gst::Tracer,
+            tracer: *mut gst::ffi::GstTracer,
             ts: u64,
             pad: *mut gst::ffi::GstPad,
-            _list_ptr: *mut gst::ffi::GstBufferList,
+            list_ptr: *mut gst::ffi::GstBufferList,
         ) {
-            PromLatencyTracerImp::do_send_latency_ts(ts, pad);
+            PromLatencyTracer::core_from_ptr(tracer).pad_push_list_pre(ts, pad, list_ptr);
         }
 
         unsafe extern "C" fn do_push_list_post(
-            _tracer: *mut gst::Tracer,
+            tracer: *mut gst::ffi::GstTracer,
             ts: u64,
             pad: *mut gst::ffi::GstPad,
         ) {
-            PromLatencyTracerImp::do_receive_and_record_latency_ts(ts, pad);
+            PromLatencyTracer::core_from_ptr(tracer).pad_push_post(ts, pad);
+        }
+
+        unsafe extern "C" fn do_push_event_pre(
+            tracer: *mut gst::ffi::GstTracer,
+            _ts: u64,
+            pad: *mut gst::ffi::GstPad,
+            event: *mut gst::ffi::GstEvent,
+        ) {
+            PromLatencyTracer::core_from_ptr(tracer).pad_push_event_pre(pad, event);
         }
 
         unsafe extern "C" fn do_pull_range_pre(
-            _tracer: *mut gst::Tracer,
+            _tracer: *mut gst::ffi::GstTracer,
             _ts: u64,
             _pad: *mut gst::ffi::GstPad,
         ) {
@@ -135,7 +1298,7 @@ impl PromLatencyTracerImp {
             // PromLatencyTracerImp::do_send_latency_ts(ts, peer);
         }
         unsafe extern "C" fn do_pull_range_post(
-            _tracer: *mut gst::Tracer,
+            _tracer: *mut gst::ffi::GstTracer,
             _ts: u64,
             _pad: *mut gst::ffi::GstPad,
         ) {
@@ -146,120 +1309,88 @@ impl PromLatencyTracerImp {
         }
 
         unsafe extern "C" fn do_pad_link_post(
-            _tracer: *mut gst::Tracer,
+            tracer: *mut gst::ffi::GstTracer,
             _ts: u64,
             src_pad: *mut gst::ffi::GstPad,
             sink_pad: *mut gst::ffi::GstPad,
             res: gst::ffi::GstPadLinkReturn,
         ) {
-            if res == ffi::GST_PAD_LINK_OK {
-                let pad_latency_cache =
-                    PromLatencyTracerImp::do_create_latency_cache_for_pad_pair(src_pad, sink_pad);
-                if pad_latency_cache == PAD_SKIP_SENTINEL as *mut PadCacheData {
-                    gst::trace!(
-                        CAT,
-                        "do_pad_link_post called for src_pad: {:?}, sink_pad: {:?}, but no cache found.",
-                        src_pad,
-                        sink_pad
-                    );
-                    return;
-                }
-
-                // If we have a valid cache, we store it in the src_pad's quark data.
-                glib::gobject_ffi::g_object_set_qdata_full(
-                    src_pad as *mut gobject_sys::GObject,
-                    *PAD_CACHE_QUARK,
-                    pad_latency_cache as *mut c_void,
-                    Some(PromLatencyTracerImp::drop_value::<PadCacheData>),
-                );
-            }
+            PromLatencyTracer::core_from_ptr(tracer).pad_link_post(src_pad, sink_pad, res);
         }
 
         unsafe extern "C" fn do_pad_unlink_post(
-            _tracer: *mut gst::Tracer,
+            tracer: *mut gst::ffi::GstTracer,
             _ts: u64,
             src_pad: *mut gst::ffi::GstPad,
             sink_pad: *mut gst::ffi::GstPad,
             res: gboolean,
         ) {
-            // For reasons unknown to me, this callback appears to be called a lot. Perhaps it is accidentally
-            // registering for all events instead of just the pad unlink events.
-            //
-            // Anyways, we can tell by the sink_pad appearing as a small value, such as 0x11, 0x21, etc.
-            if res == GTRUE && sink_pad as usize > 4096usize {
-                // See if we have a cache for this pad pair. Sometimes unlink is called for the
-                // src_pad, but the sink_pad is not a pad, its something else. I am not sure what.
-                // Anyways, as a result, we confirm the sink_pad matches what we expect before
-                // unlinking.
-                let pad_cache = glib::gobject_ffi::g_object_get_qdata(
-                    src_pad as *mut gobject_sys::GObject,
-                    *PAD_CACHE_QUARK,
-                ) as *mut PadCacheData;
-
-                // If the peer matches the provided sink, we remove the cache.
-                if !pad_cache.is_null() && sink_pad as *mut c_void == (*pad_cache).peer {
-                    gst::trace!(
-                        CAT,
-                        "removing cache for src_pad: {:?}, sink_pad: {:?}",
-                        src_pad,
-                        sink_pad
-                    );
-                    glib::gobject_ffi::g_object_set_qdata_full(
-                        src_pad as *mut gobject_sys::GObject,
-                        *PAD_CACHE_QUARK,
-                        std::ptr::null_mut(),
-                        None,
-                    );
-                }
-            }
+            PromLatencyTracer::core_from_ptr(tracer).pad_unlink_post(src_pad, sink_pad, res);
         }
 
         unsafe {
             // Push hooks; majority of the time we are pushing.
-            ffi::gst_tracing_register_hook(
-                tracer_obj.to_glib_none().0,
-                c"pad-push-pre".as_ptr(),
-                std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
-                    do_push_buffer_pre as *const (),
-                ),
-            );
-            ffi::gst_tracing_register_hook(
-                tracer_obj.to_glib_none().0,
-                c"pad-push-post".as_ptr(),
-                std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
-                    do_push_buffer_post as *const (),
-                ),
-            );
-            ffi::gst_tracing_register_hook(
-                tracer_obj.to_glib_none().0,
-                c"pad-push-list-pre".as_ptr(),
-                std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
-                    do_push_list_pre as *const (),
-                ),
-            );
-            ffi::gst_tracing_register_hook(
-                tracer_obj.to_glib_none().0,
-                c"pad-push-list-post".as_ptr(),
-                std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
-                    do_push_list_post as *const (),
-                ),
-            );
+            if types.buffers {
+                ffi::gst_tracing_register_hook(
+                    tracer_obj.to_glib_none().0,
+                    c"pad-push-pre".as_ptr(),
+                    std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                        do_push_buffer_pre as *const (),
+                    ),
+                );
+                ffi::gst_tracing_register_hook(
+                    tracer_obj.to_glib_none().0,
+                    c"pad-push-post".as_ptr(),
+                    std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                        do_push_buffer_post as *const (),
+                    ),
+                );
+            }
+            if types.buffer_lists {
+                ffi::gst_tracing_register_hook(
+                    tracer_obj.to_glib_none().0,
+                    c"pad-push-list-pre".as_ptr(),
+                    std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                        do_push_list_pre as *const (),
+                    ),
+                );
+                ffi::gst_tracing_register_hook(
+                    tracer_obj.to_glib_none().0,
+                    c"pad-push-list-post".as_ptr(),
+                    std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                        do_push_list_post as *const (),
+                    ),
+                );
+            }
+            // Event hooks; only QoS events are inspected.
+            if types.events {
+                ffi::gst_tracing_register_hook(
+                    tracer_obj.to_glib_none().0,
+                    c"pad-push-event-pre".as_ptr(),
+                    std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                        do_push_event_pre as *const (),
+                    ),
+                );
+            }
             // Pull hooks; far less common, but still useful.
-            ffi::gst_tracing_register_hook(
-                tracer_obj.to_glib_none().0,
-                c"pad-pull-range-pre".as_ptr(),
-                std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
-                    do_pull_range_pre as *const (),
-                ),
-            );
-            ffi::gst_tracing_register_hook(
-                tracer_obj.to_glib_none().0,
-                c"pad-pull-range-post".as_ptr(),
-                std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
-                    do_pull_range_post as *const (),
-                ),
-            );
+            if types.buffers {
+                ffi::gst_tracing_register_hook(
+                    tracer_obj.to_glib_none().0,
+                    c"pad-pull-range-pre".as_ptr(),
+                    std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                        do_pull_range_pre as *const (),
+                    ),
+                );
+                ffi::gst_tracing_register_hook(
+                    tracer_obj.to_glib_none().0,
+                    c"pad-pull-range-post".as_ptr(),
+                    std::mem::transmute::<*const (), Option<unsafe extern "C" fn()>>(
+                        do_pull_range_post as *const (),
+                    ),
+                );
+            }
             // Link hooks; allow us to populate and clear the pads' quark cache.
+            // Hook names must match those dispatched in gsttracerutils.c, e.g. `pad-link-post`.
             ffi::gst_tracing_register_hook(
                 tracer_obj.to_glib_none().0,
                 c"pad-link-post".as_ptr(),
@@ -277,23 +1408,405 @@ impl PromLatencyTracerImp {
         }
     }
 
+    /// Handle the pad-push-pre hook, timing the push of `buf_ptr` from `pad`.
+    ///
+    /// # Safety
+    ///
+    /// `pad` and `buf_ptr` must be those the hook was called with.
+    pub unsafe fn pad_push_pre(
+        &self,
+        ts: u64,
+        pad: *mut gst::ffi::GstPad,
+        buf_ptr: *mut gst::ffi::GstBuffer,
+    ) {
+        let bytes = ffi::gst_buffer_get_size(buf_ptr) as u64;
+        self.do_send_latency_ts(ts, pad, buf_ptr, bytes);
+    }
+
+    /// Handle the pad-push-list-pre hook, timing the push of `list_ptr` from `pad` like that of
+    /// a single buffer.
+    ///
+    /// # Safety
+    ///
+    /// `pad` and `list_ptr` must be those the hook was called with.
+    pub unsafe fn pad_push_list_pre(
+        &self,
+        ts: u64,
+        pad: *mut gst::ffi::GstPad,
+        list_ptr: *mut gst::ffi::GstBufferList,
+    ) {
+        // Sums the size of every buffer in the list
+        let bytes = ffi::gst_buffer_list_calculate_size(list_ptr) as u64;
+        // The first buffer of the list stands for the whole list end to end
+        let buf_ptr = if ffi::gst_buffer_list_length(list_ptr) > 0 {
+            ffi::gst_buffer_list_get(list_ptr, 0)
+        } else {
+            std::ptr::null_mut()
+        };
+        self.do_send_latency_ts(ts, pad, buf_ptr, bytes);
+    }
+
+    /// Handle the pad-push-post and pad-push-list-post hooks, recording the latency of the push.
+    ///
+    /// # Safety
+    ///
+    /// `pad` must be the pad the hook was called with.
+    pub unsafe fn pad_push_post(&self, ts: u64, pad: *mut gst::ffi::GstPad) {
+        self.do_receive_and_record_latency_ts(ts, pad);
+    }
+
+    /// Handle the pad-push-event-pre hook; only QoS events are inspected.
+    ///
+    /// # Safety
+    ///
+    /// `pad` and `event` must be those the hook was called with.
+    pub unsafe fn pad_push_event_pre(
+        &self,
+        pad: *mut gst::ffi::GstPad,
+        event: *mut gst::ffi::GstEvent,
+    ) {
+        if (*event).type_ == ffi::GST_EVENT_QOS {
+            self.do_record_qos(pad, event);
+        }
+    }
+
+    /// Handle the pad-link-post hook, caching the pad pair on `src_pad`.
+    ///
+    /// # Safety
+    ///
+    /// `src_pad` and `sink_pad` must be those the hook was called with.
+    pub unsafe fn pad_link_post(
+        &self,
+        src_pad: *mut gst::ffi::GstPad,
+        sink_pad: *mut gst::ffi::GstPad,
+        res: gst::ffi::GstPadLinkReturn,
+    ) {
+        if res == ffi::GST_PAD_LINK_OK {
+            self.attach_pad_cache(src_pad, sink_pad);
+            // Linking the internal pad of a ghost pad retargets it
+            self.refresh_ghost_pad_pair(src_pad);
+            self.refresh_ghost_pad_pair(sink_pad);
+        }
+    }
+
+    /// Handle the pad-unlink-post hook, removing the pad pair cached on `src_pad`.
+    ///
+    /// # Safety
+    ///
+    /// `src_pad` and `sink_pad` must be those the hook was called with.
+    pub unsafe fn pad_unlink_post(
+        &self,
+        src_pad: *mut gst::ffi::GstPad,
+        sink_pad: *mut gst::ffi::GstPad,
+        res: gboolean,
+    ) {
+        // A failed unlink leaves the pads linked
+        if res == GTRUE {
+            let pad_cache = glib::gobject_ffi::g_object_get_qdata(
+                src_pad as *mut gobject_sys::GObject,
+                self.pad_cache_quark(),
+            ) as *mut PadCacheData;
+
+            // If the peer matches the provided sink, we remove the cache.
+            if !pad_cache.is_null() && sink_pad as *mut c_void == (*pad_cache).peer {
+                gst::trace!(
+                    CAT,
+                    "removing cache for src_pad: {:?}, sink_pad: {:?}",
+                    src_pad,
+                    sink_pad
+                );
+                self.detach_pad_cache(src_pad, pad_cache);
+            }
+            // A ghost pad losing its target no longer leads to the element it labels.
+            self.refresh_ghost_pad_pair(sink_pad);
+            self.refresh_ghost_pad_pair(src_pad);
+        }
+    }
+
     /// Handle the element-new hook
-    pub fn element_new(&self, _ts: u64, element: &gst::Element, port: u16) {
-        if element.is::<gst::Pipeline>() && port > 0 {
-            METRICS_SERVER_ONCE.get_or_init(|| Self::maybe_start_metrics_server(port));
+    ///
+    /// When a Pushgateway URL is configured, metrics are pushed there instead of being served.
+    /// Otherwise scrapes are answered by `server_threads` threads, unless the HTTP server is
+    /// disabled or metrics go to another sink.
+    pub fn element_new(&self, _ts: u64, element: &gst::Element, settings: &PromLatencySettings) {
+        // A port of 0 keeps the HTTP server from being started
+        let port = if settings.http_server && settings.sink == MetricsSink::Prometheus {
+            settings.server_port
+        } else {
+            0
+        };
+        let push_interval = Duration::from_millis(settings.push_interval_ms);
+        if element.find_property(QUEUE_LEVEL_PROPERTY).is_some()
+            && self
+                .filter
+                .get()
+                .is_none_or(|filter| filter.matches(&element.name()))
+        {
+            self.metrics()
+                .queues
+                .lock()
+                .unwrap()
+                .push(element.downgrade());
+        }
+        if !element.is::<gst::Pipeline>() {
+            return;
+        }
+        if let Some(endpoint) = self.otlp_endpoint.get() {
+            self.start_otlp_push(endpoint, push_interval);
+        }
+        if let Some(url) = &settings.pushgateway_url {
+            self.start_metrics_push(url, &element.name(), push_interval);
+        } else if port > 0 {
+            let metrics = self.metrics().clone();
+            self.server_started.get_or_init(|| {
+                let port_retries = self.port_retries.load(Ordering::Relaxed);
+                let served =
+                    Self::serve_metrics(port, port_retries, settings.server_threads, metrics);
+                if served.is_none() {
+                    // Applications rarely read the debug log, so warn where they look for errors
+                    let message = gst::message::Warning::builder(
+                        gst::ResourceError::Busy,
+                        &format!("Could not serve Prometheus metrics on port {port}"),
+                    )
+                    .src(element)
+                    .build();
+                    let _ = element.post_message(message);
+                }
+                served
+            });
         }
     }
 
+    /// The port this tracer's metrics are served on, once a pipeline started the server.
+    pub fn served_port(&self) -> Option<u16> {
+        self.server_started.get().copied().flatten()
+    }
+
+    /// Handle the element-change-state-pre hook, remembering when the transition started.
+    pub fn element_change_state_pre(&self, ts: u64, element: &gst::Element) {
+        unsafe { element.set_qdata(*STATE_CHANGE_START_QUARK, ts) };
+    }
+
+    /// Handle the element-change-state-post hook, recording how long the transition took.
+    ///
+    /// Transitions returning `ASYNC` only account for the synchronous part of the change.
+    pub fn element_change_state_post(
+        &self,
+        ts: u64,
+        element: &gst::Element,
+        change: gst::StateChange,
+    ) {
+        let Some(start) = (unsafe { element.steal_qdata::<u64>(*STATE_CHANGE_START_QUARK) }) else {
+            return;
+        };
+        let duration = Duration::from_nanos(ts.saturating_sub(start));
+        gst::trace!(
+            CAT,
+            "{} {} took {:?}",
+            element.name(),
+            change.name(),
+            duration
+        );
+        self.metrics()
+            .state_change_duration
+            .with_label_values(&[element.name().as_str(), change.name().as_str()])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Stop pushing metrics, pushing them one final time so short-lived pipelines are captured.
+    pub fn dispose(&self) {
+        for pusher in [&self.pusher, &self.otlp_pusher] {
+            if let Some(pusher) = pusher.lock().unwrap().take() {
+                drop(pusher.stop_tx);
+                pusher.target.push();
+            }
+        }
+        if let Some(Err(err)) = self.file.get().map(FileSink::flush) {
+            gst::warning!(CAT, "Failed to flush latencies to file: {}", err);
+        }
+    }
+
+    /// Start pushing metrics on an interval, using the first pipeline's name as the `job` label.
+    fn start_metrics_push(&self, url: &str, job: &str, interval: Duration) {
+        let mut pusher = self.pusher.lock().unwrap();
+        if pusher.is_some() {
+            return;
+        }
+
+        let target = PushTarget {
+            destination: PushDestination::Pushgateway {
+                url: url.to_string(),
+                job: job.to_string(),
+            },
+            metrics: self.metrics().clone(),
+        };
+        gst::info!(
+            CAT,
+            "Pushing metrics to Pushgateway at {} every {:?} as job {}",
+            url,
+            interval,
+            job
+        );
+        *pusher = Some(MetricsPusher::start(target, interval));
+    }
+
+    /// Start exporting metrics to an OTLP collector on an interval.
+    fn start_otlp_push(&self, endpoint: &str, interval: Duration) {
+        let mut pusher = self.otlp_pusher.lock().unwrap();
+        if pusher.is_some() {
+            return;
+        }
+
+        let exporter = match OtlpExporter::new(endpoint) {
+            Ok(exporter) => exporter,
+            Err(err) => {
+                gst::warning!(CAT, "Failed to set up OTLP export to {}: {}", endpoint, err);
+                return;
+            }
+        };
+        gst::info!(
+            CAT,
+            "Exporting metrics to OTLP collector at {} every {:?}",
+            exporter.url(),
+            interval
+        );
+        let target = PushTarget {
+            destination: PushDestination::Otlp(Arc::new(exporter)),
+            metrics: self.metrics().clone(),
+        };
+        *pusher = Some(MetricsPusher::start(target, interval));
+    }
+
+    /// Also exports metrics to the OTLP/HTTP collector at `endpoint`, every push interval.
+    pub fn set_otlp_endpoint(&self, endpoint: &str) {
+        let _ = self.otlp_endpoint.set(endpoint.to_string());
+    }
+
+    /// Only measures pad pairs into elements whose name `filter` selects; pairs are checked once,
+    /// when linked.
+    pub fn set_filter(&self, filter: Filter) {
+        let _ = self.filter.set(filter);
+    }
+
+    /// Only measures pad pairs into pads whose `element.pad` name `filter` selects, e.g.
+    /// `mux.video_*`; like `set_filter`, pairs are checked once, when linked.
+    pub fn set_pad_filter(&self, filter: Filter) {
+        let _ = self.pad_filter.set(filter);
+    }
+
+    /// Tries up to `retries` ports after the configured one when it is already in use.
+    pub fn set_port_retries(&self, retries: u16) {
+        self.port_retries.store(retries, Ordering::Relaxed);
+    }
+
+    /// Caps the distinct label sets of per pad pair metrics; 0 disables the cap.
+    pub fn set_max_series(&self, max_series: usize) {
+        self.metrics()
+            .max_series
+            .store(max_series, Ordering::Relaxed);
+    }
+
+    /// Measures only end-to-end latency with [`LatencyScope::Pipeline`]. Only applies to pad
+    /// pairs linked afterwards.
+    pub fn set_scope(&self, scope: LatencyScope) {
+        self.pipeline_scope
+            .store(scope == LatencyScope::Pipeline, Ordering::Relaxed);
+    }
+
+    /// Reports times in seconds rather than the latency unit, under `_seconds` names.
+    pub fn set_seconds(&self, seconds: bool) {
+        self.metrics().seconds.store(seconds, Ordering::Relaxed);
+    }
+
+    pub(crate) fn metrics(&self) -> &Arc<Metrics> {
+        self.metrics
+            .get()
+            .expect("metrics are created when the tracer is constructed")
+    }
+
+    /// Gathers the metric families of this tracer, leaving the min/max window open.
+    pub fn metric_families(&self) -> Vec<MetricFamily> {
+        self.metrics().gather()
+    }
+
     // Add this function, which is the handler for the "metrics" signal
-    pub fn request_metrics() -> String {
-        let metric_families = gather();
+    pub fn request_metrics(&self) -> String {
+        let metric_families = self.metric_families();
         let mut buffer = Vec::new();
         let encoder = TextEncoder::new();
         encoder
             .encode(&metric_families, &mut buffer)
             .expect("Failed to encode metrics");
 
-        String::from_utf8(buffer).expect("Metrics buffer is not valid UTF-8")
+        String::from_utf8(buffer).expect("Metrics buffer is not valid UTF-8")
+    }
+
+    /// Handler for the "request-percentiles" signal
+    pub fn request_percentiles(&self) -> String {
+        self.metrics().percentiles()
+    }
+
+    /// Handler for the "request-metrics-json" signal
+    pub fn request_metrics_json(&self) -> String {
+        Self::encode_json(&self.metrics().gather())
+    }
+
+    /// Encodes metric families as a JSON array of `{name, labels, value}` samples.
+    ///
+    /// Histograms are flattened into their `_sum` and `_count` samples, summaries additionally
+    /// into a sample per quantile carrying a `quantile` label.
+    pub(crate) fn encode_json(metric_families: &[MetricFamily]) -> String {
+        let mut samples = Vec::new();
+        for mf in metric_families {
+            for m in mf.get_metric() {
+                let labels = m
+                    .get_label()
+                    .iter()
+                    .map(|lp| (lp.name().to_string(), lp.value().into()))
+                    .collect::<serde_json::Map<_, _>>();
+                let mut push_labelled_sample =
+                    |name: String, labels: &serde_json::Map<_, _>, value: f64| {
+                        samples.push(serde_json::json!({
+                            "name": name,
+                            "labels": labels,
+                            "value": value,
+                        }));
+                    };
+                let mut push_sample =
+                    |name: String, value: f64| push_labelled_sample(name, &labels, value);
+                match mf.get_field_type() {
+                    MetricType::COUNTER => {
+                        push_sample(mf.name().to_string(), m.get_counter().value())
+                    }
+                    MetricType::GAUGE => push_sample(mf.name().to_string(), m.get_gauge().value()),
+                    MetricType::HISTOGRAM => {
+                        let h = m.get_histogram();
+                        push_sample(format!("{}_sum", mf.name()), h.sample_sum());
+                        push_sample(format!("{}_count", mf.name()), h.sample_count() as f64);
+                    }
+                    MetricType::SUMMARY => {
+                        let summary = m.get_summary();
+                        push_sample(format!("{}_sum", mf.name()), summary.sample_sum());
+                        push_sample(
+                            format!("{}_count", mf.name()),
+                            summary.sample_count() as f64,
+                        );
+                        for q in summary.get_quantile() {
+                            let mut quantile_labels = labels.clone();
+                            quantile_labels
+                                .insert("quantile".into(), q.quantile().to_string().into());
+                            push_labelled_sample(
+                                mf.name().to_string(),
+                                &quantile_labels,
+                                q.value(),
+                            );
+                        }
+                    }
+                    MetricType::UNTYPED => {}
+                }
+            }
+        }
+        serde_json::Value::Array(samples).to_string()
     }
 
     /// Given an optional `Pad`, returns the real parent `Element`, skipping over a `GhostPad` proxy.
@@ -373,6 +1886,102 @@ impl PromLatencyTracerImp {
         }
     }
 
+    unsafe fn is_ghost_pad(pad: *mut ffi::GstPad) -> bool {
+        let ghost_pad_type = ffi::gst_ghost_pad_get_type();
+        glib::gobject_ffi::g_type_check_instance_is_a(
+            pad as *mut glib::gobject_ffi::GTypeInstance,
+            ghost_pad_type,
+        ) == glib::ffi::GTRUE
+    }
+
+    /// Creates the cache of a newly linked pad pair and attaches it to `src_pad`, replacing any
+    /// cache already there. Pad pairs that are skipped have their existing cache removed.
+    fn pad_cache_quark(&self) -> glib::ffi::GQuark {
+        *self.pad_cache_quark.get_or_init(|| {
+            let id = NEXT_PAD_CACHE_ID.fetch_add(1, Ordering::Relaxed);
+            Quark::from_str(format!("promlatency.pad_cache.{id}")).into_glib()
+        })
+    }
+
+    unsafe fn attach_pad_cache(&self, src_pad: *mut ffi::GstPad, sink_pad: *mut ffi::GstPad) {
+        let previous = glib::gobject_ffi::g_object_get_qdata(
+            src_pad as *mut gobject_sys::GObject,
+            self.pad_cache_quark(),
+        ) as *mut PadCacheData;
+        if !previous.is_null() {
+            self.detach_pad_cache(src_pad, previous);
+        }
+
+        let pad_latency_cache = self.do_create_latency_cache_for_pad_pair(src_pad, sink_pad);
+        if pad_latency_cache == PAD_SKIP_SENTINEL as *mut PadCacheData {
+            gst::trace!(
+                CAT,
+                "do_pad_link_post called for src_pad: {:?}, sink_pad: {:?}, but no cache found.",
+                src_pad,
+                sink_pad
+            );
+            return;
+        }
+
+        // If we have a valid cache, we store it in the src_pad's quark data.
+        self.metrics().active_pad_pairs.inc();
+        glib::gobject_ffi::g_object_set_qdata_full(
+            src_pad as *mut gobject_sys::GObject,
+            self.pad_cache_quark(),
+            pad_latency_cache as *mut c_void,
+            Some(PromLatencyTracerImp::drop_value::<PadCacheData>),
+        );
+    }
+
+    /// Removes `pad_cache` from `src_pad` along with the pad pair's series, so dynamic
+    /// pipelines do not accumulate them.
+    unsafe fn detach_pad_cache(&self, src_pad: *mut ffi::GstPad, pad_cache: *mut PadCacheData) {
+        let metrics = self.metrics();
+        metrics.release_series(&(*pad_cache).labels);
+        metrics.active_pad_pairs.dec();
+        if (*pad_cache).pipeline_latency.is_some() {
+            let mut sinks = self.pipeline_latency_sinks.write().unwrap();
+            if let Some(count) = sinks.get_mut(&(*pad_cache).pipeline) {
+                *count -= 1;
+                if *count == 0 {
+                    sinks.remove(&(*pad_cache).pipeline);
+                }
+            }
+        }
+        glib::gobject_ffi::g_object_set_qdata_full(
+            src_pad as *mut gobject_sys::GObject,
+            self.pad_cache_quark(),
+            std::ptr::null_mut(),
+            None,
+        );
+    }
+
+    /// Ghost pads change target by relinking their internal pad, e.g. when `decodebin` exposes
+    /// a new stream. The pad pair through the ghost pad was resolved against the old target, so
+    /// when `pad` is the internal pad of a linked ghost pad, that pad pair is resolved again.
+    unsafe fn refresh_ghost_pad_pair(&self, pad: *mut ffi::GstPad) {
+        if !Self::is_proxy_pad(pad) || Self::is_ghost_pad(pad) {
+            return;
+        }
+        let ghost_pad = ffi::gst_object_get_parent(pad as *mut ffi::GstObject) as *mut ffi::GstPad;
+        if ghost_pad.is_null() {
+            return;
+        }
+        if Self::is_ghost_pad(ghost_pad) {
+            let peer = ffi::gst_pad_get_peer(ghost_pad);
+            if !peer.is_null() {
+                gst::debug!(CAT, "Resolving the pad pair through {:?} again", ghost_pad);
+                if ffi::gst_pad_get_direction(ghost_pad) == ffi::GST_PAD_SINK {
+                    self.attach_pad_cache(peer, ghost_pad);
+                } else {
+                    self.attach_pad_cache(ghost_pad, peer);
+                }
+                ffi::gst_object_unref(peer as *mut ffi::GstObject);
+            }
+        }
+        ffi::gst_object_unref(ghost_pad as *mut ffi::GstObject);
+    }
+
     /// Drop function for the `gobject` quark data.
     /// This is called when the `gobject` quark data is removed.
     /// It safely converts the pointer back to a Box and drops it.
@@ -392,6 +2001,7 @@ impl PromLatencyTracerImp {
     /// Given a source and sink pad, returns the PadCacheData for the pad pair.
     /// If the pads are not valid for any reason, returns a sentinel value indicating to skip this pair.
     fn do_create_latency_cache_for_pad_pair(
+        &self,
         src_pad: *mut gst::ffi::GstPad,
         sink_pad: *mut gst::ffi::GstPad,
     ) -> *mut PadCacheData {
@@ -442,11 +2052,37 @@ impl PromLatencyTracerImp {
         }
 
         // Prepare metrics
-        let _src_parent = unsafe { gst::Element::from_glib_none(src_parent_element.unwrap()) };
+        let src_parent = unsafe { gst::Element::from_glib_none(src_parent_element.unwrap()) };
         let sink_parent = unsafe { gst::Element::from_glib_none(sink_parent_element.unwrap()) };
         let el_name = sink_parent.name().to_string();
+        let pipeline_scope = self.pipeline_scope.load(Ordering::Relaxed);
+        let leaves_source = src_parent
+            .element_flags()
+            .contains(gst::ElementFlags::SOURCE);
+        let into_sink = sink_parent
+            .element_flags()
+            .contains(gst::ElementFlags::SINK);
+        if pipeline_scope && !leaves_source && !into_sink {
+            gst::trace!(
+                CAT,
+                "Skipping pad pair into {}, not at a pipeline boundary",
+                el_name
+            );
+            return PAD_SKIP_SENTINEL as *mut PadCacheData;
+        }
+        // Factory of the measured element, so latency can be aggregated by element type.
+        let el_factory = sink_parent
+            .factory()
+            .map(|f| f.name().to_string())
+            .unwrap_or("unknown".to_string());
         let src_pad_name = Self::pad_name(src_pad);
         let sink_pad_name = Self::pad_name(sink_pad);
+        // With `scope=pipeline`, filters only select the sinks recording end-to-end latency, as
+        // buffers must be stamped on leaving every source.
+        let selected = self.is_selected(&el_name, &sink_pad_name);
+        if !selected && !(pipeline_scope && leaves_source) {
+            return PAD_SKIP_SENTINEL as *mut PadCacheData;
+        }
 
         // FIXME - technically would only want to compute these when we switch to PLAYING state for the pipeline
         //         otherwise the 'path' may not include the full path if the elements the bins have been added to
@@ -458,21 +2094,152 @@ impl PromLatencyTracerImp {
             .parent()
             .map(|p| p.path_string().to_string())
             .unwrap_or("none".to_string());
-        let labels = [&el_name, &src_pad_name, &sink_pad_name, &ancestor_path];
-        let last_gauge = LATENCY_LAST.with_label_values(&labels);
-        let sum_counter = LATENCY_SUM.with_label_values(&labels);
-        let count_counter = LATENCY_COUNT.with_label_values(&labels);
+        // Only the top-level caps name is used to keep the label cardinality bounded.
+        let media_type = Self::pad_media_type(src_pad);
+        let media_type_pending = media_type.is_none();
+        let metrics = self.metrics();
+        let mut labels = vec![
+            el_name,
+            el_factory,
+            src_pad_name,
+            sink_pad_name,
+            ancestor_path,
+            media_type.unwrap_or("unknown".to_string()),
+        ];
+        if let Some(property) = &metrics.label_property {
+            labels.push(Self::property_label_value(&src_parent, property));
+        }
+        let pad_metrics = if pipeline_scope {
+            metrics.acquire_labels(&mut labels);
+            None
+        } else {
+            Some(metrics.acquire_series(&mut labels))
+        };
+        let pipeline_latency_enabled = self.pipeline_latency.load(Ordering::Relaxed);
+        let pipeline = if pipeline_latency_enabled && (leaves_source || into_sink) {
+            Self::top_level_name(&sink_parent)
+        } else {
+            String::new()
+        };
+        let pipeline_latency = (pipeline_latency_enabled && into_sink && selected)
+            .then(|| metrics.pipeline_latency_with_label_values(&labels));
+        if pipeline_latency.is_some() {
+            *self
+                .pipeline_latency_sinks
+                .write()
+                .unwrap()
+                .entry(pipeline.clone())
+                .or_default() += 1;
+        }
+        // A sink element without src pads has no peer downstream, so no pad pair out of it
+        // measures what it does with a buffer: the push into it ends in its chain function. The
+        // time from the buffer reaching its sink pad until the push returns is its render
+        // latency, which leaves out the probes and proxy pads the push went through before.
+        let render_latency = if into_sink && sink_parent.src_pads().is_empty() {
+            pad_metrics.is_some().then(|| {
+                let real_sink_pad = Self::get_real_pad_ffi(sink_pad).unwrap_or(sink_pad);
+                RenderLatency::new(
+                    metrics.render_latency_last.with_label_values(&labels),
+                    &unsafe { gst::Pad::from_glib_none(real_sink_pad) },
+                )
+            })
+        } else {
+            None
+        };
+        let statsd_tags = if self.statsd.get().is_some() {
+            StatsdSink::tags(&metrics.pad_pair_labels, &labels)
+        } else {
+            String::new()
+        };
+        let file_fields = if self.file.get().is_some() {
+            FileSink::fields(&metrics.pad_pair_labels, &labels)
+        } else {
+            String::new()
+        };
 
         // Create cache
         Box::into_raw(Box::new(PadCacheData {
             ts: 0,
+            last_push_ts: 0,
             peer: sink_pad as *mut c_void,
-            last_gauge,
-            sum_counter,
-            count_counter,
+            labels,
+            media_type_pending,
+            min: u64::MAX,
+            max: 0,
+            interval_min: u64::MAX,
+            interval_max: 0,
+            min_max_window: metrics.min_max_window.load(Ordering::Relaxed),
+            metrics: pad_metrics,
+            statsd_tags,
+            file_fields,
+            stamps_source_ts: pipeline_latency_enabled && leaves_source,
+            pipeline,
+            pipeline_latency,
+            render_latency,
         }))
     }
 
+    /// Name of the pipeline, or other top-level bin, `element` is in.
+    fn top_level_name(element: &gst::Element) -> String {
+        let mut top = element.clone().upcast::<gst::Object>();
+        while let Some(parent) = top.parent() {
+            top = parent;
+        }
+        top.name().to_string()
+    }
+
+    /// Whether the `filter` and `pad-filter` params select pad pairs into `sink_pad_name` of
+    /// `el_name`.
+    fn is_selected(&self, el_name: &str, sink_pad_name: &str) -> bool {
+        if self
+            .filter
+            .get()
+            .is_some_and(|filter| !filter.matches(el_name))
+        {
+            gst::trace!(
+                CAT,
+                "Skipping pad pair into {}, not selected by filter",
+                el_name
+            );
+            return false;
+        }
+        if self
+            .pad_filter
+            .get()
+            .is_some_and(|filter| !filter.matches(&format!("{el_name}.{sink_pad_name}")))
+        {
+            gst::trace!(
+                CAT,
+                "Skipping pad pair into {}.{}, not selected by pad-filter",
+                el_name,
+                sink_pad_name
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Stringifies a property of `element`, empty if it has no such property or the value
+    /// cannot be converted to a string.
+    fn property_label_value(element: &gst::Element, property: &str) -> String {
+        if element.find_property(property).is_none() {
+            return String::new();
+        }
+        element
+            .property_value(property)
+            .transform::<String>()
+            .ok()
+            .and_then(|value| value.get::<Option<String>>().ok().flatten())
+            .unwrap_or_default()
+    }
+
+    /// Returns the structure name of the pad's current caps, such as `video/x-raw`.
+    fn pad_media_type(pad: *mut gst::ffi::GstPad) -> Option<String> {
+        let pad = unsafe { gst::Pad::from_glib_none(pad) };
+        pad.current_caps()
+            .and_then(|caps| caps.structure(0).map(|s| s.name().to_string()))
+    }
+
     fn pad_name(pad: *mut gst::ffi::GstPad) -> String {
         let name = unsafe { gst::Pad::from_glib_none(pad).name().to_string() };
         // apply regex to strip off trailing _0-9+ if present
@@ -491,10 +2258,32 @@ impl PromLatencyTracerImp {
             .to_string()
     }
 
-    unsafe fn do_send_latency_ts(ts: u64, src_pad: *mut gst::ffi::GstPad) {
+    /// Identifies this tracer's frames on the per-thread push stack.
+    fn push_owner(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    unsafe fn do_send_latency_ts(
+        &self,
+        ts: u64,
+        src_pad: *mut gst::ffi::GstPad,
+        buf_ptr: *mut gst::ffi::GstBuffer,
+        bytes: u64,
+    ) {
+        // Every push is tracked, so time spent in unmeasured pads still counts as downstream.
+        // Without element latencies to attribute, there is nothing to track.
+        let pipeline_scope = self.pipeline_scope.load(Ordering::Relaxed);
+        if !pipeline_scope {
+            PUSH_FRAMES.with_borrow_mut(|frames| frames.enter(self.push_owner(), ts));
+        }
+        if self.cpu_time.load(Ordering::Relaxed) && !pipeline_scope {
+            let cpu_ts = thread_cpu_time_ns();
+            CPU_FRAMES.with_borrow_mut(|frames| frames.enter(self.push_owner(), cpu_ts));
+        }
+
         let pad_cache = glib::gobject_ffi::g_object_get_qdata(
             src_pad as *mut gobject_sys::GObject,
-            *PAD_CACHE_QUARK,
+            self.pad_cache_quark(),
         ) as *mut PadCacheData;
         if pad_cache.is_null() {
             return;
@@ -503,17 +2292,131 @@ impl PromLatencyTracerImp {
         // If we have a valid cache, we can safely convert the pointer to a Box.
         let pad_cache: &mut PadCacheData = &mut *pad_cache;
 
+        if pad_cache.media_type_pending {
+            pad_cache.resolve_pending_media_type(self.metrics(), src_pad);
+        }
+
+        // Record the interval since the previous push on this pad
+        let metrics = self.metrics();
+        pad_cache.sync_min_max_window(metrics);
+        if let Some(pad_metrics) = pad_cache
+            .metrics
+            .as_ref()
+            .filter(|_| pad_cache.last_push_ts != 0)
+        {
+            let interval = metrics
+                .unit
+                .convert(ts.saturating_sub(pad_cache.last_push_ts));
+            pad_cache.interval_min = pad_cache.interval_min.min(interval);
+            pad_cache.interval_max = pad_cache.interval_max.max(interval);
+            pad_metrics
+                .interval_last_gauge
+                .set(interval.try_into().unwrap_or(i64::MAX));
+            pad_metrics
+                .interval_min_gauge
+                .set(pad_cache.interval_min.try_into().unwrap_or(i64::MAX));
+            pad_metrics
+                .interval_max_gauge
+                .set(pad_cache.interval_max.try_into().unwrap_or(i64::MAX));
+        }
+        pad_cache.last_push_ts = ts;
+
         // Set the ts
         pad_cache.ts = ts;
+        if let Some(pad_metrics) = &pad_cache.metrics {
+            pad_metrics.buffers_in_counter.inc();
+            pad_metrics.bytes_counter.inc_by(bytes);
+            // Of a list only the first buffer's flags are seen, like its timestamp
+            if !buf_ptr.is_null() {
+                let flags = ffi::gst_buffer_get_flags(buf_ptr);
+                if flags & ffi::GST_BUFFER_FLAG_DISCONT != 0 {
+                    pad_metrics.discont_counter.inc();
+                }
+                if flags & ffi::GST_BUFFER_FLAG_GAP != 0 {
+                    pad_metrics.gap_counter.inc();
+                }
+                if flags & ffi::GST_BUFFER_FLAG_CORRUPTED != 0 {
+                    pad_metrics.corrupted_counter.inc();
+                }
+            }
+        }
+
+        if !buf_ptr.is_null() {
+            self.do_record_pipeline_latency(ts, pad_cache, buf_ptr);
+        }
+    }
+
+    /// Stamps buffers leaving a source element with `ts`, and records the time since that stamp
+    /// for buffers arriving at a sink element.
+    ///
+    /// Buffers created mid-pipeline carry no stamp and are not recorded. A buffer that is not
+    /// writable when it leaves its source cannot be stamped either, nor have its stamp removed
+    /// once recorded.
+    unsafe fn do_record_pipeline_latency(
+        &self,
+        ts: u64,
+        pad_cache: &PadCacheData,
+        buf_ptr: *mut gst::ffi::GstBuffer,
+    ) {
+        let caps = SOURCE_TS_CAPS.as_ptr() as *mut gst::ffi::GstCaps;
+        if pad_cache.stamps_source_ts
+            && self
+                .pipeline_latency_sinks
+                .read()
+                .unwrap()
+                .contains_key(&pad_cache.pipeline)
+            && ffi::gst_mini_object_is_writable(buf_ptr as *const ffi::GstMiniObject)
+                != glib::ffi::GFALSE
+            && ffi::gst_buffer_get_reference_timestamp_meta(buf_ptr, caps).is_null()
+        {
+            ffi::gst_buffer_add_reference_timestamp_meta(
+                buf_ptr,
+                caps,
+                ts,
+                ffi::GST_CLOCK_TIME_NONE,
+            );
+        }
 
-        // Zero out the span latency
-        SPAN_LATENCY.with(|v| v.set(0));
+        let Some(pipeline_latency) = &pad_cache.pipeline_latency else {
+            return;
+        };
+        let meta = ffi::gst_buffer_get_reference_timestamp_meta(buf_ptr, caps);
+        let Some(stamp) = meta.as_ref() else {
+            return;
+        };
+        let latency = self
+            .metrics()
+            .unit
+            .convert(ts.saturating_sub(stamp.timestamp));
+        pipeline_latency
+            .last_gauge
+            .set(latency.try_into().unwrap_or(i64::MAX));
+        pipeline_latency.sum_counter.inc_by(latency);
+        pipeline_latency.count_counter.inc();
+
+        // The stamp ends at the sink, so a buffer handed on, e.g. by an appsink, does not carry it
+        if ffi::gst_mini_object_is_writable(buf_ptr as *const ffi::GstMiniObject)
+            != glib::ffi::GFALSE
+        {
+            ffi::gst_buffer_remove_meta(buf_ptr, meta as *mut ffi::GstMeta);
+        }
     }
 
-    unsafe fn do_receive_and_record_latency_ts(ts: u64, src_pad: *mut gst::ffi::GstPad) {
+    unsafe fn do_receive_and_record_latency_ts(&self, ts: u64, src_pad: *mut gst::ffi::GstPad) {
+        if self.pipeline_scope.load(Ordering::Relaxed) {
+            return;
+        }
+        let span = PUSH_FRAMES.with_borrow_mut(|frames| frames.exit(self.push_owner(), ts));
+        let cpu_span = if self.cpu_time.load(Ordering::Relaxed) {
+            let cpu_ts = thread_cpu_time_ns();
+            CPU_FRAMES.with_borrow_mut(|frames| frames.exit(self.push_owner(), cpu_ts))
+        } else {
+            None
+        };
+
         let pad_cache = glib::gobject_ffi::g_object_get_qdata(
             src_pad as *mut gobject_sys::GObject,
-            *PAD_CACHE_QUARK,
+            self.pad_cache_quark(),
         ) as *mut PadCacheData;
         if pad_cache.is_null() {
             return;
@@ -521,70 +2424,387 @@ impl PromLatencyTracerImp {
 
         // If we have a valid cache, we can safely convert the pointer to a Box.
         let pad_cache: &mut PadCacheData = &mut *pad_cache;
-
-        // If the ts is 0, we skip, as we have not had a valid push yet.
-        if pad_cache.ts == 0 {
+        let metrics = self.metrics();
+        pad_cache.sync_min_max_window(metrics);
+        let Some(pad_metrics) = &pad_cache.metrics else {
             return;
+        };
+        pad_metrics.buffers_out_counter.inc();
+        if let Some(cpu_span) = cpu_span {
+            let cpu_ns = Self::compute_element_latency(cpu_span.total, cpu_span.downstream);
+            pad_metrics
+                .cpu_seconds_counter
+                .inc_by(cpu_ns as f64 / 1_000_000_000.0);
         }
 
-        // Calculate the difference
-        let span_diff = ts.saturating_sub(pad_cache.ts);
+        // If the ts is 0, we skip, as we have not had a valid push yet.
+        let Some(span) = span.filter(|_| pad_cache.ts != 0) else {
+            return;
+        };
 
-        // Get cached latency if needed
-        let ts_latency = SPAN_LATENCY.with(|v| v.get());
-        // gst::info!(CAT, "Current span latency: {}", ts_latency);
+        // Calculate the per element difference, in the unit it is reported in
+        let latency_ns = Self::compute_element_latency(span.total, span.downstream);
+        let el_diff = metrics.unit.convert(latency_ns);
+        if let Some(statsd) = self.statsd.get() {
+            statsd.send_latency(latency_ns, &pad_cache.statsd_tags);
+        }
+        if let Some(file) = self.file.get() {
+            file.write_latency(ts, latency_ns, &pad_cache.file_fields);
+        }
 
-        // Calculate the per element difference
-        let el_diff = Self::compute_element_latency(span_diff, ts_latency);
+        pad_cache.min = pad_cache.min.min(el_diff);
+        pad_cache.max = pad_cache.max.max(el_diff);
 
         // Log the latency
-        pad_cache
+        pad_metrics
             .last_gauge
             .set(el_diff.try_into().unwrap_or(i64::MAX));
-        pad_cache.sum_counter.inc_by(el_diff);
-        pad_cache.count_counter.inc();
+        pad_metrics
+            .min_gauge
+            .set(pad_cache.min.try_into().unwrap_or(i64::MAX));
+        pad_metrics
+            .max_gauge
+            .set(pad_cache.max.try_into().unwrap_or(i64::MAX));
+        pad_metrics.sum_counter.inc_by(el_diff);
+        pad_metrics.count_counter.inc();
+        if let Some(render_latency) = &pad_cache.render_latency {
+            render_latency.record(ts, metrics.unit);
+        }
+        if let Some(summary) = &pad_metrics.summary {
+            summary.lock().unwrap().observe(el_diff);
+        }
 
         // Reset the timestamp for the next push
         pad_cache.ts = 0;
+    }
 
-        // Set the SPAN_LATENCY to span_diff so upstream elements know how much
-        // latency to subtract from their own latency.
-        SPAN_LATENCY.with(|v| v.set(span_diff));
+    /// QoS events travel upstream, so `sink_pad` is the pad the event is pushed from and the
+    /// pad cache lives on its peer src pad.
+    unsafe fn do_record_qos(
+        &self,
+        sink_pad: *mut gst::ffi::GstPad,
+        event: *mut gst::ffi::GstEvent,
+    ) {
+        let mut diff: i64 = 0;
+        ffi::gst_event_parse_qos(
+            event,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut diff,
+            std::ptr::null_mut(),
+        );
+
+        // Sinks send a QoS event for every rendered buffer; a positive diff means the buffer
+        // was late, which is when it gets dropped.
+        if diff <= 0 {
+            return;
+        }
+
+        let src_pad = ffi::gst_pad_get_peer(sink_pad);
+        if src_pad.is_null() {
+            return;
+        }
+
+        let pad_cache = glib::gobject_ffi::g_object_get_qdata(
+            src_pad as *mut gobject_sys::GObject,
+            self.pad_cache_quark(),
+        ) as *mut PadCacheData;
+        if let Some(pad_metrics) = pad_cache.as_ref().and_then(|cache| cache.metrics.as_ref()) {
+            pad_metrics.qos_dropped_counter.inc();
+        }
+
+        ffi::gst_object_unref(src_pad as *mut ffi::GstObject);
     }
 
-    /// Spawn the HTTP server in a new thread on the provided port.
-    fn maybe_start_metrics_server(port: u16) {
-        thread::spawn(move || {
-            let addr = ("0.0.0.0", port);
-            let server_r = Server::http(addr);
-            if server_r.is_err() {
-                gst::warning!(
+    /// Serve `metrics` on `port`, joining the server another tracer already runs for that port
+    /// or starting one, and return the port they are served on.
+    fn serve_metrics(
+        port: u16,
+        retries: u16,
+        threads: usize,
+        metrics: Arc<Metrics>,
+    ) -> Option<u16> {
+        let mut servers = METRICS_SERVERS.lock().unwrap();
+        if let Some(server) = servers.get(&port) {
+            gst::info!(
+                CAT,
+                "Sharing the Prometheus metrics server on port {}",
+                server.port
+            );
+            server
+                .sources
+                .lock()
+                .unwrap()
+                .push(Arc::downgrade(&metrics));
+            return Some(server.port);
+        }
+        let server = Self::bind_metrics_server(port, retries)?;
+        let metrics_server = Arc::new(MetricsServer {
+            sources: Mutex::new(vec![Arc::downgrade(&metrics)]),
+            port: server
+                .server_addr()
+                .to_ip()
+                .map_or(port, |addr| addr.port()),
+            last_scrape: AtomicU64::new(0),
+        });
+        Self::start_metrics_server(server, threads, metrics_server.clone());
+        let served_port = metrics_server.port;
+        servers.insert(port, metrics_server);
+        Some(served_port)
+    }
+
+    /// Binds `port`, or when it is taken the first free one of the `retries` ports after it.
+    fn bind_metrics_server(port: u16, retries: u16) -> Option<Server> {
+        for candidate in port..=port.saturating_add(retries) {
+            match Server::http(("0.0.0.0", candidate)) {
+                Ok(server) => return Some(server),
+                Err(err) => gst::warning!(
                     CAT,
-                    "Failed to start Prometheus metrics server on 0.0.0.0:{}",
-                    port
-                );
-                return;
+                    "Failed to start Prometheus metrics server on 0.0.0.0:{}: {}",
+                    candidate,
+                    err
+                ),
+            }
+        }
+        None
+    }
+
+    /// Serve scrapes from `threads` worker threads, so a slow scrape or a client that is slow
+    /// to read its response does not hold up the others. Requests are only handed to the workers
+    /// once fully read, so clients stalling mid-request never occupy a worker.
+    fn start_metrics_server(server: Server, threads: usize, metrics: Arc<MetricsServer>) {
+        let server = Arc::new(server);
+        gst::info!(
+            CAT,
+            "Prometheus metrics server listening on {} with {} threads",
+            metrics.port,
+            threads
+        );
+
+        for i in 0..threads.max(1) {
+            let server = server.clone();
+            let metrics = metrics.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("prom-metrics-{i}"))
+                .spawn(move || {
+                    for request in server.incoming_requests() {
+                        Self::respond_with_metrics(request, &metrics);
+                    }
+                });
+            if let Err(err) = spawned {
+                gst::warning!(CAT, "Failed to spawn metrics server thread: {}", err);
+            }
+        }
+    }
+
+    fn respond_with_metrics(request: tiny_http::Request, metrics: &MetricsServer) {
+        let openmetrics = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Accept"))
+            .is_some_and(|h| Self::prefers_openmetrics(h.value.as_str()));
+
+        // Gather and encode all registered metrics
+        let metric_families = metrics.scrape();
+        let (buffer, content_type) = if openmetrics {
+            (
+                Self::encode_openmetrics(&metric_families).into_bytes(),
+                OPENMETRICS_CONTENT_TYPE,
+            )
+        } else {
+            let mut buffer = Vec::new();
+            TextEncoder::new()
+                .encode(&metric_families, &mut buffer)
+                .expect("Failed to encode metrics");
+            (buffer, PROMETHEUS_TEXT_CONTENT_TYPE)
+        };
+
+        // Build and send HTTP response
+        let response = Response::from_data(buffer).with_header(
+            Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).unwrap(),
+        );
+        let _ = request.respond(response);
+    }
+
+    /// Returns true if an `Accept` header ranks OpenMetrics at least as high as the
+    /// Prometheus text format.
+    pub(crate) fn prefers_openmetrics(accept: &str) -> bool {
+        let mut openmetrics_q = 0.0_f32;
+        let mut text_q = 0.0_f32;
+        for media_range in accept.split(',') {
+            let mut params = media_range.split(';').map(str::trim);
+            let media_type = params.next().unwrap_or_default();
+            let q = params
+                .find_map(|p| p.strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            match media_type {
+                "application/openmetrics-text" => openmetrics_q = openmetrics_q.max(q),
+                "text/plain" => text_q = text_q.max(q),
+                _ => {}
+            }
+        }
+        openmetrics_q > 0.0 && openmetrics_q >= text_q
+    }
+
+    /// Encodes metric families in the OpenMetrics text format, terminated by `# EOF`.
+    ///
+    /// OpenMetrics requires counter samples to carry a `_total` suffix that is not part of
+    /// the family name. Counters already named `*_total` are exposed that way; the others
+    /// are exposed as `unknown` so their series names match the Prometheus text format.
+    pub(crate) fn encode_openmetrics(metric_families: &[MetricFamily]) -> String {
+        let mut out = String::new();
+        for mf in metric_families {
+            let name = mf.name();
+            let (family, metric_type) = match mf.get_field_type() {
+                MetricType::COUNTER => match name.strip_suffix("_total") {
+                    Some(family) => (family, "counter"),
+                    None => (name, "unknown"),
+                },
+                MetricType::GAUGE => (name, "gauge"),
+                MetricType::HISTOGRAM => (name, "histogram"),
+                MetricType::SUMMARY => (name, "summary"),
+                MetricType::UNTYPED => (name, "unknown"),
             };
-            let server = server_r.unwrap();
-
-            gst::info!(CAT, "Prometheus metrics server listening on {}", port);
-
-            for request in server.incoming_requests() {
-                // Gather and encode all registered metrics
-                let metric_families = gather();
-                let mut buffer = Vec::new();
-                TextEncoder::new()
-                    .encode(&metric_families, &mut buffer)
-                    .expect("Failed to encode metrics");
-
-                // Build and send HTTP response
-                let response = Response::from_data(buffer).with_header(
-                    Header::from_bytes(&b"Content-Type"[..], &b"text/plain; charset=utf-8"[..])
-                        .unwrap(),
-                );
-                let _ = request.respond(response);
+            if !mf.help().is_empty() {
+                out.push_str(&format!(
+                    "# HELP {family} {}\n",
+                    Self::escape_openmetrics(mf.help())
+                ));
             }
-        });
+            out.push_str(&format!("# TYPE {family} {metric_type}\n"));
+
+            for m in mf.get_metric() {
+                match mf.get_field_type() {
+                    MetricType::COUNTER => {
+                        Self::write_openmetrics_sample(
+                            &mut out,
+                            name,
+                            m,
+                            None,
+                            m.get_counter().value(),
+                        );
+                    }
+                    MetricType::GAUGE => {
+                        Self::write_openmetrics_sample(
+                            &mut out,
+                            name,
+                            m,
+                            None,
+                            m.get_gauge().value(),
+                        );
+                    }
+                    MetricType::HISTOGRAM => {
+                        let h = m.get_histogram();
+                        let mut inf_seen = false;
+                        for b in h.get_bucket() {
+                            let upper_bound = b.upper_bound();
+                            inf_seen |= upper_bound == f64::INFINITY;
+                            Self::write_openmetrics_sample(
+                                &mut out,
+                                &format!("{name}_bucket"),
+                                m,
+                                Some(("le", Self::format_openmetrics_value(upper_bound))),
+                                b.cumulative_count() as f64,
+                            );
+                        }
+                        if !inf_seen {
+                            Self::write_openmetrics_sample(
+                                &mut out,
+                                &format!("{name}_bucket"),
+                                m,
+                                Some(("le", "+Inf".to_string())),
+                                h.sample_count() as f64,
+                            );
+                        }
+                        Self::write_openmetrics_sample(
+                            &mut out,
+                            &format!("{name}_sum"),
+                            m,
+                            None,
+                            h.sample_sum(),
+                        );
+                        Self::write_openmetrics_sample(
+                            &mut out,
+                            &format!("{name}_count"),
+                            m,
+                            None,
+                            h.sample_count() as f64,
+                        );
+                    }
+                    MetricType::SUMMARY => {
+                        let summary = m.get_summary();
+                        for q in summary.get_quantile() {
+                            Self::write_openmetrics_sample(
+                                &mut out,
+                                name,
+                                m,
+                                Some(("quantile", Self::format_openmetrics_value(q.quantile()))),
+                                q.value(),
+                            );
+                        }
+                        Self::write_openmetrics_sample(
+                            &mut out,
+                            &format!("{name}_sum"),
+                            m,
+                            None,
+                            summary.sample_sum(),
+                        );
+                        Self::write_openmetrics_sample(
+                            &mut out,
+                            &format!("{name}_count"),
+                            m,
+                            None,
+                            summary.sample_count() as f64,
+                        );
+                    }
+                    MetricType::UNTYPED => {}
+                }
+            }
+        }
+        out.push_str("# EOF\n");
+        out
+    }
+
+    fn write_openmetrics_sample(
+        out: &mut String,
+        name: &str,
+        m: &Metric,
+        extra_label: Option<(&str, String)>,
+        value: f64,
+    ) {
+        let labels = m
+            .get_label()
+            .iter()
+            .map(|lp| (lp.name(), lp.value().to_string()))
+            .chain(extra_label)
+            .map(|(k, v)| format!("{k}=\"{}\"", Self::escape_openmetrics(&v)))
+            .collect::<Vec<_>>();
+        out.push_str(name);
+        if !labels.is_empty() {
+            out.push('{');
+            out.push_str(&labels.join(","));
+            out.push('}');
+        }
+        out.push(' ');
+        out.push_str(&Self::format_openmetrics_value(value));
+        out.push('\n');
+    }
+
+    fn format_openmetrics_value(value: f64) -> String {
+        if value == f64::INFINITY {
+            "+Inf".to_string()
+        } else if value == f64::NEG_INFINITY {
+            "-Inf".to_string()
+        } else {
+            value.to_string()
+        }
+    }
+
+    fn escape_openmetrics(s: &str) -> String {
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
     }
 
     pub(crate) fn compute_element_latency(span_diff: u64, ts_latency: u64) -> u64 {
@@ -594,11 +2814,370 @@ impl PromLatencyTracerImp {
 
 #[cfg(test)]
 mod tests {
-    use super::PromLatencyTracerImp;
+    use super::{
+        LatencyUnit, MetricFamily, Metrics, MetricsServer, PromLatencyTracerImp, PushFrames,
+        PushSpan,
+    };
+    use std::sync::{atomic::Ordering, Arc, Mutex};
 
     #[test]
     fn compute_element_latency_subtracts_and_saturates() {
         assert_eq!(PromLatencyTracerImp::compute_element_latency(100, 30), 70);
         assert_eq!(PromLatencyTracerImp::compute_element_latency(30, 50), 0);
     }
+
+    #[test]
+    fn push_frames_subtract_every_nested_push() {
+        let mut frames = PushFrames::default();
+        // An element pushing two buffers downstream for a single input buffer.
+        frames.enter(1, 0);
+        frames.enter(1, 10);
+        frames.enter(1, 15);
+        assert_eq!(
+            frames.exit(1, 20),
+            Some(PushSpan {
+                total: 5,
+                downstream: 0
+            })
+        );
+        assert_eq!(
+            frames.exit(1, 30),
+            Some(PushSpan {
+                total: 20,
+                downstream: 5
+            })
+        );
+        frames.enter(1, 40);
+        assert_eq!(
+            frames.exit(1, 70),
+            Some(PushSpan {
+                total: 30,
+                downstream: 0
+            })
+        );
+        assert_eq!(
+            frames.exit(1, 100),
+            Some(PushSpan {
+                total: 100,
+                downstream: 50
+            })
+        );
+        assert_eq!(frames.exit(1, 110), None);
+    }
+
+    #[test]
+    fn push_frames_are_kept_per_tracer() {
+        let mut frames = PushFrames::default();
+        // Hooks of two tracers interleave for the same push.
+        frames.enter(1, 0);
+        frames.enter(2, 1);
+        frames.enter(1, 10);
+        frames.enter(2, 11);
+        assert_eq!(frames.exit(1, 20).map(|s| s.total), Some(10));
+        assert_eq!(frames.exit(2, 21).map(|s| s.total), Some(10));
+        assert_eq!(
+            frames.exit(1, 50),
+            Some(PushSpan {
+                total: 50,
+                downstream: 10
+            })
+        );
+        assert_eq!(
+            frames.exit(2, 51),
+            Some(PushSpan {
+                total: 50,
+                downstream: 10
+            })
+        );
+    }
+
+    #[test]
+    fn latency_unit_converts_with_rounding() {
+        assert_eq!(LatencyUnit::Nanoseconds.convert(1_499), 1_499);
+        assert_eq!(LatencyUnit::Microseconds.convert(1_499), 1);
+        assert_eq!(LatencyUnit::Microseconds.convert(1_500), 2);
+        assert_eq!(LatencyUnit::Milliseconds.convert(2_500_000), 3);
+        assert_eq!(
+            LatencyUnit::Milliseconds.convert(u64::MAX),
+            u64::MAX / 1_000_000
+        );
+        assert_eq!(
+            LatencyUnit::from_param("us"),
+            Some(LatencyUnit::Microseconds)
+        );
+        assert_eq!(LatencyUnit::from_param("s"), None);
+    }
+
+    #[test]
+    fn metrics_are_registered_per_tracer() {
+        let first = Metrics::new(LatencyUnit::Nanoseconds, None, None);
+        let second = Metrics::new(LatencyUnit::Milliseconds, None, None);
+        let labels = ["el", "identity", "src", "sink", "pipeline0", "unknown"].map(String::from);
+
+        first.with_label_values(&labels).count_counter.inc();
+
+        assert_eq!(first.latency_count.with_label_values(&labels).get(), 1);
+        assert_eq!(second.latency_count.with_label_values(&labels).get(), 0);
+        assert!(prometheus::gather()
+            .iter()
+            .all(|mf| !mf.name().starts_with("gst_element_")));
+    }
+
+    #[test]
+    fn metrics_server_reports_series_count_and_previous_scrape() {
+        let metrics = Arc::new(Metrics::new(LatencyUnit::Nanoseconds, None, None));
+        let server = MetricsServer {
+            sources: Mutex::new(vec![Arc::downgrade(&metrics)]),
+            ..Default::default()
+        };
+        let labels = ["el", "identity", "src", "sink", "pipeline0", "unknown"].map(String::from);
+        metrics.with_label_values(&labels).count_counter.inc();
+        let family = |families: &[MetricFamily], name: &str| {
+            families.iter().find(|f| f.name() == name).cloned()
+        };
+
+        let first = server.scrape();
+        let series = first
+            .iter()
+            .filter(|f| f.name() != "gst_tracer_series_total")
+            .map(|f| f.get_metric().len())
+            .sum::<usize>();
+        let series_total = family(&first, "gst_tracer_series_total").unwrap();
+        assert_eq!(
+            series_total.get_metric()[0].get_gauge().value(),
+            series as f64
+        );
+        assert!(family(&first, "gst_tracer_last_scrape_timestamp_seconds").is_none());
+
+        let second = server.scrape();
+        let last_scrape = family(&second, "gst_tracer_last_scrape_timestamp_seconds").unwrap();
+        assert!(last_scrape.get_metric()[0].get_gauge().value() > 0.0);
+    }
+
+    #[test]
+    fn only_scrapes_start_a_new_min_max_window() {
+        let metrics = Arc::new(Metrics::new(LatencyUnit::Nanoseconds, None, None));
+        let server = MetricsServer {
+            sources: Mutex::new(vec![Arc::downgrade(&metrics)]),
+            ..Default::default()
+        };
+        let window = || metrics.min_max_window.load(Ordering::Relaxed);
+
+        server.scrape();
+        let opened = window();
+        // Pushes, the `metrics` signal and `metric_families` read without resetting
+        metrics.gather();
+        assert_eq!(window(), opened);
+        server.scrape();
+        assert_eq!(window(), opened + 1);
+    }
+
+    #[test]
+    fn label_property_adds_a_pad_pair_label() {
+        let metrics = Metrics::new(LatencyUnit::Nanoseconds, Some("stream-id"), None);
+        assert_eq!(metrics.pad_pair_labels.last().unwrap(), "stream_id");
+        let labels = [
+            "el",
+            "identity",
+            "src",
+            "sink",
+            "pipeline0",
+            "unknown",
+            "cam-1",
+        ]
+        .map(String::from);
+        metrics.with_label_values(&labels).count_counter.inc();
+        assert_eq!(metrics.latency_count.with_label_values(&labels).get(), 1);
+    }
+
+    #[test]
+    fn series_are_removed_once_every_pad_pair_released_them() {
+        let metrics = Metrics::new(LatencyUnit::Nanoseconds, None, None);
+        let mut labels =
+            ["el", "identity", "src", "sink", "pipeline0", "unknown"].map(String::from);
+        let has_series = |metrics: &Metrics| {
+            metrics
+                .registry
+                .gather()
+                .iter()
+                .any(|mf| mf.name() == "gst_element_latency_count_count")
+        };
+
+        metrics.acquire_series(&mut labels);
+        metrics.acquire_series(&mut labels);
+        metrics.release_series(&labels);
+        assert!(has_series(&metrics));
+        metrics.release_series(&labels);
+        assert!(!has_series(&metrics));
+        // Releasing again must not panic or underflow
+        metrics.release_series(&labels);
+        assert!(metrics.series_refs.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn pad_pairs_beyond_max_series_record_into_the_overflow_series() {
+        let metrics = Metrics::new(LatencyUnit::Nanoseconds, None, None);
+        metrics.max_series.store(1, Ordering::Relaxed);
+        let pad_pair = |element: &str| {
+            [element, "identity", "src", "sink", "pipeline0", "unknown"].map(String::from)
+        };
+
+        let mut first = pad_pair("first");
+        metrics.acquire_series(&mut first);
+        // Pad pairs sharing existing labels are not capped
+        metrics.acquire_series(&mut first);
+        assert_eq!(first, pad_pair("first"));
+
+        let (mut second, mut third) = (pad_pair("second"), pad_pair("third"));
+        metrics.acquire_series(&mut second).count_counter.inc();
+        metrics.acquire_series(&mut third).count_counter.inc();
+        assert_eq!(second, ["__overflow__", "", "", "", "", ""]);
+        assert_eq!(third, second);
+        assert_eq!(metrics.latency_count.with_label_values(&second).get(), 2);
+
+        metrics.release_series(&second);
+        metrics.release_series(&third);
+        assert_eq!(metrics.series_refs.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn seconds_rescales_times_and_renames_their_families() {
+        let metrics = Metrics::new(LatencyUnit::Microseconds, None, None);
+        metrics.seconds.store(true, Ordering::Relaxed);
+        let labels = ["el", "identity", "src", "sink", "pipeline0", "unknown"].map(String::from);
+        let pad_metrics = metrics.with_label_values(&labels);
+        pad_metrics.last_gauge.set(1_500);
+        pad_metrics.sum_counter.inc_by(2_000_000);
+        pad_metrics.count_counter.inc_by(3);
+
+        let families = metrics.gather();
+        let value = |name: &str| {
+            families
+                .iter()
+                .find(|mf| mf.name() == name)
+                .map(|mf| &mf.get_metric()[0])
+                .unwrap_or_else(|| panic!("Expected a {name} family"))
+        };
+        assert_eq!(
+            value("gst_element_latency_last_seconds")
+                .get_gauge()
+                .value(),
+            0.0015
+        );
+        assert_eq!(
+            value("gst_element_latency_seconds_total")
+                .get_counter()
+                .value(),
+            2.0
+        );
+        // Counts are not times, so they are left as they are
+        assert_eq!(
+            value("gst_element_latency_count_count")
+                .get_counter()
+                .value(),
+            3.0
+        );
+        let help = families
+            .iter()
+            .find(|mf| mf.name() == "gst_element_latency_last_seconds")
+            .unwrap()
+            .help();
+        assert!(!help.contains("microseconds"), "{help}");
+    }
+
+    #[test]
+    fn build_info_is_set_on_creation() {
+        let metrics = Metrics::new(LatencyUnit::Nanoseconds, None, None);
+        let build_info = metrics
+            .gather()
+            .into_iter()
+            .find(|mf| mf.name() == "gst_tracer_build_info")
+            .expect("Expected a build info family");
+        let m = &build_info.get_metric()[0];
+        assert_eq!(m.get_gauge().value(), 1.0);
+        assert!(m
+            .get_label()
+            .iter()
+            .any(|lp| lp.name() == "version" && lp.value() == env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    fn encode_json_emits_a_sample_per_series() {
+        let metrics = Metrics::new(LatencyUnit::Nanoseconds, None, None);
+        let labels = ["el", "identity", "src", "sink", "pipeline0", "unknown"].map(String::from);
+        metrics.with_label_values(&labels).bytes_counter.inc_by(42);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&PromLatencyTracerImp::encode_json(&metrics.gather())).unwrap();
+        let bytes = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|sample| sample["name"] == "gst_element_bytes_total")
+            .expect("Expected a bytes sample");
+        assert_eq!(bytes["labels"]["element"], "el");
+        assert_eq!(bytes["value"], 42.0);
+    }
+
+    #[test]
+    fn summary_is_exposed_and_removed_with_its_pad_pair() {
+        let metrics = Metrics::new(LatencyUnit::Nanoseconds, None, Some(vec![0.5, 0.99]));
+        let mut labels =
+            ["el", "identity", "src", "sink", "pipeline0", "unknown"].map(String::from);
+        let pad_metrics = metrics.acquire_series(&mut labels);
+        for latency in [10, 20, 30] {
+            pad_metrics
+                .summary
+                .as_ref()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .observe(latency);
+        }
+
+        let openmetrics = PromLatencyTracerImp::encode_openmetrics(&metrics.gather());
+        assert!(openmetrics.contains("# TYPE gst_element_latency_summary summary"));
+        assert!(openmetrics.contains("quantile=\"0.5\"} 20"));
+        assert!(openmetrics.contains("gst_element_latency_summary_count{element=\"el\""));
+
+        metrics.release_series(&labels);
+        assert!(!PromLatencyTracerImp::encode_openmetrics(&metrics.gather())
+            .contains("gst_element_latency_summary_count"));
+    }
+
+    #[test]
+    fn percentiles_come_from_the_summary_windows() {
+        let without_summary = Metrics::new(LatencyUnit::Nanoseconds, None, None);
+        assert_eq!(without_summary.percentiles(), "");
+
+        let metrics = Metrics::new(LatencyUnit::Nanoseconds, None, Some(vec![0.5]));
+        let mut labels =
+            ["el", "identity", "src", "sink", "pipeline0", "unknown"].map(String::from);
+        let pad_metrics = metrics.acquire_series(&mut labels);
+        let mut idle =
+            ["idle", "identity", "src", "sink", "pipeline0", "unknown"].map(String::from);
+        metrics.acquire_series(&mut idle);
+        for latency in 1..=100 {
+            pad_metrics
+                .summary
+                .as_ref()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .observe(latency);
+        }
+        assert_eq!(metrics.percentiles(), "el.src p50=50 p95=95 p99=99");
+    }
+
+    #[test]
+    fn prefers_openmetrics_follows_accept_quality() {
+        assert!(PromLatencyTracerImp::prefers_openmetrics(
+            "application/openmetrics-text;version=1.0.0,text/plain;version=0.0.4;q=0.5,*/*;q=0.1"
+        ));
+        assert!(!PromLatencyTracerImp::prefers_openmetrics(
+            "application/openmetrics-text;q=0.3,text/plain"
+        ));
+        assert!(!PromLatencyTracerImp::prefers_openmetrics("text/plain"));
+        assert!(!PromLatencyTracerImp::prefers_openmetrics("*/*"));
+    }
 }
@@ -0,0 +1,123 @@
+use std::{io, net::UdpSocket};
+
+/// Where latencies are reported to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsSink {
+    /// Served over HTTP, pushed to a Pushgateway or pulled via the `metrics` signal.
+    #[default]
+    Prometheus,
+    /// Sent as StatsD timings over UDP, without starting the HTTP server.
+    Statsd,
+    /// Appended to a file as JSON lines, without starting the HTTP server.
+    File,
+}
+
+impl MetricsSink {
+    /// Parses the `sink` tracer parameter: `prometheus`, `statsd` or `file`.
+    pub fn from_param(sink: &str) -> Option<Self> {
+        match sink {
+            "prometheus" => Some(Self::Prometheus),
+            "statsd" => Some(Self::Statsd),
+            "file" => Some(Self::File),
+            _ => None,
+        }
+    }
+
+    /// The `sink` tracer parameter value for this sink.
+    pub fn param(self) -> &'static str {
+        match self {
+            Self::Prometheus => "prometheus",
+            Self::Statsd => "statsd",
+            Self::File => "file",
+        }
+    }
+}
+
+/// Sends per pad pair latencies to a StatsD daemon as DogStatsD timings with tags.
+#[derive(Debug)]
+pub(crate) struct StatsdSink {
+    socket: UdpSocket,
+}
+
+impl StatsdSink {
+    pub(crate) const DEFAULT_ADDRESS: &'static str = "127.0.0.1:8125";
+
+    /// Binds an ephemeral UDP socket sending to `address`, e.g. `statsd:8125`.
+    pub(crate) fn connect(address: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(address)?;
+        Ok(Self { socket })
+    }
+
+    /// Formats the tags of a pad pair once; they only change when its media type is resolved.
+    pub(crate) fn tags(names: &[impl AsRef<str>], values: &[String]) -> String {
+        names
+            .iter()
+            .zip(values)
+            .map(|(name, value)| format!("{}:{}", name.as_ref(), Self::escape_tag_value(value)))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// `,`, `|` and `#` delimit tags and fields in the DogStatsD format.
+    fn escape_tag_value(value: &str) -> String {
+        value.replace([',', '|', '#'], "_")
+    }
+
+    pub(crate) fn format_latency(latency_ns: u64, tags: &str) -> String {
+        format!(
+            "gst.element.latency:{}|ms|#{tags}",
+            latency_ns as f64 / 1_000_000.0
+        )
+    }
+
+    /// Sends a latency as a timing in milliseconds. StatsD is lossy by design, so a failed send
+    /// is dropped rather than reported on the streaming thread.
+    pub(crate) fn send_latency(&self, latency_ns: u64, tags: &str) {
+        let _ = self
+            .socket
+            .send(Self::format_latency(latency_ns, tags).as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MetricsSink, StatsdSink};
+
+    #[test]
+    fn tags_are_formatted_and_escaped() {
+        let values =
+            ["q|1", "queue", "src", "sink", "/pipeline0", "video/x-raw,#"].map(String::from);
+        let tags = StatsdSink::tags(
+            &[
+                "element",
+                "element_factory",
+                "src_pad",
+                "sink_pad",
+                "path",
+                "media_type",
+            ],
+            &values,
+        );
+        assert_eq!(
+            tags,
+            "element:q_1,element_factory:queue,src_pad:src,sink_pad:sink,path:/pipeline0,media_type:video/x-raw__"
+        );
+        assert_eq!(
+            StatsdSink::format_latency(1_500_000, "element:q"),
+            "gst.element.latency:1.5|ms|#element:q"
+        );
+    }
+
+    #[test]
+    fn sink_param_round_trips() {
+        for sink in [
+            MetricsSink::Prometheus,
+            MetricsSink::Statsd,
+            MetricsSink::File,
+        ] {
+            assert_eq!(MetricsSink::from_param(sink.param()), Some(sink));
+        }
+        assert_eq!(MetricsSink::from_param("graphite"), None);
+    }
+}
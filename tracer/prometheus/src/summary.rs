@@ -0,0 +1,228 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use prometheus::{
+    core::{Collector, Desc},
+    proto::{LabelPair, Metric, MetricFamily, MetricType, Quantile, Summary},
+};
+
+/// Latencies kept per pad pair to compute quantiles from.
+const SUMMARY_WINDOW: usize = 1024;
+
+/// Latency windows of every pad pair, by label values.
+type SeriesMap = HashMap<Vec<String>, Arc<Mutex<SummarySeries>>>;
+
+/// Latency quantiles computed client-side per pad pair, exposed as a Prometheus summary.
+///
+/// Quantiles cover the last [`SUMMARY_WINDOW`] latencies of each pad pair, while the sum and
+/// count cover every latency recorded. Clones share their series, so one can be registered while
+/// another records.
+#[derive(Clone)]
+pub(crate) struct LatencySummary {
+    desc: Desc,
+    quantiles: Vec<f64>,
+    series: Arc<Mutex<SeriesMap>>,
+}
+
+/// Latencies recorded for a single pad pair.
+#[derive(Debug, Default)]
+pub(crate) struct SummarySeries {
+    window: VecDeque<u64>,
+    sum: u64,
+    count: u64,
+}
+
+impl SummarySeries {
+    pub(crate) fn observe(&mut self, value: u64) {
+        if self.window.len() == SUMMARY_WINDOW {
+            self.window.pop_front();
+        }
+        self.window.push_back(value);
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Nearest-rank quantiles of the window, `NaN` while it is empty.
+    fn quantiles(&self, quantiles: &[f64]) -> Vec<f64> {
+        let mut sorted = self.window.iter().copied().collect::<Vec<_>>();
+        sorted.sort_unstable();
+        quantiles
+            .iter()
+            .map(|q| {
+                let rank = (q * sorted.len() as f64).ceil() as usize;
+                sorted
+                    .get(rank.saturating_sub(1))
+                    .map_or(f64::NAN, |v| *v as f64)
+            })
+            .collect()
+    }
+}
+
+impl LatencySummary {
+    pub(crate) const DEFAULT_QUANTILES: [f64; 3] = [0.5, 0.9, 0.99];
+
+    pub(crate) fn new(
+        name: &str,
+        help: String,
+        label_names: &[&str],
+        quantiles: Vec<f64>,
+    ) -> prometheus::Result<Self> {
+        let desc = Desc::new(
+            name.to_string(),
+            help,
+            label_names.iter().map(|label| label.to_string()).collect(),
+            HashMap::new(),
+        )?;
+        Ok(Self {
+            desc,
+            quantiles,
+            series: Arc::default(),
+        })
+    }
+
+    /// Parses `summary-quantiles`, e.g. `0.5,0.9,0.99`, dropping values outside `[0, 1]`.
+    pub(crate) fn parse_quantiles(quantiles: &str) -> Vec<f64> {
+        quantiles
+            .split(',')
+            .filter_map(|q| q.trim().parse::<f64>().ok())
+            .filter(|q| (0.0..=1.0).contains(q))
+            .collect()
+    }
+
+    pub(crate) fn with_label_values(&self, labels: &[String]) -> Arc<Mutex<SummarySeries>> {
+        self.series
+            .lock()
+            .unwrap()
+            .entry(labels.to_vec())
+            .or_default()
+            .clone()
+    }
+
+    /// Quantiles of the window of every pad pair, sorted by label values.
+    pub(crate) fn quantiles_by_series(&self, quantiles: &[f64]) -> Vec<(Vec<String>, Vec<f64>)> {
+        let mut series = self
+            .series
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(labels, series)| (labels.clone(), series.lock().unwrap().quantiles(quantiles)))
+            .collect::<Vec<_>>();
+        series.sort_by(|a, b| a.0.cmp(&b.0));
+        series
+    }
+
+    pub(crate) fn remove_label_values(&self, labels: &[String]) {
+        self.series.lock().unwrap().remove(labels);
+    }
+}
+
+impl Collector for LatencySummary {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![&self.desc]
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let metrics = self
+            .series
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(labels, series)| {
+                let series = series.lock().unwrap();
+                let mut summary = Summary::default();
+                summary.set_sample_count(series.count);
+                summary.set_sample_sum(series.sum as f64);
+                summary.set_quantile(
+                    self.quantiles
+                        .iter()
+                        .zip(series.quantiles(&self.quantiles))
+                        .map(|(q, value)| {
+                            let mut quantile = Quantile::default();
+                            quantile.set_quantile(*q);
+                            quantile.set_value(value);
+                            quantile
+                        })
+                        .collect(),
+                );
+                let mut metric = Metric::default();
+                metric.set_label(
+                    self.desc
+                        .variable_labels
+                        .iter()
+                        .zip(labels)
+                        .map(|(name, value)| {
+                            let mut label = LabelPair::default();
+                            label.set_name(name.clone());
+                            label.set_value(value.clone());
+                            label
+                        })
+                        .collect(),
+                );
+                metric.set_summary(summary);
+                metric
+            })
+            .collect();
+
+        let mut family = MetricFamily::default();
+        family.set_name(self.desc.fq_name.clone());
+        family.set_help(self.desc.help.clone());
+        family.set_field_type(MetricType::SUMMARY);
+        family.set_metric(metrics);
+        vec![family]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LatencySummary, SummarySeries, SUMMARY_WINDOW};
+    use prometheus::core::Collector;
+
+    #[test]
+    fn quantiles_use_nearest_rank_over_the_window() {
+        let mut series = SummarySeries::default();
+        assert!(series.quantiles(&[0.5])[0].is_nan());
+        for value in 1..=100 {
+            series.observe(value);
+        }
+        assert_eq!(
+            series.quantiles(&[0.5, 0.9, 0.99, 1.0]),
+            [50.0, 90.0, 99.0, 100.0]
+        );
+
+        // Only the last SUMMARY_WINDOW values count towards quantiles, all towards sum and count
+        for _ in 0..SUMMARY_WINDOW {
+            series.observe(1000);
+        }
+        assert_eq!(series.quantiles(&[0.5]), [1000.0]);
+        assert_eq!(series.count, 100 + SUMMARY_WINDOW as u64);
+    }
+
+    #[test]
+    fn summary_collects_one_metric_per_pad_pair() {
+        let summary = LatencySummary::new(
+            "gst_element_latency_summary",
+            "latency".into(),
+            &["element"],
+            LatencySummary::parse_quantiles("0.5, 0.99,2"),
+        )
+        .unwrap();
+        summary
+            .with_label_values(&["a".into()])
+            .lock()
+            .unwrap()
+            .observe(10);
+        summary.with_label_values(&["b".into()]);
+        summary.remove_label_values(&["b".into()]);
+
+        let families = summary.collect();
+        let metrics = families[0].get_metric();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].get_label()[0].value(), "a");
+        let quantiles = metrics[0].get_summary().get_quantile();
+        assert_eq!(quantiles.len(), 2);
+        assert_eq!(quantiles[1].quantile(), 0.99);
+        assert_eq!(quantiles[1].value(), 10.0);
+    }
+}
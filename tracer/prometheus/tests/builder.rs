@@ -0,0 +1,198 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gst::prelude::*;
+    use gstprometheustracer::{
+        Filter, LatencyScope, LatencyUnit, MetricsSink, PromLatencySettings, PromLatencyTracer,
+        TracedTypes,
+    };
+    use gstreamer::{self as gst};
+    use std::{env, net::TcpStream, str::FromStr};
+
+    const PROM_PORT: u16 = 19093;
+
+    #[test]
+    fn given_builder_params_when_built_then_params_string_matches() {
+        let params = PromLatencyTracer::builder()
+            .port(9000)
+            .port_retries(4)
+            .http_server(false)
+            .pushgateway_url("http://localhost:9091")
+            .otlp_endpoint("http://localhost:4318")
+            .push_interval_ms(100)
+            .unit(LatencyUnit::Milliseconds)
+            .seconds(true)
+            .cpu_time(true)
+            .summary(true)
+            .summary_quantiles([0.5, 0.99])
+            .max_series(500)
+            .filter("video*,!*sink*")
+            .pad_filter("mux.video_*")
+            .scope(LatencyScope::Pipeline)
+            .pipeline_latency(true)
+            .filters("GstBuffer,GstEvent")
+            .params();
+        let s = gst::Structure::from_str(&format!("prom-latency,{params}"))
+            .expect("Expected the params to parse as a structure");
+        assert_eq!(s.get::<i32>("port"), Ok(9000));
+        assert_eq!(s.get::<i32>("port-retries"), Ok(4));
+        assert_eq!(s.get::<bool>("http-server"), Ok(false));
+        assert_eq!(
+            s.get::<String>("pushgateway-url").as_deref(),
+            Ok("http://localhost:9091")
+        );
+        assert_eq!(
+            s.get::<String>("otlp-endpoint").as_deref(),
+            Ok("http://localhost:4318")
+        );
+        assert_eq!(s.get::<i32>("push-interval-ms"), Ok(100));
+        assert_eq!(s.get::<String>("unit").as_deref(), Ok("ms"));
+        assert_eq!(s.get::<bool>("seconds"), Ok(true));
+        assert_eq!(s.get::<bool>("cpu-time"), Ok(true));
+        assert_eq!(s.get::<bool>("summary"), Ok(true));
+        assert_eq!(
+            s.get::<String>("summary-quantiles").as_deref(),
+            Ok("0.5,0.99")
+        );
+        assert_eq!(s.get::<i32>("max-series"), Ok(500));
+        assert_eq!(s.get::<String>("filter").as_deref(), Ok("video*,!*sink*"));
+        assert_eq!(s.get::<String>("pad-filter").as_deref(), Ok("mux.video_*"));
+        assert_eq!(s.get::<String>("scope").as_deref(), Ok("pipeline"));
+        assert_eq!(s.get::<bool>("pipeline-latency"), Ok(true));
+        assert_eq!(
+            s.get::<String>("filters").as_deref(),
+            Ok("GstBuffer,GstEvent")
+        );
+        assert_eq!(s.n_fields(), 17);
+        assert_eq!(PromLatencyTracer::builder().params(), "");
+    }
+
+    #[test]
+    fn given_builder_strings_with_separators_when_built_then_settings_round_trip() {
+        gst::init().expect("Failed to initialize GStreamer");
+
+        // Quotes, commas and `=` would end or split a value pasted into the params unescaped
+        let path = "/tmp/prom \"latency\",sink=statsd.jsonl";
+        let tracer = PromLatencyTracer::builder()
+            .http_server(false)
+            .filters("GstEvent")
+            .file_path(path)
+            .label_from_property("camera,id")
+            .filter("cam*,!*\"sink\"*")
+            .build();
+        let settings = tracer.settings();
+        assert_eq!(settings.file_path, path);
+        assert_eq!(settings.sink, MetricsSink::Prometheus);
+        assert_eq!(settings.label_from_property.as_deref(), Some("camera,id"));
+        assert_eq!(settings.filter, Filter::new("cam*,!*\"sink\"*"));
+    }
+
+    #[test]
+    fn given_tracer_built_without_gst_tracers_when_pipeline_runs_then_latency_recorded() {
+        // The tracer comes from this crate rather than GST_TRACERS or the plugin path.
+        env::remove_var("GST_TRACERS");
+        gst::init().expect("Failed to initialize GStreamer");
+
+        let tracer = PromLatencyTracer::builder()
+            .port(PROM_PORT)
+            .http_server(false)
+            .build();
+
+        let pipeline =
+            gst::parse::launch("fakesrc num-buffers=100 ! identity name=built ! fakesink")
+                .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        assert!(
+            TcpStream::connect(("127.0.0.1", PROM_PORT)).is_err(),
+            "Expected no metrics server to be listening"
+        );
+        let metrics = tracer
+            .emit_by_name::<Option<String>>("metrics", &[])
+            .expect("Expected to get metrics from signal");
+        assert!(metrics.contains("gst_element_latency_count_count{element=\"built\""));
+
+        let families = tracer.metric_families();
+        let count = families
+            .iter()
+            .find(|mf| mf.name() == "gst_element_latency_count_count")
+            .expect("Expected the latency count family");
+        assert!(count.get_metric().iter().any(|m| m
+            .get_label()
+            .iter()
+            .any(|lp| lp.name() == "element" && lp.value() == "built")));
+    }
+
+    #[test]
+    fn given_params_string_when_tracer_created_then_settings_parsed() {
+        gst::init().expect("Failed to initialize GStreamer");
+
+        let tracer = gst::glib::Object::builder::<PromLatencyTracer>()
+            .property(
+                "params",
+                "port=9100,http-server=false,unit=us,summary=true,summary-quantiles=\"0.5,2\",filter=\"enc*\",max-series=-3",
+            )
+            .build();
+        let settings = tracer.settings();
+        assert_eq!(settings.server_port, 9100);
+        assert!(!settings.http_server);
+        assert_eq!(settings.unit, LatencyUnit::Microseconds);
+        assert!(settings.summary);
+        // Out of range quantiles and negative limits are dropped or clamped
+        assert_eq!(settings.summary_quantiles, [0.5]);
+        assert_eq!(settings.max_series, 0);
+        assert_eq!(settings.filter, Filter::new("enc*"));
+        assert_eq!(settings.scope, LatencyScope::Element);
+        assert_eq!(settings.filters, TracedTypes::default());
+        // Options missing from the params keep their defaults
+        assert_eq!(
+            settings.push_interval_ms,
+            PromLatencySettings::default().push_interval_ms
+        );
+    }
+
+    #[test]
+    fn given_latency_tracer_flags_and_filters_when_tracer_created_then_settings_parsed() {
+        gst::init().expect("Failed to initialize GStreamer");
+
+        let tracer = gst::glib::Object::builder::<PromLatencyTracer>()
+            .property(
+                "params",
+                "http-server=false,flags=pipeline,filters=\"GstBuffer,GstEvent\"",
+            )
+            .build();
+        let settings = tracer.settings();
+        assert_eq!(settings.scope, LatencyScope::Pipeline);
+        assert!(settings.filters.buffers && settings.filters.events);
+        assert!(!settings.filters.buffer_lists);
+
+        // Element latency is what `flags=pipeline+element` asks for on top
+        let tracer = gst::glib::Object::builder::<PromLatencyTracer>()
+            .property("params", "http-server=false,flags=pipeline+element")
+            .build();
+        assert_eq!(tracer.settings().scope, LatencyScope::Element);
+    }
+
+    #[test]
+    fn given_filters_without_buffers_when_pipeline_runs_then_no_latency_recorded() {
+        env::remove_var("GST_TRACERS");
+        gst::init().expect("Failed to initialize GStreamer");
+
+        let tracer = PromLatencyTracer::builder()
+            .http_server(false)
+            .filters("GstEvent")
+            .build();
+
+        let pipeline =
+            gst::parse::launch("fakesrc num-buffers=100 ! identity name=unhooked ! fakesink")
+                .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        // Without the push hooks this tracer sees no buffer of any pipeline
+        assert!(!tracer.metric_families().iter().any(|mf| mf.name()
+            == "gst_element_latency_count_count"
+            && !mf.get_metric().is_empty()));
+    }
+}
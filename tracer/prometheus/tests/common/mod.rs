@@ -0,0 +1,44 @@
+//! Helpers shared by the integration tests, on top of the fixtures every
+//! tracer's tests use.
+#![allow(dead_code)]
+
+pub use gsttracercommon::test_util::*;
+
+use gst::prelude::*;
+use gstreamer as gst;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// The metrics exposition of the active `prom-latency` tracer.
+pub fn metrics() -> String {
+    tracer("promlatencytracer")
+        .emit_by_name::<Option<String>>("metrics", &[])
+        .expect("Expected to get metrics from signal")
+}
+
+/// Counts the buffers arriving at `sink` stamped by the tracer for end-to-end latency.
+pub fn count_stamped_buffers(sink: &gst::Element) -> Arc<AtomicUsize> {
+    let stamped = Arc::new(AtomicUsize::new(0));
+    let counter = stamped.clone();
+    sink.static_pad("sink")
+        .expect("Expected a sink pad")
+        .add_probe(gst::PadProbeType::BUFFER, move |_, info| {
+            let is_stamped = info.buffer().is_some_and(|buffer| {
+                buffer
+                    .iter_meta::<gst::ReferenceTimestampMeta>()
+                    .any(|meta| {
+                        meta.reference()
+                            .structure(0)
+                            .is_some_and(|s| s.has_name("timestamp/x-gst-prom-latency-source"))
+                    })
+            });
+            if is_stamped {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+            gst::PadProbeReturn::Ok
+        })
+        .expect("Failed to add a buffer probe");
+    stamped
+}
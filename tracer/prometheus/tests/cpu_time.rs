@@ -0,0 +1,34 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gstreamer::{self as gst};
+
+    const PROM_PORT: u16 = 19098;
+
+    #[cfg(feature = "v1_18")]
+    #[test]
+    fn given_cpu_time_enabled_when_pipeline_runs_then_cpu_seconds_recorded() {
+        setup_test();
+
+        let pipeline = gst::parse::launch(
+            "videotestsrc num-buffers=50 ! video/x-raw,width=640,height=480 ! videoconvert name=busy ! video/x-raw,format=I420 ! fakesink",
+        )
+        .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        let metrics = common::metrics();
+        let cpu_seconds =
+            common::metric_value(&metrics, "gst_element_cpu_seconds_total{element=\"busy\"")
+                .unwrap_or_else(|| panic!("Expected CPU seconds for busy in:\n{metrics}"));
+        // Converting 50 VGA frames takes measurable CPU time
+        assert!(cpu_seconds > 0.0, "Expected CPU time, got {cpu_seconds}");
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "prom-latency(port={PROM_PORT},http-server=false,cpu-time=true)"
+        ));
+    }
+}
@@ -0,0 +1,44 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gstprometheustracer::{MetricsSink, PromLatencyTracer};
+    use gstreamer::{self as gst};
+    use std::env;
+
+    #[test]
+    fn given_file_sink_when_pipeline_runs_then_latencies_appended_as_json_lines() {
+        env::remove_var("GST_TRACERS");
+        gst::init().expect("Failed to initialize GStreamer");
+        let path = env::temp_dir().join(format!("prom-latency-test-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let _tracer = PromLatencyTracer::builder()
+            .sink(MetricsSink::File)
+            .file_path(path.to_str().unwrap())
+            .build();
+
+        // Runs for about 1.5s, past the interval after which buffered lines are written out
+        let pipeline = gst::parse::launch(
+            "fakesrc num-buffers=30 ! identity name=filed sleep-time=50000 ! fakesink",
+        )
+        .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        let contents = std::fs::read_to_string(&path).expect("Expected the latency file");
+        let lines = contents
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("Expected JSON"))
+            .filter(|line| line["element"] == "filed")
+            .collect::<Vec<_>>();
+        assert!(
+            !lines.is_empty(),
+            "Expected latencies of filed in:\n{contents}"
+        );
+        assert!(lines
+            .iter()
+            .all(|line| line["latency_ns"].is_u64() && line["ts"].is_u64()));
+        let _ = std::fs::remove_file(&path);
+    }
+}
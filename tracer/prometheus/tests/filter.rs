@@ -0,0 +1,38 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gstreamer::{self as gst};
+
+    const PROM_PORT: u16 = 19101;
+
+    #[test]
+    fn given_filter_when_pipeline_runs_then_only_selected_elements_measured() {
+        setup_test();
+
+        let pipeline = gst::parse::launch(
+            "fakesrc num-buffers=20 ! identity name=kept ! identity name=dropped ! fakesink name=sink",
+        )
+        .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        let metrics = common::metrics();
+        assert!(
+            metrics.contains("element=\"kept\""),
+            "Expected kept to be measured in:\n{metrics}"
+        );
+        for excluded in ["dropped", "sink"] {
+            assert!(
+                !metrics.contains(&format!("element=\"{excluded}\"")),
+                "Expected {excluded} not to be measured in:\n{metrics}"
+            );
+        }
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "prom-latency(port={PROM_PORT},http-server=false,filter=\"k*,!sink\")"
+        ));
+    }
+}
@@ -0,0 +1,39 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gst::prelude::*;
+    use gstreamer::{self as gst};
+    use std::net::TcpStream;
+
+    const PROM_PORT: u16 = 19092;
+
+    #[cfg(feature = "v1_18")]
+    #[test]
+    fn given_http_server_disabled_when_pipeline_runs_then_metrics_only_available_via_signal() {
+        setup_test();
+
+        let pipeline =
+            gst::parse::launch("fakesrc num-buffers=100 ! identity name=nohttp ! fakesink")
+                .expect("Failed to create pipeline from launch string");
+        pipeline
+            .set_state(gst::State::Playing)
+            .expect("Unable to set the pipeline to Playing");
+        common::wait_for_eos(&pipeline);
+
+        assert!(
+            TcpStream::connect(("127.0.0.1", PROM_PORT)).is_err(),
+            "Expected no metrics server to be listening"
+        );
+
+        let metrics = common::metrics();
+        assert!(metrics.contains("gst_element_latency_count_count{element=\"nohttp\""));
+
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!("prom-latency(port={PROM_PORT},http-server=false)"));
+    }
+}
@@ -0,0 +1,44 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gstreamer::{self as gst};
+
+    const PROM_PORT: u16 = 19097;
+
+    #[cfg(feature = "v1_18")]
+    #[test]
+    fn given_label_from_property_when_pipeline_runs_then_source_property_is_a_label() {
+        setup_test();
+
+        let pipeline = gst::parse::launch(
+            "fakesrc num-buffers=42 ! identity name=labelled ! fakesink name=labelled_sink",
+        )
+        .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        let metrics = common::metrics();
+        let count_line = |element: &str| {
+            metrics
+                .lines()
+                .find(|line| {
+                    line.starts_with(&format!(
+                        "gst_element_latency_count_count{{element=\"{element}\""
+                    ))
+                })
+                .unwrap_or_else(|| panic!("Expected a latency count for {element}"))
+                .to_string()
+        };
+        // Pushed into by fakesrc, which has the property
+        assert!(count_line("labelled").contains("num_buffers=\"42\""));
+        // Pushed into by identity, which does not
+        assert!(count_line("labelled_sink").contains("num_buffers=\"\""));
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "prom-latency(port={PROM_PORT},http-server=false,label-from-property=num-buffers)"
+        ));
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gst::prelude::*;
+    use gstreamer::{self as gst};
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+        time::Duration,
+    };
+
+    const COLLECTOR_PORT: u16 = 19110;
+
+    #[test]
+    fn given_otlp_endpoint_when_pipeline_runs_then_metrics_exported_to_collector() {
+        // Stand in for the collector before the tracer starts exporting.
+        let listener = TcpListener::bind(("127.0.0.1", COLLECTOR_PORT))
+            .expect("Failed to bind fake OTLP collector");
+        setup_test();
+
+        let pipeline = gst::parse::launch("fakesrc num-buffers=100 ! identity ! fakesink")
+            .expect("Failed to create pipeline from launch string");
+        pipeline
+            .set_state(gst::State::Playing)
+            .expect("Unable to set the pipeline to Playing");
+
+        let (mut stream, _) = listener
+            .accept()
+            .expect("Expected an export from the tracer");
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        reader
+            .read_line(&mut request_line)
+            .expect("Failed to read export request");
+        let mut content_type = String::new();
+        let mut header = String::new();
+        while reader.read_line(&mut header).is_ok_and(|read| read > 2) {
+            if header.to_ascii_lowercase().starts_with("content-type:") {
+                content_type = header.trim().to_string();
+            }
+            header.clear();
+        }
+        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+
+        pipeline.set_state(gst::State::Null).unwrap();
+        thread::sleep(Duration::from_millis(100));
+
+        assert!(
+            request_line.starts_with("POST /v1/metrics "),
+            "Unexpected export request: {request_line}"
+        );
+        assert!(
+            content_type.ends_with("application/json"),
+            "Unexpected content type: {content_type}"
+        );
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+                "prom-latency(otlp-endpoint=http://127.0.0.1:{COLLECTOR_PORT},push-interval-ms=100,http-server=false)"
+            ));
+    }
+}
@@ -0,0 +1,38 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gstreamer::{self as gst};
+
+    const PROM_PORT: u16 = 19102;
+
+    #[test]
+    fn given_pad_filter_when_pipeline_runs_then_only_selected_pads_measured() {
+        setup_test();
+
+        let pipeline = gst::parse::launch(
+            "fakesrc num-buffers=20 ! f.sink_0 fakesrc num-buffers=20 ! f.sink_1 funnel name=f ! fakesink",
+        )
+        .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        let metrics = common::metrics();
+        assert!(
+            metrics.contains("sink_pad=\"sink_0\""),
+            "Expected f.sink_0 to be measured in:\n{metrics}"
+        );
+        for excluded in ["sink_pad=\"sink_1\"", "element=\"fakesink0\""] {
+            assert!(
+                !metrics.contains(excluded),
+                "Expected no {excluded} series in:\n{metrics}"
+            );
+        }
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "prom-latency(port={PROM_PORT},http-server=false,pad-filter=\"f.sink_0\")"
+        ));
+    }
+}
@@ -0,0 +1,73 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gst::prelude::*;
+    use gstreamer::{self as gst};
+    use std::sync::atomic::Ordering;
+
+    const PROM_PORT: u16 = 19111;
+
+    #[test]
+    fn given_pipeline_latency_when_run_then_source_to_sink_latency_recorded() {
+        setup_test();
+
+        let pipeline = launch_pipeline(
+            "fakesrc num-buffers=20 ! identity name=e2e_id sleep-time=1000 ! fakesink name=e2e_sink",
+        );
+        let stamped = common::count_stamped_buffers(&pipeline.by_name("e2e_id").unwrap());
+        let stamped_at_sink = common::count_stamped_buffers(&pipeline.by_name("e2e_sink").unwrap());
+        common::run_to_eos(&pipeline);
+        let metrics = common::metrics();
+
+        assert_eq!(stamped.load(Ordering::Relaxed), 20);
+        // The stamp is removed once the latency into the sink is recorded
+        assert_eq!(stamped_at_sink.load(Ordering::Relaxed), 0);
+        let count = common::metric_value(
+            &metrics,
+            "gst_pipeline_latency_count_count{element=\"e2e_sink\"",
+        )
+        .expect("Expected an end-to-end latency count for e2e_sink");
+        assert_eq!(count, 20.0);
+        // Every buffer spent at least identity's 1ms sleep between source and sink
+        let sum = common::metric_value(
+            &metrics,
+            "gst_pipeline_latency_sum_count{element=\"e2e_sink\"",
+        )
+        .expect("Expected an end-to-end latency sum for e2e_sink");
+        assert!(sum >= 20.0 * 1_000_000.0, "Expected sum >= 20ms, got {sum}");
+        // Only pad pairs into sinks have an end-to-end latency
+        assert!(!metrics
+            .lines()
+            .any(|line| line.starts_with("gst_pipeline_latency_count_count{element=\"e2e_id\"")));
+    }
+
+    #[test]
+    fn given_no_selected_sink_when_run_then_source_buffers_not_stamped() {
+        setup_test();
+
+        let pipeline =
+            launch_pipeline("fakesrc num-buffers=20 ! identity ! fakesink name=unmeasured_sink");
+        let stamped = common::count_stamped_buffers(&pipeline.by_name("unmeasured_sink").unwrap());
+        common::run_to_eos(&pipeline);
+        let metrics = common::metrics();
+
+        // No sink of this pipeline records end-to-end latency, so its buffers are left alone
+        assert_eq!(stamped.load(Ordering::Relaxed), 0);
+        assert!(!metrics.contains("gst_pipeline_latency_count_count{element=\"unmeasured_sink\""));
+    }
+
+    fn launch_pipeline(description: &str) -> gst::Pipeline {
+        gst::parse::launch(description)
+            .expect("Failed to create pipeline from launch string")
+            .downcast::<gst::Pipeline>()
+            .expect("Failed to downcast to gst::Pipeline")
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "prom-latency(port={PROM_PORT},http-server=false,pipeline-latency=true,filter=\"!unmeasured_sink\")"
+        ));
+    }
+}
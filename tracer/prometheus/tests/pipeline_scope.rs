@@ -0,0 +1,41 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gstreamer::{self as gst};
+
+    const PROM_PORT: u16 = 19106;
+
+    #[test]
+    fn given_pipeline_scope_when_pipeline_runs_then_only_end_to_end_latency_measured() {
+        setup_test();
+
+        let pipeline = gst::parse::launch(
+            "fakesrc num-buffers=20 ! identity name=scoped ! fakesink name=scopedsink",
+        )
+        .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        let metrics = common::metrics();
+        assert!(
+            metrics.contains("gst_pipeline_latency_count_count{element=\"scopedsink\""),
+            "Expected the end-to-end latency into scopedsink in:\n{metrics}"
+        );
+        for excluded in [
+            "gst_element_latency_count_count{",
+            "gst_element_buffers_in_total{",
+        ] {
+            assert!(
+                !metrics.contains(excluded),
+                "Expected no {excluded} series in:\n{metrics}"
+            );
+        }
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "prom-latency(port={PROM_PORT},http-server=false,scope=pipeline)"
+        ));
+    }
+}
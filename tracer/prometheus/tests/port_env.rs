@@ -0,0 +1,26 @@
+#[cfg(test)]
+mod tests {
+    use gstprometheustracer::PromLatencyTracer;
+    use gstreamer::{self as gst};
+    use std::env;
+
+    #[test]
+    fn given_port_env_var_when_tracer_created_then_port_param_takes_precedence() {
+        env::remove_var("GST_TRACERS");
+        env::set_var("GST_PROMETHEUS_TRACER_PORT", "19104");
+        gst::init().expect("Failed to initialize GStreamer");
+
+        let from_env = PromLatencyTracer::builder().http_server(false).build();
+        assert_eq!(from_env.settings().server_port, 19104);
+
+        let from_param = PromLatencyTracer::builder()
+            .http_server(false)
+            .port(19105)
+            .build();
+        assert_eq!(from_param.settings().server_port, 19105);
+
+        env::set_var("GST_PROMETHEUS_TRACER_PORT", "not-a-port");
+        let invalid_env = PromLatencyTracer::builder().http_server(false).build();
+        assert_eq!(invalid_env.settings().server_port, 8080);
+    }
+}
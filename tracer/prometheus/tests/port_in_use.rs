@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use gst::prelude::*;
+    use gstprometheustracer::PromLatencyTracer;
+    use gstreamer::{self as gst};
+    use std::{
+        env,
+        net::{TcpListener, TcpStream},
+    };
+
+    const TAKEN_PORT: u16 = 19107;
+    const TAKEN_PORT_NO_RETRIES: u16 = 19109;
+
+    /// Runs a short pipeline to EOS, returning the warnings posted on its bus.
+    fn run_pipeline() -> Vec<String> {
+        let pipeline = gst::parse::launch("fakesrc num-buffers=10 ! fakesink")
+            .expect("Failed to create pipeline from launch string");
+        pipeline
+            .set_state(gst::State::Playing)
+            .expect("Unable to set the pipeline to Playing");
+        let mut warnings = Vec::new();
+        let bus = pipeline.bus().unwrap();
+        for msg in bus.iter_timed(gst::ClockTime::NONE) {
+            use gst::MessageView;
+            match msg.view() {
+                MessageView::Warning(warning) => warnings.push(warning.error().to_string()),
+                MessageView::Eos(..) => break,
+                MessageView::Error(err) => panic!("Unexpected error: {}", err.error()),
+                _ => (),
+            }
+        }
+        pipeline.set_state(gst::State::Null).unwrap();
+        warnings
+    }
+
+    #[test]
+    fn given_port_in_use_when_retries_set_then_next_port_served() {
+        env::remove_var("GST_TRACERS");
+        gst::init().expect("Failed to initialize GStreamer");
+        let _taken = TcpListener::bind(("0.0.0.0", TAKEN_PORT)).expect("Failed to take the port");
+
+        let tracer = PromLatencyTracer::builder()
+            .port(TAKEN_PORT)
+            .port_retries(1)
+            .build();
+        let warnings = run_pipeline();
+
+        assert_eq!(tracer.served_port(), Some(TAKEN_PORT + 1));
+        assert!(
+            TcpStream::connect(("127.0.0.1", TAKEN_PORT + 1)).is_ok(),
+            "Expected the metrics server on the next port"
+        );
+        assert!(
+            warnings.is_empty(),
+            "Expected no warnings, got {warnings:?}"
+        );
+    }
+
+    #[test]
+    fn given_port_in_use_when_no_retries_then_warning_posted() {
+        env::remove_var("GST_TRACERS");
+        gst::init().expect("Failed to initialize GStreamer");
+        let _taken =
+            TcpListener::bind(("0.0.0.0", TAKEN_PORT_NO_RETRIES)).expect("Failed to take the port");
+
+        let tracer = PromLatencyTracer::builder()
+            .port(TAKEN_PORT_NO_RETRIES)
+            .build();
+        let warnings = run_pipeline();
+
+        assert_eq!(tracer.served_port(), None);
+        assert!(
+            warnings
+                .iter()
+                .any(|warning| warning.contains(&TAKEN_PORT_NO_RETRIES.to_string())),
+            "Expected a warning about the port, got {warnings:?}"
+        );
+    }
+}
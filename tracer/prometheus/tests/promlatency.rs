@@ -1,10 +1,12 @@
+mod common;
+
 #[cfg(test)]
 mod tests {
+    use crate::common;
     use gst::prelude::*;
     use gstreamer::{self as gst};
     use std::{
-        env::{self, consts::ARCH},
-        path::Path,
+        sync::atomic::{AtomicU32, Ordering},
         thread,
         time::{Duration, Instant},
         vec,
@@ -30,43 +32,11 @@ mod tests {
             .set_state(gst::State::Playing)
             .expect("Unable to set the pipeline to Playing");
 
-        // Grab the bus to listen for EOS
-        let bus = pipeline.bus().unwrap();
-
-        // Wait for EOS message
-        for msg in bus.iter_timed(gst::ClockTime::NONE) {
-            use gst::MessageView;
-            match msg.view() {
-                MessageView::Eos(..) => break,
-                MessageView::Error(err) => {
-                    println!(
-                        "Error from {:?}: {} ({:?})",
-                        err.src().map(|s| s.path_string()),
-                        err.error(),
-                        err.debug()
-                    );
-                    break;
-                }
-                _ => (),
-            }
-        }
+        common::wait_for_eos(&pipeline);
         // Get the active tracer and then emit to get the metrics.
 
         #[cfg(feature = "v1_18")]
-        {
-            let binding = gst::active_tracers();
-            println!("Active tracers: {}", binding.len());
-            let tracer = binding
-                .iter()
-                .inspect(|t| {
-                    println!("Active tracer: {}", t.name());
-                })
-                .find(|t| t.name() == "promlatencytracer0")
-                .expect("Expected to find the `prom-latency` tracer");
-            let _metrics_from_signal = tracer
-                .emit_by_name::<Option<String>>("metrics", &[])
-                .expect("Expected to get metrics from signal");
-        }
+        let _metrics_from_signal = common::metrics();
 
         // Stop the pipeline
         pipeline.set_state(gst::State::Null).unwrap();
@@ -96,6 +66,7 @@ mod tests {
             "gst_element_latency_last_gauge",
             "gst_element_latency_sum_count",
             "gst_element_latency_count_count",
+            "gst_element_bytes_total",
         ];
         for metric in metric_asserts {
             assert!(
@@ -148,33 +119,7 @@ mod tests {
             .downcast::<gst::Pipeline>()
             .expect("Failed to downcast to gst::Pipeline");
 
-        let bus = pipeline.bus().unwrap();
-
-        // Set the pipeline to the Playing state
-        pipeline
-            .set_state(gst::State::Playing)
-            .expect("Unable to set the pipeline to Playing");
-
-        // Wait for EOS message
-        for msg in bus.iter_timed(gst::ClockTime::NONE) {
-            use gst::MessageView;
-            match msg.view() {
-                MessageView::Eos(..) => break,
-                MessageView::Error(err) => {
-                    println!(
-                        "Error from {:?}: {} ({:?})",
-                        err.src().map(|s| s.path_string()),
-                        err.error(),
-                        err.debug()
-                    );
-                    break;
-                }
-                _ => (),
-            }
-        }
-        // Stop the pipeline
-        pipeline.set_state(gst::State::Null).unwrap();
-        thread::sleep(Duration::from_millis(100));
+        common::run_to_eos(&pipeline);
 
         // Get the metrics by performing an http request to the Prometheus endpoint
         let prometheus_url = format!("http://localhost:{PROM_PORT}");
@@ -198,39 +143,50 @@ mod tests {
             );
         }
 
-        fn get_metric_value(metrics: &str, metric_name: &str) -> Option<f64> {
-            metrics
-                .lines()
-                .find(|line| line.contains(metric_name))
-                .and_then(|line| line.split_whitespace().nth(1))
-                .and_then(|value| value.parse::<f64>().ok())
-        }
+        // The factory of the measured element is exposed alongside its instance name
+        assert!(
+            metrics.contains("element=\"lm1\",element_factory=\"identity\""),
+            "Expected lm1 to be labelled with its `identity` factory"
+        );
 
         // Check that the latency is around 100 us
         let latency_value =
-            get_metric_value(&metrics, "gst_element_latency_last_gauge{element=\"lm1\"")
+            common::metric_value(&metrics, "gst_element_latency_last_gauge{element=\"lm1\"")
                 .expect("Expected to find latency metric for lm1");
         let latency_value_no_sleep =
-            get_metric_value(&metrics, "gst_element_latency_last_gauge{element=\"lm0\"")
+            common::metric_value(&metrics, "gst_element_latency_last_gauge{element=\"lm0\"")
                 .expect("Expected to find latency metric for lm0");
 
-        // TODO - lower this thresholds once we have fixed how we are measuring latency
-        let last_check_failed = ((latency_value - latency_value_no_sleep) - 1e7).abs() >= 5e8;
+        // Allow a few milliseconds of oversleeping and scheduling noise
+        let last_check_failed = ((latency_value - latency_value_no_sleep) - 1e7).abs() >= 5e6;
 
         assert!(
             !last_check_failed,
             "Latency is not within expected range, found: {latency_value:?}"
         );
 
+        // Spikes between scrapes are kept by the min/max gauges
+        let min_value =
+            common::metric_value(&metrics, "gst_element_latency_min_gauge{element=\"lm1\"")
+                .expect("Expected to find min latency metric for lm1");
+        let max_value =
+            common::metric_value(&metrics, "gst_element_latency_max_gauge{element=\"lm1\"")
+                .expect("Expected to find max latency metric for lm1");
+        assert!(
+            min_value <= latency_value && latency_value <= max_value,
+            "Expected min <= last <= max, found: {min_value} <= {latency_value} <= {max_value}"
+        );
+
         // Check that the sum is around 1000 us
-        let sum_value = get_metric_value(&metrics, "gst_element_latency_sum_count{element=\"lm1\"")
-            .expect("Expected to find sum metric for lm1");
+        let sum_value =
+            common::metric_value(&metrics, "gst_element_latency_sum_count{element=\"lm1\"")
+                .expect("Expected to find sum metric for lm1");
         let sum_value_no_sleep =
-            get_metric_value(&metrics, "gst_element_latency_sum_count{element=\"lm0\"")
+            common::metric_value(&metrics, "gst_element_latency_sum_count{element=\"lm0\"")
                 .expect("Expected to find sum metric for lm0");
 
-        // TODO - lower this thresholds once we have fixed how we are measuring latency
-        let sum_check_failed = ((sum_value - sum_value_no_sleep) - 1e9).abs() >= 5e11;
+        // 100 buffers, allowing for up to 2.5ms of noise on average per buffer
+        let sum_check_failed = ((sum_value - sum_value_no_sleep) - 1e9).abs() >= 2.5e8;
 
         assert!(
             !sum_check_failed,
@@ -306,25 +262,7 @@ mod tests {
             .set_state(gst::State::Playing)
             .expect("Unable to set the pipeline to Playing");
 
-        // Grab the bus to listen for EOS
-        let bus = pipeline.bus().unwrap();
-        // Wait for EOS message
-        for msg in bus.iter_timed(gst::ClockTime::NONE) {
-            use gst::MessageView;
-            match msg.view() {
-                MessageView::Eos(..) => break,
-                MessageView::Error(err) => {
-                    println!(
-                        "Error from {:?}: {} ({:?})",
-                        err.src().map(|s| s.path_string()),
-                        err.error(),
-                        err.debug()
-                    );
-                    break;
-                }
-                _ => (),
-            }
-        }
+        common::wait_for_eos(&pipeline);
         // Stop the pipeline
         pipeline.set_state(gst::State::Null).unwrap();
         thread::sleep(Duration::from_millis(100));
@@ -466,24 +404,7 @@ mod tests {
             .set_state(gst::State::Playing)
             .expect("Unable to set the pipeline to Playing");
 
-        // Wait for EOS or error
-        let bus = pipeline.bus().unwrap();
-        for msg in bus.iter_timed(gst::ClockTime::NONE) {
-            use gst::MessageView;
-            match msg.view() {
-                MessageView::Eos(..) => break,
-                MessageView::Error(err) => {
-                    println!(
-                        "Error from {:?}: {} ({:?})",
-                        err.src().map(|s| s.path_string()),
-                        err.error(),
-                        err.debug()
-                    );
-                    break;
-                }
-                _ => (),
-            }
-        }
+        common::wait_for_eos(&pipeline);
 
         pipeline.set_state(gst::State::Null).unwrap();
         thread::sleep(Duration::from_millis(100));
@@ -501,6 +422,487 @@ mod tests {
         assert!(metrics.contains("id3"));
     }
 
+    #[test]
+    fn given_element_dropping_buffers_when_run_then_buffers_in_exceed_buffers_passed_on() {
+        setup_test();
+
+        let pipeline = launch_pipeline(
+            "buffer_drops",
+            "fakesrc num-buffers=1000 ! identity name=dropper drop-probability=0.5 ! fakesink name=dropsink",
+        );
+        common::run_to_eos(&pipeline);
+        let metrics = scrape_metrics();
+
+        // Every buffer pushed into `dropper` completes its push, even the dropped ones.
+        let dropper_in =
+            common::metric_value(&metrics, "gst_element_buffers_in_total{element=\"dropper\"")
+                .expect("Expected buffers in metric for dropper");
+        let dropper_out = common::metric_value(
+            &metrics,
+            "gst_element_buffers_out_total{element=\"dropper\"",
+        )
+        .expect("Expected buffers out metric for dropper");
+        assert_eq!(dropper_in, 1000.0);
+        assert_eq!(dropper_in, dropper_out);
+
+        // Only the buffers `dropper` let through arrive in `dropsink`.
+        let dropsink_in = common::metric_value(
+            &metrics,
+            "gst_element_buffers_in_total{element=\"dropsink\"",
+        )
+        .expect("Expected buffers in metric for dropsink");
+        assert!(
+            dropper_in > dropsink_in,
+            "Expected dropper to pass on fewer buffers than it received, in: {dropper_in}, passed on: {dropsink_in}"
+        );
+
+        // Latency is only measured for buffers that were actually processed
+        let dropper_count = common::metric_value(
+            &metrics,
+            "gst_element_latency_count_count{element=\"dropper\"",
+        )
+        .expect("Expected latency count metric for dropper");
+        assert!(dropper_count <= dropper_in);
+    }
+
+    #[test]
+    fn given_flagged_buffers_when_run_then_buffer_flags_counted() {
+        setup_test();
+
+        let pipeline = launch_pipeline(
+            "buffer_flags",
+            "fakesrc num-buffers=10 ! identity name=marker ! identity name=flagged ! fakesink",
+        );
+        // Flags are set as buffers enter `marker`, so they are seen when it pushes into `flagged`
+        let marker_sink = pipeline
+            .by_name("marker")
+            .and_then(|e| e.static_pad("sink"))
+            .expect("Expected identity with a sink pad");
+        let pushed = AtomicU32::new(0);
+        marker_sink.add_probe(gst::PadProbeType::BUFFER, move |_, info| {
+            if let Some(gst::PadProbeData::Buffer(buffer)) = &mut info.data {
+                let n = pushed.fetch_add(1, Ordering::Relaxed);
+                let buffer = buffer.make_mut();
+                buffer.set_flags(gst::BufferFlags::GAP);
+                if n == 0 {
+                    buffer.set_flags(gst::BufferFlags::DISCONT);
+                } else {
+                    buffer.unset_flags(gst::BufferFlags::DISCONT);
+                }
+                if n < 3 {
+                    buffer.set_flags(gst::BufferFlags::CORRUPTED);
+                }
+            }
+            gst::PadProbeReturn::Ok
+        });
+        common::run_to_eos(&pipeline);
+        let metrics = scrape_metrics();
+
+        let flagged = |name: &str| {
+            common::metric_value(&metrics, &format!("{name}{{element=\"flagged\""))
+                .unwrap_or_else(|| panic!("Expected {name} for flagged"))
+        };
+        assert_eq!(flagged("gst_element_buffer_discont_total"), 1.0);
+        assert_eq!(flagged("gst_element_buffer_gap_total"), 10.0);
+        assert_eq!(flagged("gst_element_buffer_corrupted_total"), 3.0);
+    }
+
+    #[test]
+    fn given_pipeline_with_negotiated_caps_when_run_then_media_type_label_is_set() {
+        setup_test();
+
+        let pipeline = launch_pipeline(
+            "media_type",
+            "videotestsrc num-buffers=10 ! identity name=mt_id ! fakesink",
+        );
+        common::run_to_eos(&pipeline);
+        let metrics = scrape_metrics();
+
+        let mt_id_lines = metrics
+            .lines()
+            .filter(|line| line.starts_with("gst_element_latency_count_count{element=\"mt_id\""))
+            .collect::<Vec<_>>();
+        assert!(
+            mt_id_lines
+                .iter()
+                .any(|line| line.contains("media_type=\"video/x-raw\"")),
+            "Expected mt_id to be labelled with its media type, found: {mt_id_lines:?}"
+        );
+        // Caps are not negotiated at link time; the placeholder series must not linger.
+        assert!(
+            !mt_id_lines
+                .iter()
+                .any(|line| line.contains("media_type=\"unknown\"")),
+            "Expected the unknown media type series to be removed, found: {mt_id_lines:?}"
+        );
+    }
+
+    #[test]
+    fn given_caps_after_first_buffer_when_pushed_then_media_type_label_is_set() {
+        setup_test();
+
+        let pipeline = launch_pipeline(
+            "late_caps",
+            "appsrc name=late_src format=time ! identity name=late_id ! fakesink",
+        );
+        let src = pipeline.by_name("late_src").unwrap();
+        pipeline
+            .set_state(gst::State::Playing)
+            .expect("Unable to set the pipeline to Playing");
+        // The first buffer is pushed before the src pad has caps
+        let push = || {
+            src.emit_by_name::<gst::FlowReturn>(
+                "push-buffer",
+                &[&gst::Buffer::with_size(16).unwrap()],
+            )
+        };
+        assert_eq!(push(), gst::FlowReturn::Ok);
+        src.set_property("caps", gst::Caps::new_empty_simple("application/x-late"));
+        for _ in 0..4 {
+            assert_eq!(push(), gst::FlowReturn::Ok);
+        }
+        assert_eq!(
+            src.emit_by_name::<gst::FlowReturn>("end-of-stream", &[]),
+            gst::FlowReturn::Ok
+        );
+        common::run_to_eos(&pipeline);
+        let metrics = scrape_metrics();
+
+        let late_id_lines = metrics
+            .lines()
+            .filter(|line| line.starts_with("gst_element_latency_count_count{element=\"late_id\""))
+            .collect::<Vec<_>>();
+        assert!(
+            late_id_lines
+                .iter()
+                .any(|line| line.contains("media_type=\"application/x-late\"")),
+            "Expected late_id to be labelled once caps arrived, found: {late_id_lines:?}"
+        );
+        assert!(
+            !late_id_lines
+                .iter()
+                .any(|line| line.contains("media_type=\"unknown\"")),
+            "Expected the unknown media type series to be removed, found: {late_id_lines:?}"
+        );
+    }
+
+    #[test]
+    fn given_default_scope_when_run_then_source_buffers_not_stamped() {
+        setup_test();
+
+        let pipeline = launch_pipeline(
+            "unstamped",
+            "fakesrc num-buffers=20 ! identity ! fakesink name=unstamped_sink",
+        );
+        let stamped = common::count_stamped_buffers(&pipeline.by_name("unstamped_sink").unwrap());
+        common::run_to_eos(&pipeline);
+        let metrics = scrape_metrics();
+
+        // End-to-end latency is opt-in, as its stamp is a meta visible to the pipeline
+        assert_eq!(stamped.load(Ordering::Relaxed), 0);
+        assert!(!metrics.contains("gst_pipeline_latency_count_count{"));
+    }
+
+    #[test]
+    fn given_sink_element_when_run_then_render_latency_recorded() {
+        setup_test();
+
+        let pipeline = launch_pipeline(
+            "render",
+            "fakesrc num-buffers=5 ! identity name=render_id ! fakesink name=render_sink",
+        );
+        // Delays the push into the sink before the buffer reaches its sink pad
+        pipeline
+            .by_name("render_id")
+            .unwrap()
+            .static_pad("src")
+            .unwrap()
+            .add_probe(gst::PadProbeType::BUFFER, |_, _| {
+                thread::sleep(Duration::from_millis(5));
+                gst::PadProbeReturn::Ok
+            })
+            .unwrap();
+        common::run_to_eos(&pipeline);
+        let metrics = scrape_metrics();
+
+        let render = common::metric_value(
+            &metrics,
+            "gst_element_render_latency_last_gauge{element=\"render_sink\"",
+        )
+        .expect("Expected a render latency for render_sink");
+        let latency = common::metric_value(
+            &metrics,
+            "gst_element_latency_last_gauge{element=\"render_sink\"",
+        )
+        .expect("Expected a latency for render_sink");
+        assert!(
+            render > 0.0,
+            "Expected a positive render latency, got {render}"
+        );
+        // The pad pair's latency includes the probe, the render latency starts at the sink pad
+        assert!(
+            latency >= 5_000_000.0,
+            "Expected latency >= 5ms, got {latency}"
+        );
+        assert!(
+            render < 5_000_000.0,
+            "Expected render latency < 5ms, got {render}"
+        );
+        // Elements pushing downstream are not sinks and have no render latency
+        assert!(!metrics
+            .lines()
+            .any(|line| line
+                .starts_with("gst_element_render_latency_last_gauge{element=\"render_id\"")));
+    }
+
+    #[test]
+    fn given_pipeline_with_queue_when_scraped_then_queue_levels_reported() {
+        setup_test();
+
+        let pipeline = launch_pipeline(
+            "queue-level-pipeline",
+            "fakesrc num-buffers=100 ! queue name=level_queue ! fakesink",
+        );
+        common::run_to_eos(&pipeline);
+
+        // The queue is drained once the pipeline is done, so only its presence is checked
+        let metrics = scrape_metrics();
+        for family in [
+            "gst_queue_level_buffers",
+            "gst_queue_level_bytes",
+            "gst_queue_level_time",
+        ] {
+            assert_eq!(
+                common::metric_value(&metrics, &format!("{family}{{element=\"level_queue\"}}")),
+                Some(0.0),
+                "Expected {family} to be reported for level_queue"
+            );
+        }
+    }
+
+    #[test]
+    fn given_tee_with_slow_first_branch_when_run_then_slow_branch_not_attributed_to_tee() {
+        setup_test();
+
+        // The tee pushes into the slow branch first, then into the queue.
+        let pipeline = launch_pipeline(
+            "tee_attribution",
+            "fakesrc num-buffers=20 ! identity name=tee_up ! tee name=attr_tee \
+             attr_tee. ! identity name=tee_slow sleep-time=10000 ! fakesink \
+             attr_tee. ! queue ! identity name=tee_fast ! fakesink",
+        );
+        common::run_to_eos(&pipeline);
+        let metrics = scrape_metrics();
+
+        let tee_latency = common::metric_value(
+            &metrics,
+            "gst_element_latency_last_gauge{element=\"attr_tee\"",
+        )
+        .expect("Expected latency metric for attr_tee");
+        let slow_latency = common::metric_value(
+            &metrics,
+            "gst_element_latency_last_gauge{element=\"tee_slow\"",
+        )
+        .expect("Expected latency metric for tee_slow");
+        assert!(
+            (slow_latency - 1e7).abs() < 5e6,
+            "Expected tee_slow to report its own 10ms, found: {slow_latency}"
+        );
+        assert!(
+            tee_latency < 5e6,
+            "Expected the slow branch to be excluded from the tee's latency, found: {tee_latency}"
+        );
+    }
+
+    #[test]
+    fn given_live_source_when_run_then_interbuffer_interval_matches_framerate() {
+        setup_test();
+
+        let pipeline = launch_pipeline(
+            "interbuffer_interval",
+            "videotestsrc is-live=true num-buffers=5 ! video/x-raw,framerate=10/1 ! identity name=jitter_id ! fakesink",
+        );
+        common::run_to_eos(&pipeline);
+        let metrics = scrape_metrics();
+
+        // Buffers are pushed every 100ms, give or take scheduling.
+        let interval = common::metric_value(
+            &metrics,
+            "gst_element_interbuffer_interval_last_gauge{element=\"jitter_id\"",
+        )
+        .expect("Expected interbuffer interval metric for jitter_id");
+        assert!(
+            (50_000_000.0..200_000_000.0).contains(&interval),
+            "Expected an interval of around 100ms, got {interval}ns"
+        );
+        let min = common::metric_value(
+            &metrics,
+            "gst_element_interbuffer_interval_min_gauge{element=\"jitter_id\"",
+        )
+        .expect("Expected interbuffer interval min metric for jitter_id");
+        let max = common::metric_value(
+            &metrics,
+            "gst_element_interbuffer_interval_max_gauge{element=\"jitter_id\"",
+        )
+        .expect("Expected interbuffer interval max metric for jitter_id");
+        assert!(min <= interval && interval <= max);
+    }
+
+    #[test]
+    fn given_openmetrics_accept_header_when_scraped_then_openmetrics_served() {
+        setup_test();
+
+        let pipeline = launch_pipeline(
+            "openmetrics",
+            "fakesrc num-buffers=10 ! identity name=om_id ! fakesink",
+        );
+        common::run_to_eos(&pipeline);
+
+        let prometheus_url = format!("http://localhost:{PROM_PORT}");
+        let response = reqwest::blocking::Client::new()
+            .get(&prometheus_url)
+            .header(
+                "Accept",
+                "application/openmetrics-text;version=1.0.0,text/plain;version=0.0.4;q=0.5",
+            )
+            .send()
+            .expect("Failed to fetch metrics from Prometheus endpoint");
+        let content_type = response
+            .headers()
+            .get("Content-Type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let metrics = response.text().expect("Failed to read response text");
+
+        assert!(content_type.starts_with("application/openmetrics-text"));
+        assert!(metrics.ends_with("# EOF\n"));
+        // Counter families drop the `_total` suffix, samples keep it.
+        assert!(metrics.contains("# TYPE gst_element_buffers_in counter"));
+        assert!(metrics.contains("gst_element_buffers_in_total{element=\"om_id\""));
+
+        // Scrapers that do not ask for OpenMetrics keep getting the text format.
+        let metrics = scrape_metrics();
+        assert!(!metrics.contains("# EOF"));
+    }
+
+    #[test]
+    fn given_linked_pads_when_unlinked_then_pad_cache_removed() {
+        setup_test();
+
+        let src = gst::ElementFactory::make("fakesrc")
+            .name("cache_src")
+            .build()
+            .unwrap();
+        let sink = gst::ElementFactory::make("fakesink")
+            .name("cache_sink")
+            .build()
+            .unwrap();
+        let src_pad = src.static_pad("src").unwrap();
+        let sink_pad = sink.static_pad("sink").unwrap();
+        // Every tracer keys its caches by its own quark; this is that of the only tracer here
+        let pad_cache_quark = gst::glib::Quark::from_str("promlatency.pad_cache.0");
+
+        // The `pad-link-post` hook attaches the cache to the src pad
+        src_pad.link(&sink_pad).expect("Failed to link pads");
+        assert!(
+            unsafe { src_pad.qdata::<u8>(pad_cache_quark) }.is_some(),
+            "Expected the pad cache to be populated on link"
+        );
+
+        #[cfg(feature = "v1_18")]
+        {
+            let metrics = common::metrics();
+            assert!(
+                metrics.contains("element=\"cache_sink\""),
+                "Expected the pad pair's series to be created on link"
+            );
+            // Other tests link pads concurrently, so only a lower bound holds
+            assert!(
+                common::metric_value(&metrics, "gst_tracer_active_pad_pairs ").unwrap_or_default()
+                    >= 1.0,
+                "Expected the linked pad pair to be counted as active"
+            );
+        }
+
+        // Only unlinks reach the `pad-unlink-post` hook, so the elements' state changes keep it,
+        for element in [&src, &sink] {
+            element.set_state(gst::State::Ready).unwrap();
+            element.set_state(gst::State::Null).unwrap();
+        }
+        // as does an unlink that fails
+        let other_sink_pad = gst::ElementFactory::make("fakesink")
+            .build()
+            .unwrap()
+            .static_pad("sink")
+            .unwrap();
+        assert!(src_pad.unlink(&other_sink_pad).is_err());
+        assert!(
+            unsafe { src_pad.qdata::<u8>(pad_cache_quark) }.is_some(),
+            "Expected the pad cache to be kept until the pads are unlinked"
+        );
+
+        // and the hook removes it once they are
+        assert!(src_pad.unlink(&sink_pad).is_ok());
+        assert!(
+            unsafe { src_pad.qdata::<u8>(pad_cache_quark) }.is_none(),
+            "Expected the pad cache to be removed on unlink"
+        );
+        // along with the pad pair's series
+        #[cfg(feature = "v1_18")]
+        assert!(
+            !common::metrics().contains("element=\"cache_sink\""),
+            "Expected the pad pair's series to be removed on unlink"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "v1_18")]
+    fn given_ghost_pad_when_retargeted_then_pad_pair_follows_new_target() {
+        setup_test();
+
+        let src = gst::ElementFactory::make("fakesrc")
+            .name("retarget_src")
+            .build()
+            .unwrap();
+        let first = gst::ElementFactory::make("identity")
+            .name("retarget_first")
+            .build()
+            .unwrap();
+        let second = gst::ElementFactory::make("identity")
+            .name("retarget_second")
+            .build()
+            .unwrap();
+        let bin = gst::Bin::with_name("retarget_bin");
+        bin.add(&first).unwrap();
+        bin.add(&second).unwrap();
+        let g_sink = gst::GhostPad::builder(gstreamer::PadDirection::Sink)
+            .with_target(&first.static_pad("sink").unwrap())
+            .ok()
+            .expect("Failed to create GhostPad for sink")
+            .build();
+        bin.add_pad(&g_sink).unwrap();
+        src.static_pad("src").unwrap().link(&g_sink).unwrap();
+        assert!(
+            common::metrics().contains("element=\"retarget_first\""),
+            "Expected the pad pair to be labelled with the ghost pad's target"
+        );
+
+        // Like decodebin exposing a new stream, point the ghost pad at another element
+        g_sink
+            .set_target(Some(&second.static_pad("sink").unwrap()))
+            .expect("Failed to retarget the GhostPad");
+        let metrics = common::metrics();
+        assert!(
+            metrics.contains("element=\"retarget_second\""),
+            "Expected the pad pair to follow the new target"
+        );
+        assert!(
+            !metrics.contains("element=\"retarget_first\""),
+            "Expected the series of the old target to be removed"
+        );
+    }
+
     #[test]
     fn bench_prom_latency_through_pipeline() {
         setup_test();
@@ -531,28 +933,10 @@ mod tests {
             .set_state(gst::State::Playing)
             .expect("Unable to set the pipeline to Playing");
 
-        // Grab the bus to listen for EOS
-        let bus = pipeline.bus().unwrap();
-
         // Start wall-clock timer
         let start = Instant::now();
 
-        // Block until we see an EOS message
-        for msg in bus.iter_timed(gst::ClockTime::NONE) {
-            use gst::MessageView;
-            match msg.view() {
-                MessageView::Eos(..) => break,
-                MessageView::Error(err) => {
-                    panic!(
-                        "Error from {:?}: {} ({:?})",
-                        err.src().map(|s| s.path_string()),
-                        err.error(),
-                        err.debug()
-                    );
-                }
-                _ => (),
-            }
-        }
+        common::wait_for_eos(&pipeline);
 
         // Stop the pipeline
         pipeline.set_state(gst::State::Null).unwrap();
@@ -574,41 +958,26 @@ mod tests {
             .expect("Failed to downcast to gst::Pipeline")
     }
 
-    fn setup_test() {
-        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
-        env::set_var("GST_TRACERS", "prom-latency(port=9999)");
-        env::set_var("GST_DEBUG", "GST_TRACER:5,prom-latency:7");
-        let root_manifest_dir = manifest_dir.parent().unwrap().parent().unwrap();
-        let plugin_targets = [("debug", true), ("debug", false)];
-        let plugin_paths = plugin_targets.iter().map(|(profile, with_target)| {
-            let base = root_manifest_dir.join(format!("target/{}", profile));
-            if *with_target {
-                base.join(format!("{ARCH}-unknown-linux-gnu"))
-                    .to_str()
-                    .unwrap()
-                    .to_owned()
-            } else {
-                base.to_str().unwrap().to_owned()
-            }
-        });
-        let gst_plugin_path = plugin_paths.collect::<Vec<_>>().join(":");
-        env::set_var("GST_PLUGIN_PATH", gst_plugin_path);
+    fn launch_pipeline(name: &str, launch: &str) -> gst::Pipeline {
+        let pipeline_el =
+            gst::parse::launch(launch).expect("Failed to create pipeline from launch string");
+        pipeline_el.set_property("name", name);
 
-        // Initialize GStreamer
-        gst::init().expect("Failed to initialize GStreamer");
+        pipeline_el
+            .downcast::<gst::Pipeline>()
+            .expect("Failed to downcast to gst::Pipeline")
+    }
 
-        // Verify that our element is registered:
-        assert!(
-            gst::TracerFactory::factories()
-                .iter()
-                .any(|f| f.name() == "prom-latency"),
-            "Expected to find the `prom-latency` element after registration"
-        );
+    fn scrape_metrics() -> String {
+        let prometheus_url = format!("http://localhost:{PROM_PORT}");
+        let response = reqwest::blocking::get(&prometheus_url)
+            .expect("Failed to fetch metrics from Prometheus endpoint");
+        let metrics = response.text().expect("Failed to read response text");
+        println!("Metrics:\n{metrics}");
+        metrics
+    }
 
-        let binding = gst::active_tracers();
-        let tracer = binding
-            .iter()
-            .find(|t| t.name() == "promlatencytracer0")
-            .expect("Expected to find the `promlatencytracer0` tracer");
+    fn setup_test() {
+        common::setup_tracers(&format!("prom-latency(port={PROM_PORT})"));
     }
 }
@@ -0,0 +1,75 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gst::prelude::*;
+    use gstreamer::{self as gst};
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+        time::Duration,
+    };
+
+    const PROM_PORT: u16 = 19112;
+    // Nothing listens here, exports fail but still gather the metrics every push interval.
+    const COLLECTOR_PORT: u16 = 19113;
+
+    #[test]
+    fn given_otlp_pushes_between_scrapes_when_scraped_then_max_latency_kept() {
+        setup_test();
+
+        let pipeline = gst::parse::launch(
+            "fakesrc num-buffers=100 ! identity name=spiky ! fakesink name=spiky_sink",
+        )
+        .expect("Failed to create pipeline from launch string");
+        // Starts the min/max window the second scrape reads
+        scrape_metrics();
+
+        // A spike on the first push into the sink, then enough pushes for several exports to
+        // land after it
+        let first = AtomicBool::new(true);
+        pipeline
+            .downcast_ref::<gst::Bin>()
+            .unwrap()
+            .by_name("spiky")
+            .unwrap()
+            .static_pad("src")
+            .unwrap()
+            .add_probe(gst::PadProbeType::BUFFER, move |_, _| {
+                let delay = if first.swap(false, Ordering::Relaxed) {
+                    20
+                } else {
+                    1
+                };
+                thread::sleep(Duration::from_millis(delay));
+                gst::PadProbeReturn::Ok
+            })
+            .unwrap();
+        common::run_to_eos(&pipeline);
+
+        let metrics = scrape_metrics();
+        let max = common::metric_value(
+            &metrics,
+            "gst_element_latency_max_gauge{element=\"spiky_sink\"",
+        )
+        .expect("Expected a max latency for spiky_sink");
+        assert!(
+            max >= 20_000_000.0,
+            "Expected the 20ms spike to survive the exports, got {max}"
+        );
+    }
+
+    fn scrape_metrics() -> String {
+        reqwest::blocking::get(format!("http://localhost:{PROM_PORT}"))
+            .expect("Failed to fetch metrics from Prometheus endpoint")
+            .text()
+            .expect("Failed to read response text")
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "prom-latency(port={PROM_PORT},otlp-endpoint=http://127.0.0.1:{COLLECTOR_PORT},push-interval-ms=10)"
+        ));
+    }
+}
@@ -0,0 +1,52 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gst::prelude::*;
+    use gstreamer::{self as gst};
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+        time::Duration,
+    };
+
+    const PUSHGATEWAY_PORT: u16 = 19091;
+
+    #[test]
+    fn given_pushgateway_url_when_pipeline_runs_then_metrics_pushed_for_pipeline_job() {
+        // Stand in for the Pushgateway before the tracer starts pushing.
+        let listener = TcpListener::bind(("127.0.0.1", PUSHGATEWAY_PORT))
+            .expect("Failed to bind fake Pushgateway");
+        setup_test();
+
+        let pipeline = gst::parse::launch("fakesrc num-buffers=100 ! identity ! fakesink")
+            .expect("Failed to create pipeline from launch string");
+        pipeline.set_property("name", "push_job");
+        pipeline
+            .set_state(gst::State::Playing)
+            .expect("Unable to set the pipeline to Playing");
+
+        let (mut stream, _) = listener.accept().expect("Expected a push from the tracer");
+        let mut request_line = String::new();
+        BufReader::new(&stream)
+            .read_line(&mut request_line)
+            .expect("Failed to read push request");
+        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+
+        pipeline.set_state(gst::State::Null).unwrap();
+        thread::sleep(Duration::from_millis(100));
+
+        assert!(
+            request_line.starts_with("POST /metrics/job/push_job "),
+            "Unexpected push request: {request_line}"
+        );
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+                "prom-latency(pushgateway-url=http://127.0.0.1:{PUSHGATEWAY_PORT},push-interval-ms=100)"
+            ));
+    }
+}
@@ -0,0 +1,55 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gstprometheustracer::PromLatencyTracer;
+    use gstreamer::{self as gst};
+    use std::env;
+
+    const PROM_PORT: u16 = 19103;
+
+    #[test]
+    fn given_tracers_with_same_port_when_scraped_then_server_shared() {
+        env::remove_var("GST_TRACERS");
+        gst::init().expect("Failed to initialize GStreamer");
+
+        let _first = PromLatencyTracer::builder()
+            .port(PROM_PORT)
+            .filter("shared_a")
+            .build();
+        let _second = PromLatencyTracer::builder()
+            .port(PROM_PORT)
+            .filter("shared_b")
+            .build();
+
+        let pipeline = gst::parse::launch(
+            "fakesrc num-buffers=20 ! identity name=shared_a ! identity name=shared_b ! fakesink",
+        )
+        .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        let metrics = reqwest::blocking::get(format!("http://localhost:{PROM_PORT}"))
+            .expect("Failed to fetch metrics from Prometheus endpoint")
+            .text()
+            .expect("Failed to read response text");
+        // Series exist from link time on, so only counts show both tracers recorded pushes
+        for element in ["shared_a", "shared_b"] {
+            let prefix = format!("gst_element_latency_count_count{{element=\"{element}\"");
+            let count = common::metric_value(&metrics, &prefix);
+            assert_eq!(
+                count,
+                Some(20.0),
+                "Expected 20 latencies of {element} in:\n{metrics}"
+            );
+        }
+        assert_eq!(
+            metrics
+                .lines()
+                .filter(|line| line.starts_with("gst_tracer_build_info{"))
+                .count(),
+            1,
+            "Expected build info to be served once in:\n{metrics}"
+        );
+    }
+}
@@ -0,0 +1,37 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gstreamer::{self as gst};
+
+    const PROM_PORT: u16 = 19094;
+
+    #[cfg(feature = "v1_18")]
+    #[test]
+    fn given_state_changes_enabled_when_pipeline_starts_then_durations_recorded() {
+        setup_test();
+
+        let pipeline =
+            gst::parse::launch("fakesrc num-buffers=10 ! identity name=stately ! fakesink")
+                .expect("Failed to create pipeline from launch string");
+        common::run_to_eos(&pipeline);
+
+        let metrics = common::metrics();
+        for transition in ["NULL->READY", "READY->PAUSED", "PAUSED->PLAYING"] {
+            let series = format!(
+                "gst_element_state_change_duration_seconds_count{{element=\"stately\",transition=\"{transition}\"}} 1"
+            );
+            assert!(
+                metrics.contains(&series),
+                "Expected {series} in:\n{metrics}"
+            );
+        }
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "prom-latency(port={PROM_PORT},http-server=false,state-changes=true)"
+        ));
+    }
+}
@@ -0,0 +1,60 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use gst::prelude::*;
+    use gstreamer::{self as gst};
+    use std::{
+        net::{TcpStream, UdpSocket},
+        time::Duration,
+    };
+
+    const PROM_PORT: u16 = 19096;
+    const STATSD_PORT: u16 = 19095;
+
+    #[test]
+    fn given_statsd_sink_when_pipeline_runs_then_latency_timings_sent() {
+        let socket =
+            UdpSocket::bind(("127.0.0.1", STATSD_PORT)).expect("Failed to bind fake StatsD");
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        setup_test();
+
+        let pipeline =
+            gst::parse::launch("fakesrc num-buffers=100 ! identity name=statsd_id ! fakesink")
+                .expect("Failed to create pipeline from launch string");
+        pipeline
+            .set_state(gst::State::Playing)
+            .expect("Unable to set the pipeline to Playing");
+
+        let mut datagram = [0u8; 1024];
+        let timing = loop {
+            let len = socket
+                .recv(&mut datagram)
+                .expect("Expected a StatsD timing for statsd_id");
+            let timing = String::from_utf8_lossy(&datagram[..len]).into_owned();
+            if timing.contains("#element:statsd_id,") {
+                break timing;
+            }
+        };
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        assert!(
+            timing.starts_with("gst.element.latency:") && timing.contains("|ms|#"),
+            "Unexpected StatsD timing: {timing}"
+        );
+        assert!(timing.contains("element_factory:identity"));
+        assert!(
+            TcpStream::connect(("127.0.0.1", PROM_PORT)).is_err(),
+            "Expected no metrics server to be listening"
+        );
+    }
+
+    fn setup_test() {
+        common::setup_tracers(&format!(
+            "prom-latency(port={PROM_PORT},sink=statsd,statsd-address=\"127.0.0.1:{STATSD_PORT}\")"
+        ));
+    }
+}
@@ -39,22 +39,56 @@
  *
  * ### tracer-name
  *
- * The name of the tracer, which will appear in the Pyroscope UI.
+ * The name of the tracer, which will appear in the Pyroscope UI. With `application-name` set, it
+ * is sent as the `tracer` tag instead.
  *
  * Default: `gst.pyroscope`
  *
+ * ### application-name
+ *
+ * The Pyroscope application profiles are grouped under, so several pipelines can share one
+ * application while telling their profiles apart by `tracer` and other tags.
+ *
+ * Default: `tracer-name`
+ *
  * ### sample-rate
  *
  * The sampling rate in Hz (samples per second).
  *
  * Default: `100`
  *
+ * ### profile-type
+ *
+ * What to profile. Only `cpu` is supported: the pprof-rs backend samples on a `SIGPROF` CPU-time
+ * timer and has no wall-clock or allocation profiling. Other values are rejected with a warning.
+ *
+ * Default: `cpu`
+ *
  * ### stop-agent-on-dispose
  *
  * Whether to stop the Pyroscope agent when the tracer is disposed.
  *
  * Default: `true`
  *
+ * ### stop-timeout-ms
+ *
+ * How long dispose waits for the agent to stop before abandoning it in the background.
+ *
+ * Default: `5000`
+ *
+ * ### auth-token
+ *
+ * Token sent to authenticated Pyroscope servers, such as Pyroscope Cloud.
+ *
+ * Default: unset
+ *
+ * ### default-tags
+ *
+ * Whether to add the built-in `service`, `version`, `repo`, `os` and `arch` tags. When `false`,
+ * only `tags` and the `pipeline` tag are sent.
+ *
+ * Default: `true`
+ *
  * ### tags
  *
  * Additional tags to attach to the profiling data, comma-separated, in the form `key=value`.
@@ -62,6 +96,39 @@
  * Example: `env=dev,team=video`
  *
  * Default: empty
+ *
+ * ### filter
+ *
+ * Only pipelines whose name matches start the agent, with the same wildcard patterns as the
+ * `filter` of the other tracers, e.g. `filter="ingest*,!*-test"`. The agent profiles the whole
+ * process once started.
+ *
+ * Default: empty, every pipeline
+ *
+ * ### state-tags
+ *
+ * Whether to tag samples of streaming threads with the `state` of the pipeline, e.g. `playing`
+ * or `paused`, to split CPU profiles by state. Pyroscope tags are per thread, so each streaming
+ * thread re-tags itself on its first push after a state change, at the cost of an atomic load
+ * per push. With several pipelines, the state of the last one to change state is used.
+ *
+ * Default: `false`
+ *
+ * ### flush-on-sigterm
+ *
+ * Whether to stop the agent, uploading the profiles it still holds, when the process receives
+ * `SIGTERM`, waiting at most `stop-timeout-ms`. The signal is then handled as it would have been
+ * without the tracer, by the application's own handler or by terminating.
+ *
+ * Default: `false`
+ *
+ * ## Signals
+ *
+ * ### request-status
+ *
+ * Returns the resolved settings and whether the agent is running as a `GstStructure` string, e.g.
+ * `pyroscope-status, agent-running=(boolean)true, server-url=(string)http://localhost:4040, ...`.
+ * The auth token is only reported as `auth-token-set`. Safe to call at any time.
  */
 use glib::subclass::prelude::*;
 use gst::prelude::*;
@@ -69,10 +136,21 @@ use gst::subclass::prelude::*;
 use gstreamer as gst;
 
 mod imp {
-    use std::{str::FromStr, sync::LazyLock};
+    use std::{
+        cell::Cell,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicI32, Ordering},
+            mpsc, LazyLock, OnceLock,
+        },
+        thread,
+        time::Duration,
+    };
 
     use super::*;
 
+    use glib::translate::{FromGlib, IntoGlib};
+    use gsttracercommon::{element_filter::Filter, sigterm::on_sigterm};
     use pyroscope::{pyroscope::PyroscopeAgentRunning, PyroscopeAgent};
     use pyroscope_pprofrs::{pprof_backend, PprofConfig};
 
@@ -84,13 +162,52 @@ mod imp {
         )
     });
 
-    #[derive(Debug)]
+    /// Profiling modes of the pprof-rs backend.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    enum ProfileType {
+        /// On-CPU time, sampled with `SIGPROF`.
+        #[default]
+        Cpu,
+    }
+
     struct Settings {
         server_url: String,
         tracer_name: String,
+        application_name: Option<String>,
         sample_rate: u32,
+        profile_type: ProfileType,
         stop_agent_on_dispose: bool,
+        stop_timeout: Duration,
+        auth_token: Option<String>,
+        default_tags: bool,
         tags: Vec<(String, String)>,
+        filter: Filter,
+        state_tags: bool,
+        flush_on_sigterm: bool,
+    }
+
+    // Written by hand so the auth token never ends up in debug logs.
+    impl std::fmt::Debug for Settings {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Settings")
+                .field("server_url", &self.server_url)
+                .field("tracer_name", &self.tracer_name)
+                .field("application_name", &self.application_name)
+                .field("sample_rate", &self.sample_rate)
+                .field("profile_type", &self.profile_type)
+                .field("stop_agent_on_dispose", &self.stop_agent_on_dispose)
+                .field("stop_timeout", &self.stop_timeout)
+                .field(
+                    "auth_token",
+                    &self.auth_token.as_ref().map(|_| "<redacted>"),
+                )
+                .field("default_tags", &self.default_tags)
+                .field("tags", &self.tags)
+                .field("filter", &self.filter)
+                .field("state_tags", &self.state_tags)
+                .field("flush_on_sigterm", &self.flush_on_sigterm)
+                .finish()
+        }
     }
 
     impl Default for Settings {
@@ -98,14 +215,29 @@ mod imp {
             Self {
                 server_url: "http://localhost:4040".into(),
                 tracer_name: "gst.pyroscope".into(),
+                application_name: None,
                 sample_rate: 100,
+                profile_type: ProfileType::default(),
                 stop_agent_on_dispose: true,
+                stop_timeout: Duration::from_millis(5000),
+                auth_token: None,
+                default_tags: true,
                 tags: vec![],
+                filter: Filter::default(),
+                state_tags: false,
+                flush_on_sigterm: false,
             }
         }
     }
 
     impl Settings {
+        /// The Pyroscope application name, defaulting to `tracer-name`.
+        fn application_name(&self) -> &str {
+            self.application_name
+                .as_deref()
+                .unwrap_or(&self.tracer_name)
+        }
+
         fn update_from_params(&mut self, imp: &PyroscopeTracer, params: String) {
             let s = match gst::Structure::from_str(&format!("pyroscope,{params}")) {
                 Ok(s) => s,
@@ -120,12 +252,36 @@ mod imp {
             if let Ok(v) = s.get::<String>("tracer-name") {
                 self.tracer_name = v;
             }
+            if let Ok(v) = s.get::<String>("application-name") {
+                self.application_name = Some(v);
+            }
             if let Ok(v) = s.get::<i32>("sample-rate") {
                 self.sample_rate = v as u32;
             }
+            if let Ok(v) = s.get::<String>("profile-type") {
+                match v.as_str() {
+                    "cpu" => self.profile_type = ProfileType::Cpu,
+                    "wall" | "alloc" => gst::warning!(
+                        CAT,
+                        imp = imp,
+                        "profile-type {} is not supported by the pprof-rs backend, using cpu",
+                        v
+                    ),
+                    _ => gst::warning!(CAT, imp = imp, "unknown profile-type {}, expected cpu", v),
+                }
+            }
             if let Ok(v) = s.get::<bool>("stop-agent-on-dispose") {
                 self.stop_agent_on_dispose = v;
             }
+            if let Ok(v) = s.get::<i32>("stop-timeout-ms") {
+                self.stop_timeout = Duration::from_millis(v.max(0) as u64);
+            }
+            if let Ok(v) = s.get::<String>("auth-token") {
+                self.auth_token = Some(v);
+            }
+            if let Ok(v) = s.get::<bool>("default-tags") {
+                self.default_tags = v;
+            }
             if let Ok(v) = s.get::<String>("tags") {
                 let parsed_tags: Vec<(String, String)> = v
                     .split(',')
@@ -140,6 +296,15 @@ mod imp {
                     .collect();
                 self.tags = parsed_tags;
             }
+            if let Ok(v) = s.get::<String>("filter") {
+                self.filter = Filter::new(&v);
+            }
+            if let Ok(v) = s.get::<bool>("state-tags") {
+                self.state_tags = v;
+            }
+            if let Ok(v) = s.get::<bool>("flush-on-sigterm") {
+                self.flush_on_sigterm = v;
+            }
         }
     }
 
@@ -148,10 +313,28 @@ mod imp {
         agent: Option<PyroscopeAgent<PyroscopeAgentRunning>>,
     }
 
+    thread_local! {
+        /// Pipeline state this streaming thread is tagged with, `VoidPending` if none.
+        static THREAD_STATE_TAG: Cell<gst::State> = const { Cell::new(gst::State::VoidPending) };
+    }
+
     #[derive(Debug, Default)]
     pub struct PyroscopeTracer {
         state: std::sync::RwLock<State>,
         settings: std::sync::RwLock<Settings>,
+        /// Last state a selected pipeline changed to, as a `GstState`, with `state-tags=true`.
+        pipeline_state: AtomicI32,
+    }
+
+    /// `state` tag value of a pipeline state.
+    fn state_tag(state: gst::State) -> &'static str {
+        match state {
+            gst::State::Null => "null",
+            gst::State::Ready => "ready",
+            gst::State::Paused => "paused",
+            gst::State::Playing => "playing",
+            _ => "unknown",
+        }
     }
 
     impl PyroscopeTracer {
@@ -167,56 +350,159 @@ mod imp {
             let mut state_write = self.state.write().unwrap();
             if state_write.agent.is_none() {
                 gst::debug!(CAT, "Creating new Pyroscope agent");
-                state_write.agent =
-                    Some(self.create_pyroscope_agent(&self.settings.read().unwrap(), tags));
+                // Profiling is best-effort; keep tracing without it rather than failing the pipeline.
+                match self.create_pyroscope_agent(&self.settings.read().unwrap(), tags) {
+                    Ok(agent) => state_write.agent = Some(agent),
+                    Err(err) => {
+                        gst::warning!(CAT, imp = self, "Failed to start Pyroscope agent: {}", err)
+                    }
+                }
             }
         }
 
+        /// Stop the agent on a background thread, waiting at most `stop-timeout-ms` for it.
+        ///
+        /// Stopping can take minutes; past the timeout the thread is left to finish on its own
+        /// so tearing down the pipeline does not hang.
         fn remove_agent_if_present(&self) {
-            let mut agent_write = self.state.write().unwrap();
-            if let Some(agent) = agent_write.agent.take() {
-                gst::debug!(
+            let Some(agent) = self.state.write().unwrap().agent.take() else {
+                return;
+            };
+            let stop_timeout = self.settings.read().unwrap().stop_timeout;
+            gst::debug!(
+                CAT,
+                imp = self,
+                "Disposing PyroscopeTracer, stopping agent..."
+            );
+
+            let (done_tx, done_rx) = mpsc::channel();
+            let spawned = thread::Builder::new()
+                .name("pyroscope-stop".into())
+                .spawn(move || {
+                    let result = agent.stop().map(|agent_stopped| agent_stopped.shutdown());
+                    let _ = done_tx.send(result);
+                });
+            if let Err(err) = spawned {
+                gst::warning!(
                     CAT,
-                    "Disposing PyroscopeTracer, stopping agent... This can take several minutes..."
+                    imp = self,
+                    "Failed to spawn agent stop thread: {}",
+                    err
                 );
-                let agent_stopped = agent.stop().unwrap();
-                agent_stopped.shutdown();
-                gst::debug!(CAT, "Pyroscope agent stopped");
+                return;
+            }
+
+            match done_rx.recv_timeout(stop_timeout) {
+                Ok(Ok(())) => gst::debug!(CAT, imp = self, "Pyroscope agent stopped"),
+                Ok(Err(err)) => {
+                    gst::warning!(CAT, imp = self, "Failed to stop Pyroscope agent: {}", err)
+                }
+                Err(_) => gst::warning!(
+                    CAT,
+                    imp = self,
+                    "Pyroscope agent did not stop within {:?}, abandoning it",
+                    stop_timeout
+                ),
             }
         }
 
+        /// Moves the `state` tag of the current thread to the pipeline's current state, if it
+        /// changed since this thread was last tagged.
+        fn sync_thread_state_tag(&self) {
+            let state =
+                unsafe { gst::State::from_glib(self.pipeline_state.load(Ordering::Relaxed)) };
+            let tagged = THREAD_STATE_TAG.get();
+            if state == tagged {
+                return;
+            }
+            let state_read = self.state.read().unwrap();
+            let Some(agent) = &state_read.agent else {
+                return;
+            };
+            let (add_tag, remove_tag) = agent.tag_wrapper();
+            if tagged != gst::State::VoidPending {
+                let _ = remove_tag("state".into(), state_tag(tagged).into());
+            }
+            if let Err(err) = add_tag("state".into(), state_tag(state).into()) {
+                gst::debug!(CAT, imp = self, "Failed to tag thread with state: {}", err);
+            }
+            THREAD_STATE_TAG.set(state);
+        }
+
+        /// The resolved settings and whether the agent is running, for `request-status`.
+        fn status(&self) -> gst::Structure {
+            let agent_running = self.state.read().unwrap().agent.is_some();
+            let settings = self.settings.read().unwrap();
+            let profile_type = match settings.profile_type {
+                ProfileType::Cpu => "cpu",
+            };
+            let tags = settings
+                .tags
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>()
+                .join(",");
+            gst::Structure::builder("pyroscope-status")
+                .field("agent-running", agent_running)
+                .field("server-url", &settings.server_url)
+                .field("tracer-name", &settings.tracer_name)
+                .field("application-name", settings.application_name())
+                .field("sample-rate", settings.sample_rate)
+                .field("profile-type", profile_type)
+                .field("stop-agent-on-dispose", settings.stop_agent_on_dispose)
+                .field("stop-timeout-ms", settings.stop_timeout.as_millis() as u64)
+                .field("auth-token-set", settings.auth_token.is_some())
+                .field("default-tags", settings.default_tags)
+                .field("tags", tags)
+                .field("state-tags", settings.state_tags)
+                .field("flush-on-sigterm", settings.flush_on_sigterm)
+                .build()
+        }
+
         fn create_pyroscope_agent(
             &self,
             settings: &Settings,
             tags: Vec<(&str, &str)>,
-        ) -> PyroscopeAgent<PyroscopeAgentRunning> {
+        ) -> pyroscope::Result<PyroscopeAgent<PyroscopeAgentRunning>> {
             let url = settings.server_url.clone();
-            let tracer_name = settings.tracer_name.clone();
+            let application_name = settings.application_name().to_string();
             let sample_rate = settings.sample_rate;
+            let backend = match settings.profile_type {
+                ProfileType::Cpu => pprof_backend(PprofConfig::new().sample_rate(sample_rate)),
+            };
 
             let settings_tags = settings.tags.clone();
 
             gst::debug!(CAT, "Creating Pyroscope agent with URL: {}", url);
 
-            let all_tags: Vec<(&str, &str)> = vec![
-                ("service", env!("CARGO_PKG_NAME")),
-                ("version", env!("CARGO_PKG_VERSION")),
-                ("repo", env!("CARGO_PKG_REPOSITORY")),
-                ("os", std::env::consts::OS),
-                ("arch", std::env::consts::ARCH),
-            ]
-            .into_iter()
-            .chain(settings_tags.iter().map(|(k, v)| (k.as_str(), v.as_str())))
-            .chain(tags)
-            .collect();
-
-            PyroscopeAgent::builder(url, tracer_name)
-                .tags(all_tags)
-                .backend(pprof_backend(PprofConfig::new().sample_rate(sample_rate)))
-                .build()
-                .unwrap()
-                .start()
-                .unwrap()
+            let default_tags = if settings.default_tags {
+                vec![
+                    ("service", env!("CARGO_PKG_NAME")),
+                    ("version", env!("CARGO_PKG_VERSION")),
+                    ("repo", env!("CARGO_PKG_REPOSITORY")),
+                    ("os", std::env::consts::OS),
+                    ("arch", std::env::consts::ARCH),
+                ]
+            } else {
+                vec![]
+            };
+            // The tracer name is the application name unless one is set separately
+            let tracer_tag = settings
+                .application_name
+                .as_ref()
+                .map(|_| ("tracer", settings.tracer_name.as_str()));
+            let all_tags: Vec<(&str, &str)> = default_tags
+                .into_iter()
+                .chain(tracer_tag)
+                .chain(settings_tags.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+                .chain(tags)
+                .collect();
+
+            let mut builder = PyroscopeAgent::builder(url, application_name);
+            if let Some(auth_token) = &settings.auth_token {
+                builder = builder.auth_token(auth_token.clone());
+            }
+            builder.tags(all_tags).backend(backend).build()?.start()
         }
     }
 
@@ -233,6 +519,20 @@ mod imp {
     }
 
     impl ObjectImpl for PyroscopeTracer {
+        fn signals() -> &'static [glib::subclass::Signal] {
+            static SIGNALS: OnceLock<Vec<glib::subclass::Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![glib::subclass::Signal::builder("request-status")
+                    .flags(glib::SignalFlags::ACTION)
+                    .return_type::<String>()
+                    .class_handler(|_, args| {
+                        let tracer = args[0].get::<super::PyroscopeTracer>().expect("signal arg");
+                        Some(tracer.imp().status().to_string().to_value())
+                    })
+                    .build()]
+            })
+        }
+
         /// Called whenever the plugin itself is loaded; including during gst-inspect-1.0
         /// and other utility commands; avoid starting collectors or doing other heavy work here.
         fn constructed(&self) {
@@ -245,6 +545,19 @@ mod imp {
             }
 
             self.register_hook(TracerHook::BinAddPost);
+            if self.settings.read().unwrap().state_tags {
+                self.register_hook(TracerHook::ElementChangeStatePost);
+                self.register_hook(TracerHook::PadPushPre);
+            }
+            if self.settings.read().unwrap().flush_on_sigterm {
+                // Stopping the agent sends the profiles it has not uploaded yet
+                let tracer = self.obj().downgrade();
+                on_sigterm(move || {
+                    if let Some(tracer) = tracer.upgrade() {
+                        tracer.imp().remove_agent_if_present();
+                    }
+                });
+            }
         }
 
         /// Called when the tracer is disposed, typically when the pipeline is stopped or the plugin is unloaded.
@@ -274,10 +587,40 @@ mod imp {
             success: bool,
         ) {
             // If the agent is not running & this is the pipeline bin, start it up.
-            if success && bin.downcast_ref::<gst::Pipeline>().is_some() {
+            if success
+                && bin.downcast_ref::<gst::Pipeline>().is_some()
+                && self.settings.read().unwrap().filter.matches(&bin.name())
+            {
                 self.create_first_agent(vec![("pipeline", bin.name().as_str())]);
             }
         }
+
+        /// Records the state of selected pipelines for `state-tags`; other elements are skipped.
+        fn element_change_state_post(
+            &self,
+            _ts: u64,
+            element: &gst::Element,
+            change: gst::StateChange,
+            result: Result<gst::StateChangeSuccess, gst::StateChangeError>,
+        ) {
+            if result.is_err()
+                || element.downcast_ref::<gst::Pipeline>().is_none()
+                || !self
+                    .settings
+                    .read()
+                    .unwrap()
+                    .filter
+                    .matches(&element.name())
+            {
+                return;
+            }
+            self.pipeline_state
+                .store(change.next().into_glib(), Ordering::Relaxed);
+        }
+
+        fn pad_push_pre(&self, _ts: u64, _pad: &gst::Pad, _buffer: &gst::Buffer) {
+            self.sync_thread_state_tag();
+        }
     }
 }
 